@@ -0,0 +1,320 @@
+//! Generate a `git-testament` from a build script
+//!
+//! Some build environments cannot run `git` while procedural macros are
+//! being expanded, for example sandboxed or distributed builds.  This crate
+//! allows the testament to be generated by a build script instead, and then
+//! included into the crate.
+//!
+//! In `build.rs`:
+//!
+//! ```no_run
+//! fn main() {
+//!     let out_dir = std::env::var_os("OUT_DIR").unwrap();
+//!     git_testament_build::emit(out_dir).expect("Unable to generate testament");
+//! }
+//! ```
+//!
+//! And then in the crate, which must also depend on `git-testament`:
+//!
+//! ```ignore
+//! use git_testament::{render_testament, GitTestament};
+//!
+//! const TESTAMENT: GitTestament<'static> =
+//!     include!(concat!(env!("OUT_DIR"), "/testament.rs"));
+//!
+//! fn main() {
+//!     println!("My version information: {}", render_testament!(TESTAMENT));
+//! }
+//! ```
+//!
+//! The build script asks cargo to re-run it when commits are made, the
+//! branch is switched, or changes are staged.  Edits to files which are
+//! otherwise clean are only noticed once something else causes the build
+//! script to run again.
+//!
+//! The testament generated here always records the target and toolchain in
+//! its `build_info`.  A crate which uses the `build_info` option of the
+//! macros instead can call [`forward_build_info`] from its build script.
+
+// Clippy thinks the build script's fn main() is needless, but it is needed
+// to show what goes in `build.rs`
+#![allow(clippy::needless_doctest_main)]
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use git_testament_core::StatusFlag::*;
+use git_testament_core::{
+    metadata_paths, parse_source_date_epoch, path_from_bytes, today, AcquireOptions, BuildInfo,
+    Error, GitInformation, TestamentError, Vcs, BUILD_INFO_ENV_VARS, DISCOVERY_ENV_VARS,
+    OVERRIDE_ENV_VARS,
+};
+
+use proc_macro2::TokenStream;
+use quote::quote;
+
+/// The name of the file written into the output directory by [`emit`]
+pub const FILE_NAME: &str = "testament.rs";
+
+/// The same default as `git_testament!`
+const DEFAULT_SHORT_HASH_LENGTH: usize = 9;
+
+/// Generate a testament for the crate being built and write it to
+/// `testament.rs` in `out_dir`, returning the path written.
+///
+/// The file contains a single expression of type
+/// `git_testament::GitTestament<'static>`, suitable for use with `include!()`.
+/// This also prints the `cargo:rerun-if-changed` and
+/// `cargo:rerun-if-env-changed` lines needed to keep the testament up to date,
+/// so it must only be called from a build script.
+pub fn emit<P>(out_dir: P) -> Result<PathBuf, Error>
+where
+    P: AsRef<Path>,
+{
+    let manifest_dir = env::var_os("CARGO_MANIFEST_DIR")
+        .ok_or(TestamentError::MissingEnv("CARGO_MANIFEST_DIR"))?;
+    let manifest_dir = Path::new(&manifest_dir);
+
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    for var in DISCOVERY_ENV_VARS.iter().chain(OVERRIDE_ENV_VARS) {
+        println!("cargo:rerun-if-env-changed={var}");
+    }
+    let gitinfo = GitInformation::with_overrides(
+        GitInformation::acquire(manifest_dir, &AcquireOptions::default()),
+        manifest_dir,
+        |var| env::var(var).ok(),
+    );
+    // A package from `cargo package` has no repository metadata to watch
+    if let Some(gitinfo) = gitinfo
+        .as_ref()
+        .ok()
+        .filter(|gi| gi.cargo_vcs_info.is_none() && gi.vcs != Vcs::Environment)
+    {
+        for warning in &gitinfo.warnings {
+            println!("cargo:warning=The testament may be incomplete: {warning}");
+        }
+        for path in metadata_paths(manifest_dir)? {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+        // So that reverting a modification is noticed
+        for entry in &gitinfo.status {
+            println!(
+                "cargo:rerun-if-changed={}",
+                gitinfo
+                    .toplevel
+                    .join(path_from_bytes(&entry.path))
+                    .display()
+            );
+        }
+    }
+
+    let path = out_dir.as_ref().join(FILE_NAME);
+    fs::write(&path, generate(gitinfo.ok().as_ref()).to_string())?;
+    Ok(path)
+}
+
+/// Pass the target, profile and optimisation level, which cargo only gives
+/// to build scripts, on to the compiler, so that the `build_info` option of
+/// the `git_testament!` macros can record them.
+///
+/// This prints `cargo:rustc-env` lines, so it must only be called from a
+/// build script.
+pub fn forward_build_info() {
+    for var in BUILD_INFO_ENV_VARS {
+        if let Ok(value) = env::var(var) {
+            println!("cargo:rustc-env={var}={value}");
+        }
+    }
+}
+
+fn generate(gitinfo: Option<&GitInformation>) -> TokenStream {
+    let pkgver = env::var("CARGO_PKG_VERSION").unwrap_or_else(|_| "?.?.?".to_owned());
+    let (now, build_date_source) = match env::var("SOURCE_DATE_EPOCH") {
+        Ok(sde) => match parse_source_date_epoch(&sde) {
+            Ok(sde) => (sde.date, quote! { Environment }),
+            Err(e) => {
+                println!("cargo:warning=Ignoring SOURCE_DATE_EPOCH {sde:?}, using today: {e}");
+                (today(), quote! { Build })
+            }
+        },
+        Err(_) => (today(), quote! { Build }),
+    };
+
+    let build_info = BuildInfo::acquire();
+    let [target, profile, opt_level, rustc] = [
+        &build_info.target,
+        &build_info.profile,
+        &build_info.opt_level,
+        &build_info.rustc,
+    ]
+    .map(|value| optional(value.as_deref()));
+    let build_info = quote! {
+        ::core::option::Option::Some(::git_testament::BuildInfo {
+            target: #target,
+            profile: #profile,
+            opt_level: #opt_level,
+            rustc: #rustc,
+        })
+    };
+
+    let gitinfo = match gitinfo {
+        Some(gitinfo) => gitinfo,
+        None => {
+            return quote! {
+                ::git_testament::GitTestament {
+                    commit: ::git_testament::CommitKind::NoRepository(#pkgver, #now),
+                    build_info: #build_info,
+                    ..::git_testament::__testament_base!(commit = #build_date_source, build_info = Environment)
+                }
+            }
+        }
+    };
+
+    let branch_name = optional(gitinfo.branch.as_deref());
+    let remote_url = optional(gitinfo.remote_url.as_deref());
+    let source = match gitinfo.vcs {
+        Vcs::Environment => quote! { ::git_testament::TestamentSource::Environment },
+        _ => quote! { ::git_testament::TestamentSource::Git },
+    };
+    let overridden = !gitinfo.overridden.is_empty();
+    // Where each field came from, given which of them were overridden
+    let source_of = |vars: &[&str]| {
+        if gitinfo.overridden.iter().any(|var| vars.contains(var)) {
+            quote! { Environment }
+        } else {
+            quote! { Git }
+        }
+    };
+    let commit_source = source_of(&[
+        "GIT_TESTAMENT_COMMIT",
+        "GIT_TESTAMENT_DATE",
+        "GIT_TESTAMENT_TAG",
+        "GIT_TESTAMENT_DISTANCE",
+    ]);
+    let branch_source = source_of(&["GIT_TESTAMENT_BRANCH"]);
+    let modifications_source = source_of(&["GIT_TESTAMENT_DIRTY"]);
+
+    if let Some(vcs_info) = &gitinfo.cargo_vcs_info {
+        let id = &vcs_info.sha1;
+        let modifications_collected = gitinfo.status_collected;
+        let source = match gitinfo.vcs {
+            Vcs::RevisionFile => quote! { ::git_testament::TestamentSource::RevisionFile },
+            _ => quote! { ::git_testament::TestamentSource::CargoVcsInfo },
+        };
+        return quote! {
+            ::git_testament::GitTestament {
+                commit: ::git_testament::CommitKind::FromTarball(#id, #pkgver, #now),
+                source: #source,
+                build_info: #build_info,
+                modifications_collected: #modifications_collected,
+                ..::git_testament::__testament_base!(commit = Git, build_info = Environment)
+            }
+        };
+    }
+
+    let commitinfo = match &gitinfo.commitinfo {
+        Some(commitinfo) => commitinfo,
+        None => {
+            return quote! {
+                ::git_testament::GitTestament {
+                    commit: ::git_testament::CommitKind::NoCommit(#pkgver, #now),
+                    branch_name: #branch_name,
+                    remote_url: #remote_url,
+                    source: #source,
+                    build_info: #build_info,
+                    overridden: #overridden,
+                    ..::git_testament::__testament_base!(
+                        commit = #build_date_source,
+                        branch_name = #branch_source,
+                        build_info = Environment,
+                    )
+                }
+            }
+        }
+    };
+
+    let (id, date) = (&commitinfo.id, &commitinfo.date);
+    let commit = if !commitinfo.tag.is_empty() {
+        let (tag, distance) = (&commitinfo.tag, commitinfo.distance);
+        quote! { ::git_testament::CommitKind::FromTag(#tag, #id, #date, #distance) }
+    } else if gitinfo.shallow {
+        quote! { ::git_testament::CommitKind::Shallow(#pkgver, #id, #date) }
+    } else {
+        quote! { ::git_testament::CommitKind::NoTags(#id, #date) }
+    };
+
+    let modifications = gitinfo.status.iter().map(|status| {
+        let path = status.path.as_slice();
+        match status.status {
+            Untracked => quote! { ::git_testament::GitModification::Untracked(&[#(#path),*]) },
+            Added => quote! { ::git_testament::GitModification::Added(&[#(#path),*]) },
+            Modified => quote! { ::git_testament::GitModification::Modified(&[#(#path),*]) },
+            Deleted => quote! { ::git_testament::GitModification::Removed(&[#(#path),*]) },
+            Renamed => {
+                let original = status.original_path.as_deref().unwrap_or_default();
+                quote! {
+                    ::git_testament::GitModification::Renamed(&[#(#original),*], &[#(#path),*])
+                }
+            }
+            Copied => {
+                let original = status.original_path.as_deref().unwrap_or_default();
+                quote! {
+                    ::git_testament::GitModification::Copied(&[#(#original),*], &[#(#path),*])
+                }
+            }
+        }
+    });
+    let describe_hash_length = commitinfo.abbrev;
+    let commit_timestamp = &commitinfo.timestamp;
+    let describe = optional(commitinfo.describe.as_deref());
+    let commit_count = commitinfo.commit_count;
+    let is_merge = commitinfo.merge;
+    let shallow = gitinfo.shallow;
+    let worktree = gitinfo.worktree;
+    let bare = gitinfo.bare;
+    let detached = gitinfo.detached;
+    let worktree_digest = optional(gitinfo.worktree_digest.as_deref());
+    let lfs_incomplete = gitinfo.lfs_incomplete;
+    let modifications_collected = gitinfo.status_collected;
+
+    quote! {
+        ::git_testament::GitTestament {
+            commit: #commit,
+            modifications: &[#(#modifications),*],
+            branch_name: #branch_name,
+            remote_url: #remote_url,
+            source: #source,
+            short_hash_length: #DEFAULT_SHORT_HASH_LENGTH,
+            describe_hash_length: #describe_hash_length,
+            shallow: #shallow,
+            worktree: #worktree,
+            bare: #bare,
+            detached: #detached,
+            commit_timestamp: ::core::option::Option::Some(#commit_timestamp),
+            describe: #describe,
+            commit_count: ::core::option::Option::Some(#commit_count),
+            is_merge: #is_merge,
+            worktree_digest: #worktree_digest,
+            lfs_incomplete: #lfs_incomplete,
+            modifications_collected: #modifications_collected,
+            overridden: #overridden,
+            build_info: #build_info,
+            ..::git_testament::__testament_base!(
+                commit = #commit_source,
+                modifications = #modifications_source,
+                branch_name = #branch_source,
+                shallow = Git,
+                worktree = Git,
+                build_info = Environment,
+            )
+        }
+    }
+}
+
+fn optional(value: Option<&str>) -> TokenStream {
+    match value {
+        Some(value) => quote! { ::core::option::Option::Some(#value) },
+        None => quote! { ::core::option::Option::None },
+    }
+}
@@ -0,0 +1,121 @@
+//! The error type for acquiring repository information
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// The reasons why repository information could not be acquired.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TestamentError {
+    /// A version control tool could not be run at all, usually because it is
+    /// not installed
+    ToolUnavailable { tool: String, source: io::Error },
+    /// There is no repository at or above the given directory
+    NoRepository(PathBuf),
+    /// A command ran but failed, with its error output
+    CommandFailed { command: String, stderr: String },
+    /// The output of a command, or the contents of a file, could not be
+    /// understood
+    UnexpectedOutput(String),
+    /// An environment variable which is needed was not set
+    MissingEnv(&'static str),
+    /// Any other I/O error, such as failing to write a file
+    Io(io::Error),
+    /// No sources of repository information were configured
+    NoSources,
+    /// Every source was tried and failed, with the name of each source and
+    /// the reason it failed
+    AllSourcesFailed(Vec<(&'static str, TestamentError)>),
+}
+
+impl TestamentError {
+    /// Whether this error only means that there was no repository to be
+    /// found, rather than that something went wrong with one.
+    ///
+    /// A source whose tool is not installed is taken as not having found a
+    /// repository, so long as some other source looked and found nothing.
+    pub fn is_no_repository(&self) -> bool {
+        match self {
+            TestamentError::NoRepository(_) => true,
+            TestamentError::AllSourcesFailed(errors) => {
+                errors.iter().any(|(_, e)| e.is_no_repository())
+                    && errors.iter().all(|(_, e)| {
+                        e.is_no_repository() || matches!(e, TestamentError::ToolUnavailable { .. })
+                    })
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TestamentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TestamentError::ToolUnavailable { tool, source } => {
+                write!(f, "unable to run {tool}: {source}")
+            }
+            TestamentError::NoRepository(path) => {
+                write!(f, "no repository found at {}", path.display())
+            }
+            TestamentError::CommandFailed { command, stderr } => {
+                write!(f, "{command} failed: {}", stderr.trim_end())
+            }
+            TestamentError::UnexpectedOutput(message) => f.write_str(message),
+            TestamentError::MissingEnv(var) => write!(f, "{var} env variable not set"),
+            TestamentError::Io(e) => e.fmt(f),
+            TestamentError::NoSources => {
+                f.write_str("no sources of repository information were configured")
+            }
+            TestamentError::AllSourcesFailed(errors) => {
+                for (i, (source, e)) in errors.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("; ")?;
+                    }
+                    write!(f, "{source}: {e}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for TestamentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TestamentError::ToolUnavailable { source, .. } => Some(source),
+            TestamentError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TestamentError {
+    fn from(e: io::Error) -> Self {
+        TestamentError::Io(e)
+    }
+}
+
+impl From<std::string::FromUtf8Error> for TestamentError {
+    fn from(e: std::string::FromUtf8Error) -> Self {
+        TestamentError::UnexpectedOutput(format!("output was not UTF-8: {e}"))
+    }
+}
+
+impl From<std::num::ParseIntError> for TestamentError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        TestamentError::UnexpectedOutput(format!("expected a number: {e}"))
+    }
+}
+
+impl From<String> for TestamentError {
+    fn from(message: String) -> Self {
+        TestamentError::UnexpectedOutput(message)
+    }
+}
+
+impl From<&str> for TestamentError {
+    fn from(message: &str) -> Self {
+        TestamentError::UnexpectedOutput(message.to_owned())
+    }
+}
@@ -0,0 +1,255 @@
+//! Acquiring repository information from Mercurial
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::warn;
+
+use crate::StatusFlag::*;
+use crate::{
+    beyond_ceiling, format_commit_date, format_commit_timestamp, glob_matches, note, run_command,
+    strip_credentials, text_output, AcquireOptions, CommitInfo, Error, GitInformation, StatusEntry,
+    TestamentError, UntrackedFiles, Vcs,
+};
+
+/// The length of the abbreviated hashes Mercurial shows with `{node|short}`
+const SHORT_NODE_LENGTH: usize = 12;
+
+fn run_hg(dir: &Path, args: &[&str]) -> Result<String, Error> {
+    // HGPLAIN disables anything in the user's configuration which might
+    // change the output, such as aliases or localisation
+    let output = run_command(
+        Command::new("hg")
+            .args(args)
+            .env("HGPLAIN", "1")
+            .current_dir(dir),
+    )?;
+    text_output(output)
+}
+
+/// Turn a glob into a regular expression for Mercurial's `re:` patterns, with
+/// `*` matching across directories as it does for git.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c if c.is_ascii_alphanumeric() || !c.is_ascii() => regex.push(c),
+            // Escaping by code also keeps the template string literal valid
+            c => regex.push_str(&format!("\\x{:02x}", c as u32)),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// The latest tag matching `pattern`, if any, which is an ancestor of the
+/// working directory's parent, and the distance to it
+fn latest_tag(root: &Path, pattern: Option<&str>) -> Result<Option<(String, usize)>, Error> {
+    let tags = match pattern {
+        Some(pattern) => format!("latesttag(r\"re:{}\")", glob_to_regex(pattern)),
+        None => "latesttag".to_owned(),
+    };
+    let template = format!("{{{tags} % '{{tag}}\\t{{distance}}\\n'}}");
+    let output = run_hg(root, &["log", "-r", ".", "-T", &template])?;
+    let (tag, distance) = output
+        .lines()
+        .next()
+        .and_then(|line| line.split_once('\t'))
+        .ok_or("Unexpected output from hg log")?;
+    // Mercurial reports `null` if there are no tags at all
+    if tag == "null" {
+        return Ok(None);
+    }
+    Ok(Some((tag.to_owned(), distance.parse()?)))
+}
+
+fn status(root: &Path, options: &AcquireOptions) -> Result<Vec<StatusEntry>, Error> {
+    let mut args = vec!["status", "--modified", "--added", "--removed", "--deleted"];
+    if options.untracked != UntrackedFiles::No {
+        args.push("--unknown");
+    }
+    let output = run_hg(root, &args)?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let (code, path) = line.split_once(' ')?;
+            let status = match code {
+                "M" => Modified,
+                "A" => Added,
+                "R" | "!" => Deleted,
+                "?" => Untracked,
+                _ => return None,
+            };
+            Some(StatusEntry {
+                path: path.as_bytes().to_vec(),
+                status,
+                original_path: None,
+            })
+        })
+        .filter(|entry| {
+            !options
+                .ignore
+                .iter()
+                .any(|pattern| glob_matches(pattern, &String::from_utf8_lossy(&entry.path)))
+        })
+        .collect())
+}
+
+pub(crate) fn acquire(start: &Path, options: &AcquireOptions) -> Result<GitInformation, Error> {
+    let mut warnings = Vec::new();
+    let root = run_hg(start, &["root"]).map_err(|e| match e {
+        TestamentError::CommandFailed { ref stderr, .. }
+            if stderr.contains("no repository found") =>
+        {
+            TestamentError::NoRepository(start.to_owned())
+        }
+        e => e,
+    })?;
+    let root = PathBuf::from(root.trim_end());
+    if beyond_ceiling(start, &root) {
+        return Err(TestamentError::NoRepository(start.to_owned()));
+    }
+    let output = run_hg(
+        &root,
+        &[
+            "log",
+            "-r",
+            ".",
+            "-T",
+            concat!(
+                "{node}\n{date|hgdate}\n{p1node} {p2node}\n{activebookmark}\n{branch}\n",
+                "{revset('::.')|count}\n",
+            ),
+        ],
+    )?;
+    let mut lines = output.lines();
+    let mut next = || lines.next().ok_or("Unexpected output from hg log");
+    let node = next()?.to_owned();
+    let (time, offset) = next()?
+        .split_once(' ')
+        .ok_or("Unexpected date from hg log")?;
+    // Mercurial records the offset in seconds west of UTC
+    let (time, offset_mins): (i64, i32) = (time.parse()?, -offset.parse::<i32>()? / 60);
+    let parents: Vec<String> = next()?
+        .split(' ')
+        .filter(|parent| !parent.bytes().all(|b| b == b'0'))
+        .map(str::to_owned)
+        .collect();
+    let bookmark = next()?;
+    let branch = next()?;
+    let commit_count: usize = next()?.parse()?;
+    let branch = if bookmark.is_empty() {
+        branch
+    } else {
+        bookmark
+    };
+
+    // The null revision is all zeroes, and means there are no commits yet
+    let dates = if node.bytes().all(|b| b == b'0') {
+        None
+    } else {
+        let dates = format_commit_date(time, offset_mins)
+            .and_then(|date| Ok((date, format_commit_timestamp(time, offset_mins)?)));
+        match dates {
+            Ok(dates) => Some(dates),
+            Err(e) => {
+                note(
+                    &mut warnings,
+                    format!("Unable to understand the date of commit {node}: {e}"),
+                );
+                None
+            }
+        }
+    };
+    let commitinfo = if let Some((date, timestamp)) = dates {
+        let (tag, distance) = match latest_tag(&root, options.describe_match.as_deref()) {
+            Ok(tag) => tag.unwrap_or_default(),
+            Err(e) => {
+                warn!("No tag info found!\n{e:?}");
+                Default::default()
+            }
+        };
+        let release = options.release_tag.as_ref().and_then(|pattern| {
+            match latest_tag(&root, Some(pattern)) {
+                Ok(release) => release,
+                Err(e) => {
+                    warn!("No release tag matching {pattern} found!\n{e:?}");
+                    None
+                }
+            }
+        });
+        Some(CommitInfo {
+            date,
+            timestamp,
+            id: node,
+            tag,
+            distance,
+            commit_count,
+            describe: None,
+            release,
+            contained_in: None,
+            abbrev: SHORT_NODE_LENGTH,
+            merge: parents.len() > 1,
+            tags_at_head: vec![],
+            parents: if options.parents { parents } else { vec![] },
+            signed: false,
+            signer: None,
+            signed_tag: None,
+            tag_message: None,
+        })
+    } else {
+        None
+    };
+
+    let remote_url = options.remote_url.as_ref().and_then(|remote| {
+        // Mercurial's equivalent of git's `origin` is called `default`
+        let remote = if remote == "origin" {
+            "default"
+        } else {
+            remote
+        };
+        match run_hg(&root, &["paths", remote]) {
+            Ok(url) => Some(strip_credentials(url.trim())),
+            Err(e) => {
+                note(
+                    &mut warnings,
+                    format!("Unable to determine URL of path {remote}: {e}"),
+                );
+                None
+            }
+        }
+    });
+
+    // Without a commit there is nothing to compare the working tree with
+    let status_collected = commitinfo.is_none() || !options.skip_status;
+    let status = if commitinfo.is_some() && !options.skip_status {
+        status(&root, options)?
+    } else {
+        vec![]
+    };
+
+    Ok(GitInformation {
+        vcs: Vcs::Mercurial,
+        toplevel: root,
+        branch: Some(branch.to_owned()),
+        commitinfo,
+        remote: None,
+        shallow: false,
+        worktree: false,
+        bare: false,
+        detached: false,
+        remote_url,
+        status,
+        status_collected,
+        submodules: vec![],
+        worktree_digest: None,
+        diff: None,
+        git_version: None,
+        lfs_incomplete: false,
+        cargo_vcs_info: None,
+        overridden: vec![],
+        warnings,
+    })
+}
@@ -0,0 +1,260 @@
+//! Acquiring repository information from Jujutsu
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::warn;
+
+use crate::StatusFlag::*;
+use crate::{
+    beyond_ceiling, format_commit_date, format_commit_timestamp, glob_matches, note, parse_offset,
+    run_command, strip_credentials, text_output, AcquireOptions, CommitInfo, Error, GitInformation,
+    StatusEntry, TestamentError, Vcs,
+};
+
+/// The length of the abbreviated hashes Jujutsu shows by default
+const SHORT_ID_LENGTH: usize = 12;
+
+fn run_jj(dir: &Path, args: &[&str]) -> Result<String, Error> {
+    let output = run_command(
+        Command::new("jj")
+            .args(["--no-pager", "--color", "never"])
+            .args(args)
+            .current_dir(dir),
+    )?;
+    text_output(output)
+}
+
+/// Log a single revision with the given template, without snapshotting the
+/// working copy again
+fn log(root: &Path, revset: &str, template: &str) -> Result<String, Error> {
+    run_jj(
+        root,
+        &[
+            "log",
+            "--ignore-working-copy",
+            "--no-graph",
+            "-r",
+            revset,
+            "-T",
+            template,
+        ],
+    )
+}
+
+/// Quote a string for use in a revset or template
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// The most recent tag matching `pattern`, if any, which is an ancestor of
+/// `commit`, and the number of commits since it
+fn latest_tag(
+    root: &Path,
+    commit: &str,
+    pattern: Option<&str>,
+) -> Result<Option<(String, usize)>, Error> {
+    let tags = match pattern {
+        Some(pattern) => format!("tags({})", quote(&format!("glob:{pattern}"))),
+        None => "tags()".to_owned(),
+    };
+    let output = log(
+        root,
+        &format!("latest({tags} & ::{commit})"),
+        r#"commit_id ++ "\n" ++ tags.map(|t| t.name()).join("\n") ++ "\n""#,
+    )?;
+    let mut lines = output.lines();
+    let tagged = match lines.next() {
+        Some(tagged) => tagged,
+        None => return Ok(None),
+    };
+    let tag = lines
+        .find(|tag| pattern.is_none_or(|pattern| glob_matches(pattern, tag)))
+        .ok_or("Unexpected output from jj log")?;
+    let distance = log(root, &format!("{tagged}..{commit}"), r#""x""#)?.len();
+    Ok(Some((tag.to_owned(), distance)))
+}
+
+fn status(root: &Path, options: &AcquireOptions) -> Result<Vec<StatusEntry>, Error> {
+    let output = run_jj(root, &["diff", "--summary", "-r", "@"])?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let (code, path) = line.split_once(' ')?;
+            let status = match code {
+                "M" => Modified,
+                "A" | "C" | "R" => Added,
+                "D" => Deleted,
+                _ => return None,
+            };
+            Some(StatusEntry {
+                path: path.as_bytes().to_vec(),
+                status,
+                original_path: None,
+            })
+        })
+        .filter(|entry| {
+            !options
+                .ignore
+                .iter()
+                .any(|pattern| glob_matches(pattern, &String::from_utf8_lossy(&entry.path)))
+        })
+        .collect())
+}
+
+pub(crate) fn acquire(start: &Path, options: &AcquireOptions) -> Result<GitInformation, Error> {
+    let mut warnings = Vec::new();
+    let root = run_jj(start, &["root"]).map_err(|e| match e {
+        TestamentError::CommandFailed { ref stderr, .. } if stderr.contains("no jj repo") => {
+            TestamentError::NoRepository(start.to_owned())
+        }
+        e => e,
+    })?;
+    let root = PathBuf::from(root.trim_end());
+    if beyond_ceiling(start, &root) {
+        return Err(TestamentError::NoRepository(start.to_owned()));
+    }
+    // This snapshots the working copy, so it must come before anything which
+    // ignores the working copy
+    let status = if options.skip_status {
+        vec![]
+    } else {
+        status(&root, options)?
+    };
+
+    let output = log(
+        &root,
+        "@-",
+        concat!(
+            r#"commit_id ++ "\n" ++ committer.timestamp().format("%s %z") ++ "\n" ++ "#,
+            r#"parents.map(|c| c.commit_id()).join(" ") ++ "\n" ++ "#,
+            r#"local_bookmarks.map(|b| b.name()).join(" ") ++ "\n""#,
+        ),
+    )?;
+    // A merge in the working copy has several parents, so use the first
+    let mut lines = output.lines();
+    let mut next = || lines.next().ok_or("Unexpected output from jj log");
+    let commit = next()?.to_owned();
+    let (time, offset) = next()?
+        .split_once(' ')
+        .ok_or("Unexpected timestamp from jj log")?;
+    let (time, offset_mins): (i64, i32) = (time.parse()?, parse_offset(offset)?);
+    let parents: Vec<String> = next()?
+        .split(' ')
+        .filter(|parent| !parent.is_empty() && !parent.bytes().all(|b| b == b'0'))
+        .map(str::to_owned)
+        .collect();
+    let branch = next()?.split(' ').next().filter(|b| !b.is_empty());
+    let branch = branch.map(str::to_owned);
+
+    // The root commit is all zeroes, and means there are no commits yet
+    let dates = if commit.bytes().all(|b| b == b'0') {
+        None
+    } else {
+        let dates = format_commit_date(time, offset_mins)
+            .and_then(|date| Ok((date, format_commit_timestamp(time, offset_mins)?)));
+        match dates {
+            Ok(dates) => Some(dates),
+            Err(e) => {
+                note(
+                    &mut warnings,
+                    format!("Unable to understand the date of commit {commit}: {e}"),
+                );
+                None
+            }
+        }
+    };
+    let commitinfo = if let Some((date, timestamp)) = dates {
+        let (tag, distance) = match latest_tag(&root, &commit, options.describe_match.as_deref()) {
+            Ok(tag) => tag.unwrap_or_default(),
+            Err(e) => {
+                warn!("No tag info found!\n{e:?}");
+                Default::default()
+            }
+        };
+        // The root commit is an ancestor of every commit, but is not a real one
+        let commit_count = match log(&root, &format!("::{commit}"), r#""x""#) {
+            Ok(commits) => commits.len().saturating_sub(1),
+            Err(e) => {
+                note(&mut warnings, format!("Unable to count commits: {e}"));
+                0
+            }
+        };
+        let release = options.release_tag.as_ref().and_then(|pattern| {
+            match latest_tag(&root, &commit, Some(pattern)) {
+                Ok(release) => release,
+                Err(e) => {
+                    warn!("No release tag matching {pattern} found!\n{e:?}");
+                    None
+                }
+            }
+        });
+        Some(CommitInfo {
+            date,
+            timestamp,
+            id: commit,
+            tag,
+            distance,
+            commit_count,
+            describe: None,
+            release,
+            contained_in: None,
+            abbrev: SHORT_ID_LENGTH,
+            merge: parents.len() > 1,
+            tags_at_head: vec![],
+            parents: if options.parents { parents } else { vec![] },
+            signed: false,
+            signer: None,
+            signed_tag: None,
+            tag_message: None,
+        })
+    } else {
+        None
+    };
+
+    let status = if commitinfo.is_some() { status } else { vec![] };
+    let status_collected = commitinfo.is_none() || !options.skip_status;
+
+    let remote_url = options.remote_url.as_ref().and_then(|remote| {
+        let remotes = match run_jj(&root, &["git", "remote", "list"]) {
+            Ok(remotes) => remotes,
+            Err(e) => {
+                note(&mut warnings, format!("Unable to list remotes: {e}"));
+                return None;
+            }
+        };
+        let url = remotes
+            .lines()
+            .find_map(|line| line.strip_prefix(remote.as_str())?.strip_prefix(' '));
+        if url.is_none() {
+            note(
+                &mut warnings,
+                format!("Unable to determine URL of remote {remote}"),
+            );
+        }
+        url.map(|url| strip_credentials(url.trim()))
+    });
+
+    Ok(GitInformation {
+        vcs: Vcs::Jujutsu,
+        toplevel: root,
+        branch,
+        commitinfo,
+        remote: None,
+        shallow: false,
+        worktree: false,
+        bare: false,
+        detached: false,
+        remote_url,
+        status,
+        status_collected,
+        submodules: vec![],
+        worktree_digest: None,
+        diff: None,
+        git_version: None,
+        lfs_incomplete: false,
+        cargo_vcs_info: None,
+        overridden: vec![],
+        warnings,
+    })
+}
@@ -0,0 +1,2541 @@
+//! Shared implementation of `git-testament`
+//!
+//! This crate contains the logic for probing a git repository which is shared
+//! between the `git-testament-derive` procedural macro, the `runtime` feature
+//! of `git-testament` and `git-testament-build`.  Build scripts and other
+//! tools which need the same information can use it directly, rather than
+//! reimplementing the probing:
+//!
+//! ```no_run
+//! use git_testament_core::{AcquireOptions, GitInformation};
+//!
+//! # fn main() -> Result<(), git_testament_core::Error> {
+//! let info = GitInformation::acquire(".".as_ref(), &AcquireOptions::default())?;
+//! if let Some(commit) = &info.commitinfo {
+//!     println!("{} {}+{}", commit.id, commit.tag, commit.distance);
+//! }
+//! println!("{} modifications", info.status.len());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The pieces of the probing are also available on their own: [`run_git`]
+//! runs git as the probing does, [`branch_name`] finds the branch, and
+//! [`parse_describe`] and [`parse_status`] understand the output of `git
+//! describe --long` and `git status --porcelain=v2 -z`.  To test how the
+//! output of git is understood without a repository, implement [`GitRunner`]
+//! to replay recorded output and pass it to [`GitInformation::acquire_with`].
+//!
+//! The API follows the version of this crate, rather than that of
+//! `git-testament`, so minor versions before 1.0 may break it.
+
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf, Prefix};
+use std::process::{Command, Stdio};
+
+use log::warn;
+
+use time::format_description::{well_known::Rfc3339, FormatItem};
+use time::{macros::format_description, OffsetDateTime, UtcOffset};
+
+const DATE_FORMAT: &[FormatItem<'_>] = format_description!("[year]-[month]-[day]");
+
+/// The minimum length to which git abbreviates commit hashes
+const DEFAULT_ABBREV: usize = 7;
+
+mod error;
+
+pub use error::TestamentError;
+
+/// The error type returned when repository information cannot be acquired.
+pub type Error = TestamentError;
+
+/// The environment variables which change where the repository is found.
+///
+/// `GIT_DIR` and `GIT_WORK_TREE` are honoured by git itself, and are resolved
+/// against the current directory if they are relative, since git is run from
+/// elsewhere.  `GIT_CEILING_DIRECTORIES` is honoured by git, and also when
+/// looking for Mercurial and Jujutsu repositories.  `NIX_BUILD_TOP` marks a
+/// Nix build, in which the [`NIX_REVISION_FILE`] is read if there is no
+/// repository.  Anything which caches a testament should be invalidated when
+/// any of these change.
+pub const DISCOVERY_ENV_VARS: &[&str] = &[
+    "GIT_DIR",
+    "GIT_WORK_TREE",
+    "GIT_CEILING_DIRECTORIES",
+    "NIX_BUILD_TOP",
+];
+
+/// The file which, by convention, holds the hash of the commit a Nix build is
+/// of, since the sources Nix builds from have no repository.  It is read in a
+/// Nix build unless [`AcquireOptions::revision_file`] names another.
+pub const NIX_REVISION_FILE: &str = ".git-revision";
+
+/// The environment variables which take precedence over what is acquired from
+/// the repository, see [`GitInformation::with_overrides`].
+pub const OVERRIDE_ENV_VARS: &[&str] = &[
+    "GIT_TESTAMENT_COMMIT",
+    "GIT_TESTAMENT_DATE",
+    "GIT_TESTAMENT_TAG",
+    "GIT_TESTAMENT_DISTANCE",
+    "GIT_TESTAMENT_BRANCH",
+    "GIT_TESTAMENT_DIRTY",
+];
+
+/// Options which control how repository information is acquired
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AcquireOptions {
+    /// Skip modifications to paths marked `export-ignore` in `.gitattributes`
+    pub respect_export_ignore: bool,
+    /// A glob pattern for tags considered to be releases, see [`CommitInfo::release`]
+    pub release_tag: Option<String>,
+    /// A glob pattern restricting the tags used to describe the commit, as
+    /// `git describe --match`
+    pub describe_match: Option<String>,
+    /// A glob pattern for tags never used to describe the commit, as
+    /// `git describe --exclude`
+    pub describe_exclude: Option<String>,
+    /// Only follow the first parent of merges when describing the commit, as
+    /// `git describe --first-parent`
+    pub describe_first_parent: bool,
+    /// The number of candidate tags to consider when describing the commit,
+    /// as `git describe --candidates`
+    pub describe_candidates: Option<usize>,
+    /// Whether to also look for the earliest tag which contains the commit,
+    /// see [`CommitInfo::contained_in`]
+    pub describe_mode: DescribeMode,
+    /// Read the message and date of the annotated tag chosen by
+    /// `git describe`, see [`CommitInfo::tag_message`]
+    pub tag_message: bool,
+    /// Glob patterns for paths whose modifications should be ignored
+    ///
+    /// These are matched against the path relative to the top of the
+    /// repository, and a `*` may match across directory separators.
+    pub ignore: Vec<String>,
+    /// If there are no commits, fall back to the remote default branch, see
+    /// [`GitInformation::remote`]
+    pub remote_fallback: bool,
+    /// Record the parents of the commit, see [`CommitInfo::parents`]
+    pub parents: bool,
+    /// Record every tag at the commit, see [`CommitInfo::tags_at_head`]
+    pub tags_at_head: bool,
+    /// The name of a remote whose URL should be recorded, see
+    /// [`GitInformation::remote_url`]
+    pub remote_url: Option<String>,
+    /// Verify the signature of the commit, see [`CommitInfo::signed`]
+    pub verify_signature: bool,
+    /// Verify the signatures of annotated tags at the commit, see
+    /// [`CommitInfo::signed_tag`]
+    pub verify_tags: bool,
+    /// An SSH allowed signers file to verify signatures with, in place of
+    /// git's `gpg.ssh.allowedSignersFile` configuration
+    pub allowed_signers: Option<PathBuf>,
+    /// A GnuPG home directory whose keyring to verify signatures with, in
+    /// place of the builder's own
+    pub gpg_home: Option<PathBuf>,
+    /// How untracked files are reported in the status
+    pub untracked: UntrackedFiles,
+    /// Do not collect the status at all, since it can take a long time in a
+    /// very large repository, see [`GitInformation::status_collected`]
+    pub skip_status: bool,
+    /// Record the state of each submodule, see [`GitInformation::submodules`]
+    pub submodules: bool,
+    /// Digest the changes to tracked files, see
+    /// [`GitInformation::worktree_digest`]
+    pub worktree_digest: bool,
+    /// Record at most this many bytes of the changes to tracked files, see
+    /// [`GitInformation::diff`]
+    pub embed_diff: Option<usize>,
+    /// Check whether any Git LFS objects have not been fetched, see
+    /// [`GitInformation::lfs_incomplete`]
+    pub lfs: bool,
+    /// If `HEAD` is detached, take the branch from the CI system building
+    /// the crate, if there is one, see [`ci_branch`]
+    pub ci_branch: bool,
+    /// A file holding the hash of the commit, read if there is no repository,
+    /// relative to the directory the repository is searched for from.  In a
+    /// Nix build the [`NIX_REVISION_FILE`] is read if this is not given.
+    pub revision_file: Option<PathBuf>,
+}
+
+impl AcquireOptions {
+    /// The arguments to pass to `git describe` to describe the commit
+    fn describe_args(&self) -> Vec<String> {
+        let mut args = vec![];
+        if let Some(pattern) = &self.describe_match {
+            args.extend(["--match".to_owned(), pattern.clone()]);
+        }
+        if let Some(pattern) = &self.describe_exclude {
+            args.extend(["--exclude".to_owned(), pattern.clone()]);
+        }
+        if self.describe_first_parent {
+            args.push("--first-parent".to_owned());
+        }
+        if let Some(candidates) = self.describe_candidates {
+            args.push(format!("--candidates={candidates}"));
+        }
+        args
+    }
+
+    /// The configuration and environment with which git verifies signatures
+    /// using the configured allowed signers file and keyring, if there are any
+    fn verify_env(&self) -> (Vec<OsString>, Vec<(&'static str, &OsStr)>) {
+        let mut config = vec![];
+        if let Some(allowed_signers) = &self.allowed_signers {
+            let mut setting = OsString::from("gpg.ssh.allowedSignersFile=");
+            setting.push(allowed_signers);
+            config.push(setting);
+        }
+        let env = self
+            .gpg_home
+            .iter()
+            .map(|gpg_home| ("GNUPGHOME", gpg_home.as_os_str()))
+            .collect();
+        (config, env)
+    }
+
+    /// The pathspecs which exclude the `ignore` patterns, if there are any
+    fn exclude_pathspecs(&self) -> Vec<String> {
+        if self.ignore.is_empty() {
+            return vec![];
+        }
+        let excludes = self
+            .ignore
+            .iter()
+            .map(|pattern| format!(":(exclude){pattern}"));
+        ["--".to_owned(), ".".to_owned()]
+            .into_iter()
+            .chain(excludes)
+            .collect()
+    }
+}
+
+/// Which tags describe the commit, see [`AcquireOptions::describe_mode`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum DescribeMode {
+    /// Only the most recent tag in the history of the commit, as
+    /// `git describe`
+    #[default]
+    Since,
+    /// Also the earliest tag whose history contains the commit, as
+    /// `git describe --contains`
+    Contains,
+}
+
+/// How untracked files are reported, mirroring git's `--untracked-files`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum UntrackedFiles {
+    /// Untracked files are not reported at all
+    No,
+    /// An untracked directory is reported as a single entry
+    #[default]
+    Normal,
+    /// Every untracked file is reported, even within untracked directories
+    All,
+}
+
+impl UntrackedFiles {
+    fn as_arg(self) -> &'static str {
+        match self {
+            UntrackedFiles::No => "--untracked-files=no",
+            UntrackedFiles::Normal => "--untracked-files=normal",
+            UntrackedFiles::All => "--untracked-files=all",
+        }
+    }
+}
+
+mod hg;
+mod jj;
+
+/// A `git` command run from `dir`.
+///
+/// Since `dir` is rarely the current directory, any relative `GIT_DIR` or
+/// `GIT_WORK_TREE` is made absolute so that it still refers to the same place.
+fn git_command(dir: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.current_dir(dir);
+    for var in ["GIT_DIR", "GIT_WORK_TREE"] {
+        if let Some(value) = std::env::var_os(var).filter(|value| !value.is_empty()) {
+            if let Ok(absolute) = std::path::absolute(value) {
+                command.env(var, absolute);
+            }
+        }
+    }
+    command
+}
+
+/// Run git with `args` from `dir`, returning its output if it succeeded.
+///
+/// As when probing, any relative `GIT_DIR` or `GIT_WORK_TREE` is resolved
+/// against the current directory, and standard input is closed.  If git did
+/// not succeed then the error is [`TestamentError::CommandFailed`] with its
+/// error output, or [`TestamentError::ToolUnavailable`] if it could not be
+/// run at all.
+pub fn run_git<GD>(dir: GD, args: &[&str]) -> Result<Vec<u8>, Error>
+where
+    GD: AsRef<Path>,
+{
+    run_command(git_command(dir.as_ref()).args(args))
+}
+
+/// Whether finding `root` from `start` would have meant looking in one of the
+/// `GIT_CEILING_DIRECTORIES`, which git would refuse to do
+fn beyond_ceiling(start: &Path, root: &Path) -> bool {
+    let ceilings = match std::env::var_os("GIT_CEILING_DIRECTORIES") {
+        Some(ceilings) => ceilings,
+        None => return false,
+    };
+    std::env::split_paths(&ceilings)
+        .filter(|ceiling| ceiling.is_absolute())
+        .any(|ceiling| {
+            ceiling != start
+                && relative_to(start, &ceiling).is_some()
+                && relative_to(&ceiling, root).is_some()
+        })
+}
+
+/// Run a command, returning its output if it succeeded and its error output
+/// as the error otherwise.
+fn run_command(command: &mut Command) -> Result<Vec<u8>, Error> {
+    run_command_with_stderr(command).map(|(stdout, _)| stdout)
+}
+
+/// Run a command as [`run_command`] does, also returning its error output if
+/// it succeeded.
+fn run_command_with_stderr(command: &mut Command) -> Result<(Vec<u8>, Vec<u8>), Error> {
+    log::trace!("Running {}", command_line(command));
+    let output = command
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| spawn_error(command, e))?;
+    if output.status.success() {
+        log::trace!("{} bytes of output", output.stdout.len());
+        Ok((output.stdout, output.stderr))
+    } else {
+        log::trace!("Failed with {}", output.status);
+        Err(command_failed(command, &output.stderr))
+    }
+}
+
+/// The output of a command as text, with any carriage returns at the ends of
+/// lines removed.  Some Windows setups, such as wrappers around git or
+/// `core.autocrlf`, end lines with `\r\n`, which would otherwise end up in
+/// branch and tag names.
+fn text_output(output: Vec<u8>) -> Result<String, Error> {
+    Ok(normalize_newlines(&String::from_utf8(output)?))
+}
+
+fn normalize_newlines(text: &str) -> String {
+    text.split('\n')
+        .map(|line| line.trim_end_matches('\r'))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// `path` relative to `base`, if it is within it.  On Windows the drive
+/// letters may differ in case, and `canonicalize` gives `\\?\C:\...` where
+/// git gives `C:/...`, so the paths are compared component by component.
+fn relative_to<'a>(path: &'a Path, base: &Path) -> Option<&'a Path> {
+    let same = |a: Component, b: Component| match (a, b) {
+        (Component::Prefix(a), Component::Prefix(b)) => match (a.kind(), b.kind()) {
+            (
+                Prefix::Disk(a) | Prefix::VerbatimDisk(a),
+                Prefix::Disk(b) | Prefix::VerbatimDisk(b),
+            ) => a.eq_ignore_ascii_case(&b),
+            _ => a == b,
+        },
+        (a, b) => a == b,
+    };
+    let mut components = path.components();
+    for component in base.components() {
+        if !same(components.next()?, component) {
+            return None;
+        }
+    }
+    Some(components.as_path())
+}
+
+/// The command as it might be typed, for messages
+fn command_line(command: &Command) -> String {
+    let mut line = command.get_program().to_string_lossy().into_owned();
+    for arg in command.get_args() {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
+    }
+    line
+}
+
+/// The error for a command which could not be started
+fn spawn_error(command: &Command, e: std::io::Error) -> Error {
+    TestamentError::ToolUnavailable {
+        tool: command.get_program().to_string_lossy().into_owned(),
+        source: e,
+    }
+}
+
+/// The error for a command which ran but did not succeed
+fn command_failed(command: &Command, stderr: &[u8]) -> Error {
+    TestamentError::CommandFailed {
+        command: command_line(command),
+        stderr: String::from_utf8_lossy(stderr).into_owned(),
+    }
+}
+
+/// A way of running git, which is [`SystemGit`] unless the output of git is
+/// being replayed, see [`GitInformation::acquire_with`].
+///
+/// The commands may be run from several threads at once.
+pub trait GitRunner: Sync {
+    /// Run git with `args` from `dir`, returning its output if it succeeded,
+    /// and otherwise [`TestamentError::CommandFailed`] with its error output.
+    fn run_git(&self, dir: &Path, args: &[&str]) -> Result<Vec<u8>, Error>;
+
+    /// Run git as [`GitRunner::run_git`] does, with `input` as its standard
+    /// input.  By default the input is ignored, which suits runners which
+    /// replay recorded output.
+    fn run_git_with_input(
+        &self,
+        dir: &Path,
+        args: &[&str],
+        input: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let _ = input;
+        self.run_git(dir, args)
+    }
+
+    /// Run git as [`GitRunner::run_git`] does, with each of `config` given
+    /// to it as `-c name=value` and the `env` variables set, returning its
+    /// error output as well, since `git verify-tag` reports there.  By
+    /// default the configuration and environment are ignored and there is
+    /// no error output, which suits runners which replay recorded output.
+    fn run_git_with_env(
+        &self,
+        dir: &Path,
+        args: &[&str],
+        config: &[OsString],
+        env: &[(&str, &OsStr)],
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let _ = (config, env);
+        Ok((self.run_git(dir, args)?, vec![]))
+    }
+}
+
+/// Run the `git` found on the `PATH`, see [`run_git`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemGit;
+
+impl GitRunner for SystemGit {
+    fn run_git(&self, dir: &Path, args: &[&str]) -> Result<Vec<u8>, Error> {
+        run_git(dir, args)
+    }
+
+    fn run_git_with_input(
+        &self,
+        dir: &Path,
+        args: &[&str],
+        input: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        run_git_with_input(dir, args, input)
+    }
+
+    fn run_git_with_env(
+        &self,
+        dir: &Path,
+        args: &[&str],
+        config: &[OsString],
+        env: &[(&str, &OsStr)],
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let mut command = git_command(dir);
+        for setting in config {
+            command.arg("-c").arg(setting);
+        }
+        command.args(args).envs(env.iter().copied());
+        run_command_with_stderr(&mut command)
+    }
+}
+
+fn run_git_with_input<GD>(dir: GD, args: &[&str], input: &[u8]) -> Result<Vec<u8>, Error>
+where
+    GD: AsRef<Path>,
+{
+    let mut command = git_command(dir.as_ref());
+    command
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    let mut child = command.spawn().map_err(|e| spawn_error(&command, e))?;
+    // Feed the input from another thread so that git cannot deadlock on a
+    // full stdout pipe while we are still writing its stdin.
+    let mut stdin = child.stdin.take().expect("stdin was piped");
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    writer
+        .join()
+        .map_err(|_| "Unable to write the input to git")??;
+    if output.status.success() {
+        Ok(output.stdout)
+    } else {
+        Err(command_failed(&command, &output.stderr))
+    }
+}
+
+/// Find the top of the working tree containing `start`, or for a bare
+/// repository the repository itself, along with whether it is bare.
+fn find_git_dir(git: &dyn GitRunner, start: &Path) -> Result<(PathBuf, bool), Error> {
+    // run git rev-parse --show-toplevel in the starting directory
+    let (mut dir, bare) = match git.run_git(start, &["rev-parse", "--show-toplevel"]) {
+        Ok(dir) => (dir, false),
+        // A bare repository has no working tree, but its history can still
+        // be read from the repository itself
+        Err(TestamentError::CommandFailed { ref stderr, .. })
+            if stderr.contains("must be run in a work tree") =>
+        {
+            (
+                git.run_git(start, &["rev-parse", "--absolute-git-dir"])?,
+                true,
+            )
+        }
+        Err(TestamentError::CommandFailed { ref stderr, .. })
+            if stderr.contains("not a git repository") =>
+        {
+            return Err(TestamentError::NoRepository(start.to_owned()));
+        }
+        Err(e) => return Err(e),
+    };
+    while matches!(dir.last(), Some(b'\n' | b'\r')) {
+        dir.pop();
+    }
+    Ok((path_from_bytes(&dir), bare))
+}
+
+/// A commit as reported by `git log`
+struct LoggedCommit {
+    id: String,
+    time: i64,
+    offset: i32,
+    parents: Vec<String>,
+    /// The branch `refname` points at, if it is a symbolic ref such as `HEAD`
+    branch: Option<String>,
+}
+
+/// Resolve `refname` to a commit with a single `git log`, so that the hash,
+/// commit time, parents and branch are all taken from the same commit.
+///
+/// The hashes and dates are always ASCII.  Only the decorations can contain
+/// other bytes, since git allows ref names which are not UTF-8, so they are
+/// read lossily rather than failing; the branch is recorded as a string
+/// anyway, and such names are vanishingly rare.
+fn log_commit(git: &dyn GitRunner, git_dir: &Path, refname: &str) -> Result<LoggedCommit, Error> {
+    let output = git.run_git(
+        git_dir,
+        &[
+            "log",
+            "-1",
+            "--no-show-signature",
+            "--format=%H%n%ct%n%ci%n%P%n%D",
+            refname,
+            "--",
+        ],
+    )?;
+    let output = normalize_newlines(&String::from_utf8_lossy(&output));
+    let mut lines = output.lines();
+    let mut field = |name: &str| {
+        lines
+            .next()
+            .ok_or_else(|| Error::from(format!("Missing {name} in the log of {refname}")))
+    };
+    let id = field("hash")?.to_owned();
+    let time: i64 = field("commit time")?.parse()?;
+    // The date ends with the committer's offset, e.g. `2024-01-01 12:00:00 +0100`
+    let offset = parse_offset(field("commit date")?.rsplit(' ').next().unwrap_or_default())?;
+    let parents = field("parents")?
+        .split_whitespace()
+        .map(str::to_owned)
+        .collect();
+    // The decorations look like `HEAD -> main, tag: v1.0, origin/main`
+    let branch = field("decorations")
+        .unwrap_or_default()
+        .split(", ")
+        .find_map(|decoration| decoration.strip_prefix(&format!("{refname} -> ")))
+        .map(str::to_owned);
+    Ok(LoggedCommit {
+        id,
+        time,
+        offset,
+        parents,
+        branch,
+    })
+}
+
+/// Parse a timezone offset such as `+0100` into minutes east of UTC
+fn parse_offset(offset: &str) -> Result<i32, Error> {
+    if offset.len() != 5 || !offset.is_ascii() {
+        return Err(format!("Insufficient/Incorrect data in timezone offset: {offset}").into());
+    }
+    let hours: i32 = offset[1..=2].parse()?;
+    let mins: i32 = offset[3..=4].parse()?;
+    let absoffset: i32 = mins + (hours * 60);
+    Ok(if offset.starts_with('-') {
+        // Negative...
+        -absoffset
+    } else {
+        // Positive...
+        absoffset
+    })
+}
+
+/// Match `text` against a glob `pattern` in which `*` matches any sequence of
+/// characters, including `/`, and `?` matches any single character.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    // Where to resume if the most recent `*` needs to match more characters
+    let mut star = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// The first line of the message of the annotated tag `tag`, and the date it
+/// was tagged, or `None` if it is a lightweight tag.
+fn tag_message(
+    git: &dyn GitRunner,
+    git_dir: &Path,
+    tag: &str,
+) -> Result<Option<(String, String)>, Error> {
+    let object = git.run_git(git_dir, &["cat-file", "-p", &format!("refs/tags/{tag}")])?;
+    let object = normalize_newlines(&String::from_utf8_lossy(&object));
+    let (headers, message) = object.split_once("\n\n").unwrap_or((&object, ""));
+    // A lightweight tag refers to the commit itself, rather than a tag object
+    if !headers.starts_with("object ") {
+        return Ok(None);
+    }
+    let tagger = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("tagger "))
+        .ok_or_else(|| format!("No tagger in tag {tag}"))?;
+    let parts: Vec<&str> = tagger.split_whitespace().collect();
+    if parts.len() < 2 {
+        return Err(format!("Insufficient tagger data in {tagger}").into());
+    }
+    let time: i64 = parts[parts.len() - 2].parse()?;
+    let offset = parse_offset(parts[parts.len() - 1])?;
+    let title = message.lines().next().unwrap_or_default().trim().to_owned();
+    Ok(Some((title, format_commit_date(time, offset)?)))
+}
+
+/// The number of commits reachable from `sha`, including itself
+fn commit_count(git: &dyn GitRunner, git_dir: &Path, sha: &str) -> Result<usize, Error> {
+    let count = text_output(git.run_git(git_dir, &["rev-list", "--count", sha])?)?;
+    Ok(count.trim_end().parse()?)
+}
+
+/// Find the earliest tag containing the commit, as `git describe --contains`,
+/// and count the commits in that tag which are not in the commit.
+///
+/// `--first-parent` and `--candidates` mean nothing to `--contains`, so only
+/// the patterns restricting the tags are passed on.
+fn contained_in(
+    git: &dyn GitRunner,
+    git_dir: &Path,
+    options: &AcquireOptions,
+    sha: &str,
+) -> Result<(String, usize), Error> {
+    let mut args = vec!["describe", "--contains", "--tags"];
+    if let Some(pattern) = &options.describe_match {
+        args.extend(["--match", pattern]);
+    }
+    if let Some(pattern) = &options.describe_exclude {
+        args.extend(["--exclude", pattern]);
+    }
+    args.push(sha);
+    let res = text_output(git.run_git(git_dir, &args)?)?;
+    // This is the tag, followed by the path from it to the commit, for
+    // example `v1.2.0~3^2~1`, or `v1.2.0^0` if the commit is the tag
+    let tag = res.trim_end().split(['~', '^']).next().unwrap_or_default();
+    if tag.is_empty() {
+        return Err(format!("Unexpected output from git describe --contains: {res:?}").into());
+    }
+    let range = format!("{sha}..refs/tags/{tag}");
+    let count = text_output(git.run_git(git_dir, &["rev-list", "--count", &range])?)?;
+    Ok((tag.to_owned(), count.trim_end().parse()?))
+}
+
+/// Check the signature on a commit, returning whether it is good and if so
+/// who it was signed by.
+///
+/// Signatures which are good but whose key's validity is unknown (`U`) are
+/// considered good, since that is the usual state of affairs for SSH keys and
+/// for GPG keys which the builder has not explicitly trusted.
+fn verify_signature(
+    git: &dyn GitRunner,
+    git_dir: &Path,
+    options: &AcquireOptions,
+    sha: &str,
+) -> Result<(bool, Option<String>), Error> {
+    let (config, env) = options.verify_env();
+    let (output, _) = git.run_git_with_env(
+        git_dir,
+        &["show", "-s", "--format=%G?%n%GS", sha],
+        &config,
+        &env,
+    )?;
+    let output = text_output(output)?;
+    let mut lines = output.lines();
+    let signed = matches!(lines.next(), Some("G") | Some("U"));
+    let signer = lines
+        .next()
+        .filter(|signer| signed && !signer.is_empty())
+        .map(str::to_owned);
+    Ok((signed, signer))
+}
+
+/// The tags which point directly at the commit, in git's order.
+fn tags_at(git: &dyn GitRunner, git_dir: &Path, sha: &str) -> Result<Vec<String>, Error> {
+    let tags = text_output(git.run_git(git_dir, &["tag", "--points-at", sha])?)?;
+    Ok(tags.lines().map(str::to_owned).collect())
+}
+
+/// Find the first annotated tag at `sha` with a good signature, returning its
+/// name and, if it could be determined, who it was signed by.
+///
+/// Lightweight tags cannot be signed, and `git verify-tag` rejects them along
+/// with tags whose signatures are bad or made by keys which are not known.
+fn verify_tags(
+    git: &dyn GitRunner,
+    git_dir: &Path,
+    options: &AcquireOptions,
+    sha: &str,
+) -> Result<Option<(String, Option<String>)>, Error> {
+    let (config, env) = options.verify_env();
+    for tag in tags_at(git, git_dir, sha)? {
+        match git.run_git_with_env(git_dir, &["verify-tag", "--raw", &tag], &config, &env) {
+            Ok((_, report)) => {
+                let report = String::from_utf8_lossy(&report);
+                return Ok(Some((tag, tag_signer(&report))));
+            }
+            Err(TestamentError::CommandFailed { stderr, .. }) => {
+                log::debug!("Tag {tag} did not verify: {}", stderr.trim_end());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
+/// The signer named in the report from `git verify-tag --raw`, which is the
+/// GnuPG status output for GPG signatures and a single line for SSH ones.
+fn tag_signer(report: &str) -> Option<String> {
+    report.lines().find_map(|line| {
+        if let Some(good) = line.strip_prefix("[GNUPG:] GOODSIG ") {
+            // The key id, then the user id
+            let (_, user) = good.split_once(' ')?;
+            return Some(user.to_owned());
+        }
+        let principal = line.strip_prefix("Good \"git\" signature for ")?;
+        let (principal, _) = principal.rsplit_once(" with ")?;
+        Some(principal.to_owned())
+    })
+}
+
+/// The commit time, as a UTC time and the committer's offset from UTC, if
+/// they are within the range which can be represented.
+fn commit_time(time: i64, offset_mins: i32) -> Result<(OffsetDateTime, UtcOffset), Error> {
+    let utc = OffsetDateTime::from_unix_timestamp(time)
+        .map_err(|e| format!("Invalid commit time {time}: {e}"))?;
+    let offset = UtcOffset::from_whole_seconds(offset_mins.saturating_mul(60))
+        .map_err(|e| format!("Invalid UTC offset of {offset_mins} minutes: {e}"))?;
+    Ok((utc, offset))
+}
+
+fn format_commit_date(time: i64, offset_mins: i32) -> Result<String, Error> {
+    let (naive, _) = commit_time(time, offset_mins)?;
+    Ok(naive
+        .format(DATE_FORMAT)
+        .map_err(|e| format!("Unable to format commit date: {e}"))?)
+}
+
+/// Parse the date given by `GIT_TESTAMENT_DATE` into the commit date and
+/// timestamp.  A date alone is taken to be midnight UTC.
+fn parse_override_date(value: &str) -> Result<(String, String), Error> {
+    let value = value.trim();
+    let invalid = |e| format!("GIT_TESTAMENT_DATE is not a date or RFC 3339 timestamp: {e}");
+    if let Ok(time) = OffsetDateTime::parse(value, &Rfc3339) {
+        let date = time
+            .format(DATE_FORMAT)
+            .map_err(|e| invalid(e.to_string()))?;
+        return Ok((date, value.to_owned()));
+    }
+    let date = time::Date::parse(value, DATE_FORMAT).map_err(|e| invalid(e.to_string()))?;
+    let date = date
+        .format(DATE_FORMAT)
+        .map_err(|e| invalid(e.to_string()))?;
+    let timestamp = format!("{date}T00:00:00Z");
+    Ok((date, timestamp))
+}
+
+fn format_commit_timestamp(time: i64, offset_mins: i32) -> Result<String, Error> {
+    let (_, offset) = commit_time(time, offset_mins)?;
+    // Shift the time into the committer's timezone, checking that it is
+    // still in range, before labelling it with their offset
+    let local = time
+        .checked_add(i64::from(offset.whole_seconds()))
+        .ok_or_else(|| format!("Invalid commit time {time}"))?;
+    let local = OffsetDateTime::from_unix_timestamp(local)
+        .map_err(|e| format!("Invalid commit time {time}: {e}"))?;
+    Ok(local
+        .replace_offset(offset)
+        .format(&Rfc3339)
+        .map_err(|e| format!("Unable to format commit timestamp: {e}"))?)
+}
+
+/// Remove any credentials from a remote URL, since those must never end up
+/// embedded in a binary.
+///
+/// For `http` and `https` URLs the entire user information is removed since
+/// access tokens are commonly given as the user name.  For other URLs only
+/// the password is removed, so `ssh://git@host/repo` is left alone.
+fn strip_credentials(url: &str) -> String {
+    let (scheme, rest) = match url.split_once("://") {
+        Some(parts) => parts,
+        // scp-like syntax (user@host:path) or a local path
+        None => return url.to_owned(),
+    };
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    let host = match authority.rsplit_once('@') {
+        Some((userinfo, host)) => {
+            let scheme_lower = scheme.to_ascii_lowercase();
+            if scheme_lower == "http" || scheme_lower == "https" {
+                host.to_owned()
+            } else {
+                let user = userinfo.split(':').next().unwrap_or("");
+                format!("{user}@{host}")
+            }
+        }
+        None => authority.to_owned(),
+    };
+    format!("{scheme}://{host}{path}")
+}
+
+/// The release of git being run, as far as it matters for the arguments and
+/// output formats git understands
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct GitVersion(u32, u32);
+
+impl GitVersion {
+    /// The oldest release which everything is known to work with
+    const OLDEST: GitVersion = GitVersion(2, 0);
+    /// The first release with `git status --porcelain=v2`
+    const PORCELAIN_V2: GitVersion = GitVersion(2, 11);
+    /// The first release with `git rev-parse --is-shallow-repository`
+    const IS_SHALLOW: GitVersion = GitVersion(2, 15);
+
+    /// Whether `version` has a feature, assuming it does if the version of git
+    /// could not be determined, since that is most likely a recent release
+    /// which describes itself differently
+    fn supports(version: Option<GitVersion>, feature: GitVersion) -> bool {
+        version.is_none_or(|version| version >= feature)
+    }
+}
+
+/// The output of `git --version`, such as `2.39.3 (Apple Git-145)`, and the
+/// release it names if that could be understood
+fn git_version(git: &dyn GitRunner, dir: &Path) -> Option<(String, Option<GitVersion>)> {
+    let output = match git.run_git(dir, &["--version"]) {
+        Ok(output) => output,
+        Err(e) => {
+            warn!("Unable to determine the version of git: {e}");
+            return None;
+        }
+    };
+    let output = String::from_utf8_lossy(&output);
+    let description = output.trim().strip_prefix("git version ")?.to_owned();
+    Some((description.clone(), parse_git_version(&description)))
+}
+
+/// Parse the major and minor version from a description of a release such as
+/// `2.45.1.windows.1`
+fn parse_git_version(description: &str) -> Option<GitVersion> {
+    let mut numbers = description.split(['.', ' ']);
+    let major = numbers.next()?.parse().ok()?;
+    let minor = numbers.next()?.parse().ok()?;
+    Some(GitVersion(major, minor))
+}
+
+fn is_shallow(git: &dyn GitRunner, dir: &Path, version: Option<GitVersion>) -> bool {
+    // Older releases can only say where the list of shallow commits would be
+    let shallow = if GitVersion::supports(version, GitVersion::IS_SHALLOW) {
+        git.run_git(dir, &["rev-parse", "--is-shallow-repository"])
+            .map(|output| output.trim_ascii() == b"true")
+    } else {
+        git.run_git(dir, &["rev-parse", "--git-path", "shallow"])
+            .map(|path| dir.join(path_from_bytes(path.trim_ascii())).exists())
+    };
+    match shallow {
+        Ok(shallow) => shallow,
+        Err(e) => {
+            warn!("Unable to determine if the repository is shallow: {e}");
+            false
+        }
+    }
+}
+
+/// Whether the working tree is a linked worktree (see `git worktree`), whose
+/// git directory is separate from the repository's common directory.
+fn is_linked_worktree(git: &dyn GitRunner, dir: &Path) -> Result<bool, Error> {
+    let output = text_output(git.run_git(
+        dir,
+        &["rev-parse", "--absolute-git-dir", "--git-common-dir"],
+    )?)?;
+    let mut lines = output.lines();
+    let (git_dir, common_dir) = match (lines.next(), lines.next()) {
+        (Some(git_dir), Some(common_dir)) => (git_dir, common_dir),
+        _ => return Err("Unexpected output from git rev-parse".into()),
+    };
+    // The common directory may be relative to the working directory
+    let common_dir = dir.join(common_dir).canonicalize()?;
+    Ok(Path::new(git_dir).canonicalize()? != common_dir)
+}
+
+/// The branch checked out in the working tree containing `dir`, and whether
+/// `HEAD` is detached, in which case the branch is the nearest branch or tag
+/// `git name-rev` could find, if any.
+///
+/// This does not consult CI systems, see [`ci_branch`] for that.
+pub fn branch_name(dir: &Path) -> Result<(Option<String>, bool), Error> {
+    current_branch(&SystemGit, dir)
+}
+
+fn current_branch(git: &dyn GitRunner, dir: &Path) -> Result<(Option<String>, bool), Error> {
+    match git.run_git(dir, &["symbolic-ref", "-q", "HEAD"]) {
+        Ok(symref) => {
+            let name = text_output(symref)?;
+            let name = name.trim();
+            let name = name.strip_prefix("refs/heads/").unwrap_or(name);
+            Ok(((!name.is_empty()).then(|| name.to_owned()), false))
+        }
+        Err(_) => {
+            let name = text_output(git.run_git(dir, &["name-rev", "--name-only", "HEAD"])?)?;
+            Ok((sanitize_rev_name(name.trim()), true))
+        }
+    }
+}
+
+/// Tidy a name given by `git name-rev`, such as `tags/v1.0.0^0` or
+/// `remotes/origin/main~2`, into the name of the tag or branch it is relative
+/// to, or `None` if it is not relative to any.
+fn sanitize_rev_name(name: &str) -> Option<String> {
+    let name = name.split(['~', '^']).next().unwrap_or_default();
+    let name = name
+        .strip_prefix("tags/")
+        .or_else(|| name.strip_prefix("remotes/"))
+        .unwrap_or(name);
+    match name {
+        "" | "undefined" => None,
+        name => Some(name.to_owned()),
+    }
+}
+
+/// Describe `sha` with `git describe --long`.
+///
+/// The description is a tag name followed by ASCII, and the testament records
+/// the tag as a string, so a tag whose name is not UTF-8 is an error rather
+/// than being garbled, and is treated as no tag being found.
+fn describe(
+    git: &dyn GitRunner,
+    dir: &Path,
+    sha: &str,
+    extra_args: &[&str],
+) -> Result<String, Error> {
+    let mut args = vec!["describe", "--tags", "--long"];
+    args.extend_from_slice(extra_args);
+    args.push(sha);
+    Ok(text_output(git.run_git(dir, &args)?)?.trim_end().to_owned())
+}
+
+/// Split the output of `git describe --long` into the tag name and the
+/// number of commits since that tag.
+///
+/// ```
+/// use git_testament_core::parse_describe;
+///
+/// let (tag, distance) = parse_describe("release-1.0-rc1-3-g1234abc").unwrap();
+/// assert_eq!(tag, "release-1.0-rc1");
+/// assert_eq!(distance, 3);
+/// assert!(parse_describe("1234abc").is_err());
+/// ```
+pub fn parse_describe(res: &str) -> Result<(String, usize), Error> {
+    let unexpected = || format!("Unexpected output from git describe: {res:?}");
+    // The tag may itself contain dashes, so work from the end
+    let (rest, _hash) = res.rsplit_once('-').ok_or_else(unexpected)?;
+    let (tag_name, commit_count) = rest.rsplit_once('-').ok_or_else(unexpected)?;
+    if tag_name.is_empty() {
+        return Err(unexpected().into());
+    }
+    Ok((tag_name.to_owned(), commit_count.parse()?))
+}
+
+/// The kind of change recorded for a path in the working tree
+#[derive(Clone, Copy, Debug)]
+pub enum StatusFlag {
+    Added,
+    Deleted,
+    Modified,
+    Renamed,
+    Copied,
+    Untracked,
+}
+use StatusFlag::*;
+
+/// A single modification to the working tree
+#[derive(Clone, Debug)]
+pub struct StatusEntry {
+    /// The path relative to the top of the working tree, exactly as the
+    /// version control tool reported it, which need not be UTF-8
+    pub path: Vec<u8>,
+    pub status: StatusFlag,
+    /// For a rename or copy, the path the file was renamed or copied from
+    pub original_path: Option<Vec<u8>>,
+}
+
+/// Turn the bytes of a path, as git reports it, into a path.  On Unix this
+/// is exact, and elsewhere git reports paths as UTF-8.
+pub fn path_from_bytes(bytes: &[u8]) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(std::ffi::OsStr::from_bytes(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(bytes).into_owned())
+    }
+}
+
+fn status(
+    git: &dyn GitRunner,
+    dir: &Path,
+    options: &AcquireOptions,
+    version: Option<GitVersion>,
+) -> Result<Vec<StatusEntry>, Error> {
+    // With `-z` paths are neither quoted nor escaped, so they arrive intact,
+    // and the second version of the format reports renames unambiguously
+    let porcelain_v2 = GitVersion::supports(version, GitVersion::PORCELAIN_V2);
+    let mut args = vec![
+        "status",
+        if porcelain_v2 {
+            "--porcelain=v2"
+        } else {
+            "--porcelain"
+        },
+        "-z",
+        options.untracked.as_arg(),
+        "--ignore-submodules=all",
+    ];
+    let excludes = options.exclude_pathspecs();
+    args.extend(excludes.iter().map(String::as_str));
+    let output = git.run_git(dir, &args)?;
+    if porcelain_v2 {
+        parse_status(&output)
+    } else {
+        parse_status_v1(&output)
+    }
+}
+
+/// Parse the output of `git status --porcelain -z`, for releases of git which
+/// predate the second version of the format.
+///
+/// Each record is the two status letters, a space and the path, and a rename
+/// or copy is followed by the path it came from.
+fn parse_status_v1(info: &[u8]) -> Result<Vec<StatusEntry>, Error> {
+    let mut ret = Vec::new();
+    let mut records = info.split(|b| *b == 0).filter(|record| !record.is_empty());
+    while let Some(record) = records.next() {
+        let unexpected = || {
+            TestamentError::UnexpectedOutput(format!(
+                "Unexpected output from git status: {:?}",
+                String::from_utf8_lossy(record)
+            ))
+        };
+        let (index_change, worktree_change, path) = match record {
+            [index_change, worktree_change, b' ', path @ ..] if !path.is_empty() => {
+                (*index_change, *worktree_change, path.to_vec())
+            }
+            _ => return Err(unexpected()),
+        };
+        let (status, original_path) = match (index_change, worktree_change) {
+            (b'?', b'?') => (Untracked, None),
+            (b'!', b'!') => continue,
+            (b'R', _) | (_, b'R') => (
+                Renamed,
+                Some(records.next().ok_or_else(unexpected)?.to_vec()),
+            ),
+            (b'C', _) | (_, b'C') => (
+                Copied,
+                Some(records.next().ok_or_else(unexpected)?.to_vec()),
+            ),
+            (b'A', _) | (_, b'A') => (Added, None),
+            (b'M', _) | (_, b'M') => (Modified, None),
+            (b'D', _) | (_, b'D') => (Deleted, None),
+            _ => continue,
+        };
+        ret.push(StatusEntry {
+            path,
+            status,
+            original_path,
+        });
+    }
+    Ok(ret)
+}
+
+/// Parse the output of `git status --porcelain=v2 -z` into the modifications
+/// it reports, skipping any ignored files it lists.
+///
+/// ```
+/// use git_testament_core::{parse_status, StatusFlag};
+///
+/// let output = b"1 .M N... 100644 100644 100644 abc abc src/main.rs\0\
+///     2 R. N... 100644 100644 100644 abc abc R100 new name.rs\0old.rs\0\
+///     ? notes.txt\0";
+/// let status = parse_status(output).unwrap();
+/// assert!(matches!(status[0].status, StatusFlag::Modified));
+/// assert_eq!(status[1].path, b"new name.rs");
+/// assert_eq!(status[1].original_path.as_deref(), Some(&b"old.rs"[..]));
+/// assert!(matches!(status[2].status, StatusFlag::Untracked));
+/// ```
+pub fn parse_status(info: &[u8]) -> Result<Vec<StatusEntry>, Error> {
+    let mut ret = Vec::new();
+
+    let mut records = info.split(|b| *b == 0).filter(|record| !record.is_empty());
+    while let Some(record) = records.next() {
+        let unexpected = || {
+            TestamentError::UnexpectedOutput(format!(
+                "Unexpected output from git status: {:?}",
+                String::from_utf8_lossy(record)
+            ))
+        };
+        // The path is the last field, after a number of fields which depends
+        // on the kind of record, and may itself contain spaces
+        let fields = match record[0] {
+            b'1' => 9,
+            b'2' => 10,
+            b'u' => 11,
+            b'?' | b'!' => 2,
+            _ => return Err(unexpected()),
+        };
+        let parts: Vec<&[u8]> = record.splitn(fields, |b| *b == b' ').collect();
+        if parts.len() != fields {
+            return Err(unexpected());
+        }
+        let path = parts[fields - 1].to_vec();
+        let (status, original_path) = match (record[0], parts[1]) {
+            (b'?', _) => (Untracked, None),
+            (b'!', _) => continue,
+            // A rename or copy is followed by the path it came from
+            (b'2', [index_change, worktree_change]) => {
+                let original = records.next().ok_or_else(unexpected)?.to_vec();
+                if *index_change == b'R' || *worktree_change == b'R' {
+                    (Renamed, Some(original))
+                } else {
+                    (Copied, Some(original))
+                }
+            }
+            (_, [index_change, worktree_change]) => match (index_change, worktree_change) {
+                (b'A', _) | (_, b'A') => (Added, None),
+                (b'M', _) | (_, b'M') => (Modified, None),
+                (b'D', _) | (_, b'D') => (Deleted, None),
+                _ => continue,
+            },
+            _ => return Err(unexpected()),
+        };
+        ret.push(StatusEntry {
+            path,
+            status,
+            original_path,
+        });
+    }
+
+    Ok(ret)
+}
+
+/// The diff of the changes to tracked files, which is empty if there are no
+/// such changes.
+///
+/// Everything which configuration could change about the diff is fixed, so
+/// that the same changes always give the same diff, and so the same digest.
+fn worktree_diff(
+    git: &dyn GitRunner,
+    dir: &Path,
+    options: &AcquireOptions,
+) -> Result<Vec<u8>, Error> {
+    let mut args = vec![
+        "diff",
+        "HEAD",
+        "--binary",
+        "--full-index",
+        "--no-color",
+        "--no-ext-diff",
+        "--no-textconv",
+        "--no-renames",
+        "--diff-algorithm=myers",
+        "--unified=3",
+        "--src-prefix=a/",
+        "--dst-prefix=b/",
+        "--ignore-submodules=all",
+    ];
+    let excludes = options.exclude_pathspecs();
+    args.extend(excludes.iter().map(String::as_str));
+    git.run_git(dir, &args)
+}
+
+/// The object id git gives `diff` as a blob, or `None` if it is empty
+fn worktree_digest(git: &dyn GitRunner, dir: &Path, diff: &[u8]) -> Result<Option<String>, Error> {
+    if diff.is_empty() {
+        return Ok(None);
+    }
+    let id = git.run_git_with_input(dir, &["hash-object", "--stdin"], diff)?;
+    Ok(Some(String::from_utf8(id)?.trim_end().to_owned()))
+}
+
+/// Whether any files stored with Git LFS are still pointers because their
+/// objects were never fetched.
+///
+/// Only files which the repository's attributes send through the LFS filter
+/// are considered, so git-lfs need not be installed if there are none.
+fn lfs_incomplete(git: &dyn GitRunner, dir: &Path) -> Result<bool, Error> {
+    let lfs_files = git.run_git(dir, &["ls-files", "-z", ":(attr:filter=lfs)"])?;
+    if lfs_files.is_empty() {
+        return Ok(false);
+    }
+    // Each line is the object id, then `*` if the object is in the working
+    // tree or `-` if only its pointer is, then the path
+    let output = text_output(git.run_git(dir, &["lfs", "ls-files"])?)?;
+    Ok(output
+        .lines()
+        .any(|line| line.split(' ').nth(1) == Some("-")))
+}
+
+/// Remove any status entries whose paths are marked `export-ignore` by the
+/// repository's git attributes.
+fn filter_export_ignored(
+    git: &dyn GitRunner,
+    dir: &Path,
+    status: Vec<StatusEntry>,
+) -> Result<Vec<StatusEntry>, Error> {
+    if status.is_empty() {
+        return Ok(status);
+    }
+    let mut input = Vec::new();
+    for entry in &status {
+        input.extend_from_slice(&entry.path);
+        input.push(0);
+    }
+    let output = git.run_git_with_input(
+        dir,
+        &["check-attr", "-z", "--stdin", "export-ignore"],
+        &input,
+    )?;
+    // Output is a sequence of NUL terminated path, attribute, value triples
+    let fields: Vec<&[u8]> = output.split(|b| *b == 0).collect();
+    let ignored: Vec<&[u8]> = fields
+        .chunks_exact(3)
+        .filter(|triple| triple[2] == b"set")
+        .map(|triple| triple[0])
+        .collect();
+    Ok(status
+        .into_iter()
+        .filter(|entry| !ignored.contains(&&entry.path[..]))
+        .collect())
+}
+
+/// Information about the commit at `HEAD`
+#[derive(Clone, Debug)]
+pub struct CommitInfo {
+    /// The full commit hash
+    pub id: String,
+    /// The commit date, formatted as `YYYY-MM-DD`
+    pub date: String,
+    /// The commit time as an RFC 3339 timestamp, in the committer's timezone
+    pub timestamp: String,
+    /// The tag chosen by `git describe`, or empty if there was none
+    pub tag: String,
+    /// The number of commits since the tag
+    pub distance: usize,
+    /// The number of commits reachable from the commit, including itself.
+    /// In a shallow clone this only counts the commits which were fetched.
+    pub commit_count: usize,
+    /// The output of `git describe --tags --long --dirty`, verbatim, if there
+    /// was a tag to describe the commit with
+    pub describe: Option<String>,
+    /// The first line of the message of the tag chosen by `git describe`, and
+    /// the date it was tagged formatted as `YYYY-MM-DD`, if it is an annotated
+    /// tag and [`AcquireOptions::tag_message`] was requested
+    pub tag_message: Option<(String, String)>,
+    /// The most recent tag matching [`AcquireOptions::release_tag`] and the
+    /// number of commits since it
+    pub release: Option<(String, usize)>,
+    /// The earliest tag whose history contains the commit, and the number of
+    /// commits in that tag which are not in the commit, if
+    /// [`AcquireOptions::describe_mode`] was [`DescribeMode::Contains`].
+    ///
+    /// This is `None` if no tag has been made since the commit.
+    pub contained_in: Option<(String, usize)>,
+    /// The length to which `git describe` would abbreviate the commit hash
+    pub abbrev: usize,
+    /// The full hashes of the parents of the commit, first parent first, if
+    /// [`AcquireOptions::parents`] was requested
+    pub parents: Vec<String>,
+    /// Whether the commit is a merge, with more than one parent
+    pub merge: bool,
+    /// Every tag which points directly at the commit, rather than just the
+    /// one chosen by `git describe`, if [`AcquireOptions::tags_at_head`] was
+    /// requested
+    pub tags_at_head: Vec<String>,
+    /// Whether the commit has a good signature, if
+    /// [`AcquireOptions::verify_signature`] was requested
+    pub signed: bool,
+    /// The signer of the commit, if it has a good signature
+    pub signer: Option<String>,
+    /// The name of an annotated tag at the commit which has a good signature,
+    /// and who it was signed by if that is known, if
+    /// [`AcquireOptions::verify_tags`] was requested
+    pub signed_tag: Option<(String, Option<String>)>,
+}
+
+/// Information about a remote-tracking branch
+#[derive(Clone, Debug)]
+pub struct RemoteInfo {
+    /// The short name of the remote-tracking branch, e.g. `origin/main`
+    pub name: String,
+    /// The full commit hash of the branch
+    pub id: String,
+    /// The commit date, formatted as `YYYY-MM-DD`
+    pub date: String,
+}
+
+/// Find the remote-tracking branch which a repository with no commits of its
+/// own is most likely to be based on.  This is the remote's `HEAD` if it is
+/// known, otherwise the first remote-tracking branch.
+fn remote_default_branch(git: &dyn GitRunner, git_dir: &Path) -> Result<Option<RemoteInfo>, Error> {
+    let refs = text_output(git.run_git(
+        git_dir,
+        &[
+            "for-each-ref",
+            "--format=%(refname:short) %(symref:short)",
+            "refs/remotes",
+        ],
+    )?)?;
+    let mut candidates = refs.lines().filter_map(|line| line.split_once(' '));
+    let name = match candidates.clone().find(|(_, target)| !target.is_empty()) {
+        // A remote HEAD, which points at the default branch
+        Some((_, target)) => target,
+        None => match candidates.next() {
+            Some((name, _)) => name,
+            None => return Ok(None),
+        },
+    };
+    let commit = log_commit(git, git_dir, name)?;
+    Ok(Some(RemoteInfo {
+        name: name.to_owned(),
+        id: commit.id,
+        date: format_commit_date(commit.time, commit.offset)?,
+    }))
+}
+
+/// The state of a submodule
+#[derive(Clone, Debug)]
+pub struct SubmoduleEntry {
+    /// The path of the submodule relative to the top of the repository
+    pub path: String,
+    /// The full hash of the commit checked out in the submodule
+    pub commit: String,
+    /// Whether the submodule has modifications, or has a different commit
+    /// checked out to that recorded in the superproject
+    pub dirty: bool,
+}
+
+/// Find the state of the initialised submodules, recursively
+fn submodules(git: &dyn GitRunner, git_dir: &Path) -> Result<Vec<SubmoduleEntry>, Error> {
+    let output = text_output(git.run_git(git_dir, &["submodule", "status", "--recursive"])?)?;
+    let mut ret = Vec::new();
+    for line in output.lines() {
+        let (flag, rest) = line.split_at(1);
+        if flag == "-" {
+            // Not initialised, so there is nothing checked out
+            continue;
+        }
+        let (commit, rest) = rest.split_once(' ').ok_or("Bad submodule status")?;
+        let path = rest.rsplit_once(" (").map_or(rest, |(path, _)| path);
+        let modified = !git
+            .run_git(&git_dir.join(path), &["status", "--porcelain"])?
+            .is_empty();
+        ret.push(SubmoduleEntry {
+            path: path.to_owned(),
+            commit: commit.to_owned(),
+            dirty: flag != " " || modified,
+        });
+    }
+    Ok(ret)
+}
+
+/// The version control system which information was acquired from
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Vcs {
+    #[default]
+    Git,
+    Mercurial,
+    Jujutsu,
+    /// No repository could be read, and the information came only from the
+    /// [`OVERRIDE_ENV_VARS`]
+    Environment,
+    /// No repository could be read, and the commit came from the
+    /// [`AcquireOptions::revision_file`], as in a Nix build
+    RevisionFile,
+}
+
+/// Everything we learned about a repository
+///
+/// Despite the name, this may also describe a Mercurial or Jujutsu
+/// repository, in which case the git specific fields are left empty.
+#[derive(Clone, Debug)]
+pub struct GitInformation {
+    /// The version control system the repository belongs to
+    pub vcs: Vcs,
+    /// The top level of the working tree
+    pub toplevel: PathBuf,
+    /// The branch name, if one could be determined
+    pub branch: Option<String>,
+    /// Whether `HEAD` is detached, as it is when CI systems check out a
+    /// commit rather than a branch.  The branch is then the nearest branch or
+    /// tag, or the CI system's branch if [`AcquireOptions::ci_branch`] was
+    /// requested.
+    pub detached: bool,
+    /// Information about `HEAD`, or `None` if there are no commits yet
+    pub commitinfo: Option<CommitInfo>,
+    /// If there are no commits yet, and [`AcquireOptions::remote_fallback`]
+    /// was requested, the remote branch which the repository will most likely
+    /// be based on once it is fetched or checked out
+    pub remote: Option<RemoteInfo>,
+    /// Whether the repository is a shallow clone, in which case tags and
+    /// distances may be missing or wrong
+    pub shallow: bool,
+    /// Whether the working tree is a linked worktree, created by
+    /// `git worktree add`, rather than the repository's main working tree
+    pub worktree: bool,
+    /// Whether the repository is bare, with no working tree, as when `GIT_DIR`
+    /// names a bare mirror and the source was exported from it.  There are
+    /// then no modifications, and the `toplevel` is the repository itself.
+    pub bare: bool,
+    /// The URL of the remote named by [`AcquireOptions::remote_url`], with
+    /// any credentials removed
+    pub remote_url: Option<String>,
+    /// The modifications to the working tree
+    pub status: Vec<StatusEntry>,
+    /// Whether the modifications to the working tree were collected.  If
+    /// [`AcquireOptions::skip_status`] was requested, or the status could not
+    /// be determined, then the `status` is empty but the working tree may
+    /// still have been modified.
+    pub status_collected: bool,
+    /// The submodules, if [`AcquireOptions::submodules`] was requested
+    pub submodules: Vec<SubmoduleEntry>,
+    /// If [`AcquireOptions::worktree_digest`] was requested and tracked files
+    /// have been changed, a digest of those changes, so that builds from
+    /// different dirty working trees can be told apart.  This is the object
+    /// id git would give a blob of `git diff HEAD`, ignoring the `ignore`
+    /// patterns, and covers the whole repository even when the status has
+    /// been [scoped](GitInformation::scoped_to).
+    pub worktree_digest: Option<String>,
+    /// If [`AcquireOptions::embed_diff`] was requested and tracked files have
+    /// been changed, the start of `git diff HEAD`, cut off after the number
+    /// of bytes requested.  Like the digest, this ignores the `ignore`
+    /// patterns and untracked files, and covers the whole repository.
+    pub diff: Option<Vec<u8>>,
+    /// If [`AcquireOptions::lfs`] was requested, whether any files stored
+    /// with Git LFS were only pointers, because their objects had not been
+    /// fetched, so that the build may have used placeholders for them.  This
+    /// is assumed if they could not be checked, for example because git-lfs
+    /// is not installed.
+    pub lfs_incomplete: bool,
+    /// The version of git which was run, as `git --version` describes it
+    /// (for example `2.39.3 (Apple Git-145)`), if it could be determined.
+    /// Older releases are given older arguments where they must be, so this
+    /// helps to explain any differences in what was acquired.
+    pub git_version: Option<String>,
+    /// If there was no repository, but the crate is a package made by
+    /// `cargo package` (for example one downloaded from crates.io), the
+    /// commit it was packaged from.  The other fields are then all empty.
+    ///
+    /// This is also the commit read from a revision file, in which case the
+    /// [`GitInformation::vcs`] is [`Vcs::RevisionFile`].
+    pub cargo_vcs_info: Option<CargoVcsInfo>,
+    /// The [`OVERRIDE_ENV_VARS`] which took precedence over what was
+    /// acquired, see [`GitInformation::with_overrides`]
+    pub overridden: Vec<&'static str>,
+    /// Problems which were encountered while acquiring the information, but
+    /// which did not prevent it, for example a commit signature which could
+    /// not be checked.  Each of these has also been logged.
+    pub warnings: Vec<String>,
+}
+
+/// Log a problem which does not prevent information from being acquired, and
+/// remember it so that it can be reported to the user.
+fn note(warnings: &mut Vec<String>, message: String) {
+    warn!("{message}");
+    warnings.push(message);
+}
+
+/// The commit recorded by `cargo package` in `.cargo_vcs_info.json`
+#[derive(Clone, Debug)]
+pub struct CargoVcsInfo {
+    /// The full hash of the commit which the package was made from
+    pub sha1: String,
+    /// Whether the working tree had modifications when it was packaged
+    pub dirty: bool,
+}
+
+/// Read the `.cargo_vcs_info.json` which `cargo package` places at the root
+/// of a package made from a git repository.
+fn cargo_vcs_info(start: &Path) -> Option<CargoVcsInfo> {
+    let json = fs::read_to_string(start.join(".cargo_vcs_info.json")).ok()?;
+    // The file is small and written by cargo, so there is no need for a full
+    // JSON parser just to pick out two values
+    let value = |key: &str| {
+        let quoted = format!("\"{key}\"");
+        let rest = &json[json.find(&quoted)? + quoted.len()..];
+        Some(rest.trim_start().strip_prefix(':')?.trim_start())
+    };
+    let sha1 = value("sha1")?.strip_prefix('"')?;
+    let sha1 = &sha1[..sha1.find('"')?];
+    if sha1.is_empty() || !sha1.bytes().all(|b| b.is_ascii_hexdigit()) {
+        warn!("Ignoring .cargo_vcs_info.json with unexpected sha1 {sha1:?}");
+        return None;
+    }
+    Some(CargoVcsInfo {
+        sha1: sha1.to_owned(),
+        dirty: value("dirty").is_some_and(|dirty| dirty.starts_with("true")),
+    })
+}
+
+/// Read the commit from the `revision_file`, or in a Nix build from the
+/// [`NIX_REVISION_FILE`].  Following Nix's `dirtyRev`, a hash ending in
+/// `-dirty` means the sources were modified.
+fn revision_file(start: &Path, options: &AcquireOptions) -> Option<CargoVcsInfo> {
+    let path = match &options.revision_file {
+        Some(path) => start.join(path),
+        None if std::env::var_os("NIX_BUILD_TOP").is_some() => start.join(NIX_REVISION_FILE),
+        None => return None,
+    };
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            // Only a file which was asked for is missed
+            if options.revision_file.is_some() {
+                warn!("Unable to read {}: {e}", path.display());
+            }
+            return None;
+        }
+    };
+    let revision = contents.trim();
+    let (sha1, dirty) = match revision.strip_suffix("-dirty") {
+        Some(sha1) => (sha1, true),
+        None => (revision, false),
+    };
+    if sha1.is_empty() || !sha1.bytes().all(|b| b.is_ascii_hexdigit()) {
+        warn!(
+            "Ignoring {} with unexpected contents {revision:?}",
+            path.display()
+        );
+        return None;
+    }
+    Some(CargoVcsInfo {
+        sha1: sha1.to_owned(),
+        dirty,
+    })
+}
+
+/// Wait for a query run on another thread, passing on any panic
+fn join<T>(handle: std::thread::ScopedJoinHandle<'_, T>) -> T {
+    handle
+        .join()
+        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+}
+
+/// Acquire the information about the commit at `HEAD`.
+///
+/// The queries about the commit are run alongside one another.
+fn commit_info(
+    git: &dyn GitRunner,
+    git_dir: &Path,
+    options: &AcquireOptions,
+    head: LoggedCommit,
+    shallow: bool,
+    bare: bool,
+    warnings: &mut Vec<String>,
+) -> Option<CommitInfo> {
+    let LoggedCommit {
+        id: commit_id,
+        time: commit_time,
+        offset: commit_offset,
+        parents,
+        ..
+    } = head;
+    let dates = format_commit_date(commit_time, commit_offset)
+        .and_then(|date| Ok((date, format_commit_timestamp(commit_time, commit_offset)?)));
+    let (commit_date, commit_timestamp) = match dates {
+        Ok(dates) => dates,
+        Err(e) => {
+            // Without a date there is little to say about the commit, so
+            // treat the repository as though it had none
+            note(
+                warnings,
+                format!("Unable to understand the date of commit {commit_id}: {e}"),
+            );
+            return None;
+        }
+    };
+
+    std::thread::scope(|scope| {
+        let commit = commit_id.as_str();
+        // `--dirty` only applies to HEAD, which is the commit being described,
+        // and the tag and distance can be taken from the same output.  A bare
+        // repository has no working tree to be dirty.
+        let described = scope.spawn(move || {
+            let describe_args = options.describe_args();
+            let mut args = vec!["describe", "--tags", "--long"];
+            if !bare {
+                args.push("--dirty");
+            }
+            args.extend(describe_args.iter().map(String::as_str));
+            git.run_git(git_dir, &args)
+                .map(|res| String::from_utf8_lossy(&res).trim_end().to_owned())
+        });
+        let release = options.release_tag.as_ref().map(|pattern| {
+            let release =
+                scope.spawn(move || describe(git, git_dir, commit, &["--match", pattern]));
+            (pattern, release)
+        });
+        let contains = (options.describe_mode == DescribeMode::Contains)
+            .then(|| scope.spawn(move || contained_in(git, git_dir, options, commit)));
+        let count = scope.spawn(move || commit_count(git, git_dir, commit));
+        let abbrev = scope.spawn(move || git.run_git(git_dir, &["rev-parse", "--short", commit]));
+        let signature = options
+            .verify_signature
+            .then(|| scope.spawn(move || verify_signature(git, git_dir, options, commit)));
+        let signed_tag = options
+            .verify_tags
+            .then(|| scope.spawn(move || verify_tags(git, git_dir, options, commit)));
+        let tags_at_head = options
+            .tags_at_head
+            .then(|| scope.spawn(move || tags_at(git, git_dir, commit)));
+
+        let (tag, distance, dirty_describe) = match join(described) {
+            Ok(res) => match parse_describe(res.strip_suffix("-dirty").unwrap_or(&res)) {
+                Ok((tag, distance)) => (tag, distance, Some(res)),
+                Err(e) => {
+                    note(warnings, format!("Unable to describe the commit: {e}"));
+                    ("".to_owned(), 0, None)
+                }
+            },
+            Err(e) if shallow => {
+                // Tags are rarely reachable from a shallow clone
+                log::debug!("No tag info found in shallow repository: {e}");
+                ("".to_owned(), 0, None)
+            }
+            Err(e) => {
+                warn!("No tag info found!\n{:?}", e);
+                ("".to_owned(), 0, None)
+            }
+        };
+
+        // This needs the tag, so cannot be run alongside the rest
+        let tag_message = if options.tag_message && !tag.is_empty() {
+            match tag_message(git, git_dir, &tag) {
+                Ok(message) => message,
+                Err(e) => {
+                    note(warnings, format!("Unable to read tag {tag}: {e}"));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let release = release.and_then(|(pattern, release)| match join(release) {
+            Ok(res) => match parse_describe(&res) {
+                Ok(release) => Some(release),
+                Err(e) => {
+                    note(
+                        warnings,
+                        format!("Unable to describe the commit with {pattern}: {e}"),
+                    );
+                    None
+                }
+            },
+            Err(e) => {
+                warn!("No release tag matching {pattern} found!\n{:?}", e);
+                None
+            }
+        });
+
+        let contained_in = match contains.map(join) {
+            Some(Ok(contained_in)) => Some(contained_in),
+            Some(Err(e)) => {
+                // This is expected of any commit made since the latest tag
+                log::debug!("No tag contains the commit: {e}");
+                None
+            }
+            None => None,
+        };
+
+        let count = match join(count) {
+            Ok(count) => count,
+            Err(e) => {
+                note(warnings, format!("Unable to count commits: {e}"));
+                0
+            }
+        };
+
+        let abbrev = match join(abbrev) {
+            Ok(short) => String::from_utf8_lossy(&short).trim_end().len(),
+            Err(e) => {
+                note(warnings, format!("Unable to abbreviate commit hash: {e}"));
+                DEFAULT_ABBREV
+            }
+        };
+
+        let merge = parents.len() > 1;
+        let parents = if options.parents { parents } else { vec![] };
+
+        let tags_at_head = match tags_at_head.map(join) {
+            Some(Ok(tags)) => tags,
+            Some(Err(e)) => {
+                note(
+                    warnings,
+                    format!("Unable to list the tags at the commit: {e}"),
+                );
+                vec![]
+            }
+            None => vec![],
+        };
+
+        let (signed, signer) = match signature.map(join) {
+            Some(Ok(signature)) => signature,
+            Some(Err(e)) => {
+                note(warnings, format!("Unable to verify commit signature: {e}"));
+                (false, None)
+            }
+            None => (false, None),
+        };
+
+        let signed_tag = match signed_tag.map(join) {
+            Some(Ok(signed_tag)) => signed_tag,
+            Some(Err(e)) => {
+                note(warnings, format!("Unable to verify tags: {e}"));
+                None
+            }
+            None => None,
+        };
+
+        Some(CommitInfo {
+            id: commit_id.clone(),
+            date: commit_date,
+            timestamp: commit_timestamp,
+            tag,
+            distance,
+            commit_count: count,
+            describe: dirty_describe,
+            tag_message,
+            release,
+            contained_in,
+            abbrev,
+            parents,
+            merge,
+            tags_at_head,
+            signed,
+            signer,
+            signed_tag,
+        })
+    })
+}
+
+impl GitInformation {
+    /// Acquire information about the repository containing `start`.
+    ///
+    /// If `start` is not inside a git repository, but is the root of a
+    /// package made by `cargo package`, then only the commit recorded in its
+    /// `.cargo_vcs_info.json` is returned, see [`GitInformation::cargo_vcs_info`].
+    /// Failing that, the commit is read from the revision file if there is
+    /// one, see [`AcquireOptions::revision_file`].  Otherwise this fails if `start` is not inside a git repository, or if
+    /// `git` cannot be run at all.
+    pub fn acquire(start: &Path, options: &AcquireOptions) -> Result<Self, Error> {
+        Self::acquire_with(&SystemGit, start, options)
+    }
+
+    /// Acquire information about the repository containing `start`, as
+    /// [`GitInformation::acquire`] does, running git with `git`.
+    ///
+    /// This is mostly useful for testing how the output of git is understood,
+    /// with a runner which replays recorded output, see
+    /// [`GitRunner::run_git_with_env`] for verifying signatures.
+    pub fn acquire_with(
+        git: &dyn GitRunner,
+        start: &Path,
+        options: &AcquireOptions,
+    ) -> Result<Self, Error> {
+        let mut warnings = Vec::new();
+        let (git_dir, bare) = match find_git_dir(git, start) {
+            Ok(found) => found,
+            Err(e) => match cargo_vcs_info(start)
+                .map(|vcs_info| (Vcs::Git, vcs_info))
+                .or_else(|| Some((Vcs::RevisionFile, revision_file(start, options)?)))
+            {
+                Some((vcs, vcs_info)) => {
+                    return Ok(Self {
+                        git_version: None,
+                        vcs,
+                        toplevel: start.to_owned(),
+                        branch: None,
+                        detached: false,
+                        commitinfo: None,
+                        remote: None,
+                        shallow: false,
+                        worktree: false,
+                        bare: false,
+                        remote_url: None,
+                        status: vec![],
+                        // A dirty revision file says nothing of what was
+                        // modified, so the sources are not claimed to be clean
+                        status_collected: vcs != Vcs::RevisionFile || !vcs_info.dirty,
+                        submodules: vec![],
+                        worktree_digest: None,
+                        diff: None,
+                        lfs_incomplete: false,
+                        cargo_vcs_info: Some(vcs_info),
+                        overridden: vec![],
+                        warnings: vec![],
+                    });
+                }
+                None => return Err(e),
+            },
+        };
+        let (git_version, version) = match git_version(git, &git_dir) {
+            Some((description, version)) => (Some(description), version),
+            None => (None, None),
+        };
+        if let Some(version) = version.filter(|version| *version < GitVersion::OLDEST) {
+            note(
+                &mut warnings,
+                format!(
+                    "git {}.{} is older than {}.{}, so some information may be missing",
+                    version.0,
+                    version.1,
+                    GitVersion::OLDEST.0,
+                    GitVersion::OLDEST.1
+                ),
+            );
+        }
+        // The queries are independent, and each may take a while in a large
+        // repository, so run them alongside one another
+        std::thread::scope(|scope| {
+            let git_dir = git_dir.as_path();
+            let worktree = scope.spawn(move || is_linked_worktree(git, git_dir));
+            // There is no working tree to look at in a bare repository
+            let status = (!bare && !options.skip_status)
+                .then(|| scope.spawn(move || status(git, git_dir, options, version)));
+            // The digest and the embedded diff are both taken from one diff
+            let diff =
+                ((options.worktree_digest || options.embed_diff.is_some()) && !bare).then(|| {
+                    scope.spawn(move || {
+                        let diff = worktree_diff(git, git_dir, options)?;
+                        let digest = if options.worktree_digest {
+                            worktree_digest(git, git_dir, &diff)?
+                        } else {
+                            None
+                        };
+                        Ok::<_, Error>((digest, diff))
+                    })
+                });
+            let lfs_incomplete =
+                (options.lfs && !bare).then(|| scope.spawn(move || lfs_incomplete(git, git_dir)));
+            let submodules = (options.submodules && !bare)
+                .then(|| scope.spawn(move || submodules(git, git_dir)));
+            let remote_url = options.remote_url.as_ref().map(|remote| {
+                let url = scope.spawn(move || git.run_git(git_dir, &["remote", "get-url", remote]));
+                (remote, url)
+            });
+
+            let shallow = is_shallow(git, git_dir, version);
+            let head = match log_commit(git, git_dir, "HEAD") {
+                Ok(head) => Some(head),
+                Err(e) => {
+                    warn!("No commit at HEAD: {e}");
+                    None
+                }
+            };
+            // The log names the branch unless `HEAD` is detached or unborn
+            let branch = match head.as_ref().and_then(|head| head.branch.clone()) {
+                Some(branch) => Ok((Some(branch), false)),
+                None => current_branch(git, git_dir),
+            };
+            let mut commit_warnings = Vec::new();
+            let commitinfo = head.and_then(|head| {
+                commit_info(
+                    git,
+                    git_dir,
+                    options,
+                    head,
+                    shallow,
+                    bare,
+                    &mut commit_warnings,
+                )
+            });
+
+            let worktree = match join(worktree) {
+                Ok(worktree) => worktree,
+                Err(e) => {
+                    note(
+                        &mut warnings,
+                        format!(
+                            "Unable to determine if the working tree is a linked worktree: {e}"
+                        ),
+                    );
+                    false
+                }
+            };
+            let (branch, detached) = match branch {
+                Ok(b) => b,
+                Err(e) => {
+                    note(
+                        &mut warnings,
+                        format!("Unable to determine branch name: {e}"),
+                    );
+                    (None, false)
+                }
+            };
+            let branch = match (detached && options.ci_branch).then(ci_branch) {
+                Some(Some(ci_branch)) => Some(ci_branch),
+                _ => branch,
+            };
+            warnings.extend(commit_warnings);
+
+            let remote = if commitinfo.is_none() && options.remote_fallback {
+                match remote_default_branch(git, git_dir) {
+                    Ok(remote) => remote,
+                    Err(e) => {
+                        note(
+                            &mut warnings,
+                            format!("Unable to determine remote default branch: {e}"),
+                        );
+                        None
+                    }
+                }
+            } else {
+                None
+            };
+
+            let remote_url = remote_url.and_then(|(remote, url)| match join(url) {
+                Ok(url) => Some(strip_credentials(String::from_utf8_lossy(&url).trim())),
+                Err(e) => {
+                    note(
+                        &mut warnings,
+                        format!("Unable to determine URL of remote {remote}: {e}"),
+                    );
+                    None
+                }
+            });
+
+            // Without a commit there is nothing to compare the working tree
+            // with, so the status and digest are not needed after all
+            let mut status_collected = bare || !options.skip_status || commitinfo.is_none();
+            let status = match status.filter(|_| commitinfo.is_some()) {
+                Some(status) => {
+                    let status = join(status).and_then(|status| {
+                        if options.respect_export_ignore {
+                            filter_export_ignored(git, git_dir, status)
+                        } else {
+                            Ok(status)
+                        }
+                    });
+                    match status {
+                        Ok(status) => status,
+                        Err(e) => {
+                            note(
+                                &mut warnings,
+                                format!("Unable to determine the modifications: {e}"),
+                            );
+                            status_collected = false;
+                            vec![]
+                        }
+                    }
+                }
+                None => vec![],
+            };
+
+            let (worktree_digest, diff) = match diff.map(join) {
+                Some(Ok((digest, mut diff))) if commitinfo.is_some() => {
+                    let diff = match options.embed_diff {
+                        Some(limit) if !diff.is_empty() => {
+                            diff.truncate(limit);
+                            Some(diff)
+                        }
+                        _ => None,
+                    };
+                    (digest, diff)
+                }
+                Some(Err(e)) if commitinfo.is_some() => {
+                    note(
+                        &mut warnings,
+                        format!("Unable to diff the working tree: {e}"),
+                    );
+                    (None, None)
+                }
+                _ => (None, None),
+            };
+
+            let lfs_incomplete = match lfs_incomplete.map(join) {
+                Some(Ok(incomplete)) => incomplete,
+                Some(Err(e)) => {
+                    note(
+                        &mut warnings,
+                        format!("Unable to check the Git LFS objects, so assuming some are missing: {e}"),
+                    );
+                    true
+                }
+                None => false,
+            };
+
+            let submodules = match submodules.map(join) {
+                Some(Ok(submodules)) => submodules,
+                Some(Err(e)) => {
+                    note(
+                        &mut warnings,
+                        format!("Unable to determine submodule status: {e}"),
+                    );
+                    vec![]
+                }
+                None => vec![],
+            };
+
+            Ok(Self {
+                vcs: Vcs::Git,
+                toplevel: git_dir.to_owned(),
+                branch,
+                detached,
+                commitinfo,
+                remote,
+                shallow,
+                worktree,
+                bare,
+                remote_url,
+                status,
+                status_collected,
+                submodules,
+                worktree_digest,
+                diff,
+                lfs_incomplete,
+                git_version,
+                cargo_vcs_info: None,
+                overridden: vec![],
+                warnings,
+            })
+        })
+    }
+
+    /// Acquire information about the Mercurial repository containing `start`.
+    ///
+    /// Only the options which make sense for Mercurial are honoured, so for
+    /// example submodules and `export-ignore` attributes are not considered.
+    pub fn acquire_mercurial(start: &Path, options: &AcquireOptions) -> Result<Self, Error> {
+        hg::acquire(start, options)
+    }
+
+    /// Acquire information about the Jujutsu repository containing `start`.
+    ///
+    /// The parent of the working copy commit is treated as the commit which
+    /// was built, and the changes in the working copy commit as the
+    /// modifications to the working tree.  The most recent tag which is an
+    /// ancestor is used as the tag.  Only the options which make sense for
+    /// Jujutsu are honoured.
+    pub fn acquire_jujutsu(start: &Path, options: &AcquireOptions) -> Result<Self, Error> {
+        jj::acquire(start, options)
+    }
+
+    /// Override what was acquired with the [`OVERRIDE_ENV_VARS`], as read by
+    /// `lookup`, for build environments such as Nix or Bazel sandboxes where
+    /// the repository cannot be read but the orchestrator knows its state.
+    ///
+    /// * `GIT_TESTAMENT_COMMIT` -> The full commit hash.  If this is not the
+    ///   commit which was acquired, then nothing else acquired about the
+    ///   commit or the working tree is kept.
+    /// * `GIT_TESTAMENT_DATE` -> The commit date, as `YYYY-MM-DD` or as an
+    ///   RFC 3339 timestamp.  This is needed along with any other commit.
+    /// * `GIT_TESTAMENT_TAG` -> The tag describing the commit
+    /// * `GIT_TESTAMENT_DISTANCE` -> The number of commits since the tag,
+    ///   zero if the tag is given without it
+    /// * `GIT_TESTAMENT_BRANCH` -> The branch name
+    /// * `GIT_TESTAMENT_DIRTY` -> `true` or `false` (or `1` or `0`), whether
+    ///   the working tree was modified.  Unless the modifications were also
+    ///   acquired, a modified working tree only has unknown modifications,
+    ///   see [`GitInformation::status_collected`].
+    ///
+    /// If none are set, `acquired` is returned untouched.  Otherwise, if
+    /// acquisition failed, the information comes from the overrides alone,
+    /// with [`Vcs::Environment`], and the working tree is unknown unless
+    /// `GIT_TESTAMENT_DIRTY` is given.  The variables which were applied are
+    /// recorded in [`GitInformation::overridden`].
+    ///
+    /// ```
+    /// use std::path::Path;
+    /// use git_testament_core::{GitInformation, TestamentError, Vcs};
+    ///
+    /// let start = Path::new("/build/source");
+    /// let info = GitInformation::with_overrides(
+    ///     Err(TestamentError::NoRepository(start.to_owned())),
+    ///     start,
+    ///     |var| match var {
+    ///         "GIT_TESTAMENT_COMMIT" => Some("763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3".to_owned()),
+    ///         "GIT_TESTAMENT_DATE" => Some("2019-04-02".to_owned()),
+    ///         "GIT_TESTAMENT_TAG" => Some("v1.0.0".to_owned()),
+    ///         _ => None,
+    ///     },
+    /// )
+    /// .unwrap();
+    /// assert_eq!(info.vcs, Vcs::Environment);
+    /// assert_eq!(info.commitinfo.unwrap().tag, "v1.0.0");
+    /// assert!(!info.status_collected);
+    /// ```
+    pub fn with_overrides(
+        acquired: Result<Self, Error>,
+        start: &Path,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, Error> {
+        let overrides: Vec<(&'static str, String)> = OVERRIDE_ENV_VARS
+            .iter()
+            .filter_map(|&var| Some((var, lookup(var)?)))
+            .collect();
+        if overrides.is_empty() {
+            return acquired;
+        }
+        let value = |var: &str| {
+            overrides
+                .iter()
+                .find(|(name, _)| *name == var)
+                .map(|(_, value)| value.as_str())
+        };
+
+        let mut info = match acquired {
+            Ok(info) => info,
+            Err(e) => {
+                log::debug!("Only recording the overrides, since acquisition failed: {e}");
+                Self {
+                    git_version: None,
+                    vcs: Vcs::Environment,
+                    toplevel: start.to_owned(),
+                    branch: None,
+                    detached: false,
+                    commitinfo: None,
+                    remote: None,
+                    shallow: false,
+                    worktree: false,
+                    bare: false,
+                    remote_url: None,
+                    status: vec![],
+                    status_collected: false,
+                    submodules: vec![],
+                    worktree_digest: None,
+                    diff: None,
+                    lfs_incomplete: false,
+                    cargo_vcs_info: None,
+                    overridden: vec![],
+                    warnings: vec![],
+                }
+            }
+        };
+
+        if let Some(id) = value("GIT_TESTAMENT_COMMIT") {
+            if info.commitinfo.as_ref().is_none_or(|ci| ci.id != id) {
+                if value("GIT_TESTAMENT_DATE").is_none() {
+                    return Err(TestamentError::MissingEnv("GIT_TESTAMENT_DATE"));
+                }
+                info.commitinfo = Some(CommitInfo {
+                    id: id.to_owned(),
+                    date: String::new(),
+                    timestamp: String::new(),
+                    tag: String::new(),
+                    distance: 0,
+                    commit_count: 0,
+                    describe: None,
+                    tag_message: None,
+                    release: None,
+                    contained_in: None,
+                    abbrev: DEFAULT_ABBREV,
+                    parents: vec![],
+                    merge: false,
+                    tags_at_head: vec![],
+                    signed: false,
+                    signer: None,
+                    signed_tag: None,
+                });
+                // The working tree was compared with some other commit
+                info.status.clear();
+                info.status_collected = false;
+                info.worktree_digest = None;
+                info.diff = None;
+                info.cargo_vcs_info = None;
+            }
+        }
+
+        let describes_commit = [
+            "GIT_TESTAMENT_DATE",
+            "GIT_TESTAMENT_TAG",
+            "GIT_TESTAMENT_DISTANCE",
+        ];
+        match &mut info.commitinfo {
+            Some(commitinfo) => {
+                if let Some(date) = value("GIT_TESTAMENT_DATE") {
+                    (commitinfo.date, commitinfo.timestamp) = parse_override_date(date)?;
+                }
+                if let Some(tag) = value("GIT_TESTAMENT_TAG") {
+                    commitinfo.tag = tag.to_owned();
+                    commitinfo.distance = 0;
+                    commitinfo.tag_message = None;
+                    commitinfo.contained_in = None;
+                }
+                if let Some(distance) = value("GIT_TESTAMENT_DISTANCE") {
+                    commitinfo.distance = distance.trim().parse().map_err(|e| {
+                        format!("GIT_TESTAMENT_DISTANCE is not a number of commits: {e}")
+                    })?;
+                }
+                if value("GIT_TESTAMENT_TAG").is_some() || value("GIT_TESTAMENT_DISTANCE").is_some()
+                {
+                    // This no longer agrees with the tag
+                    commitinfo.describe = None;
+                }
+            }
+            None if describes_commit.iter().any(|var| value(var).is_some()) => {
+                return Err(TestamentError::MissingEnv("GIT_TESTAMENT_COMMIT"));
+            }
+            None => {}
+        }
+
+        if let Some(branch) = value("GIT_TESTAMENT_BRANCH") {
+            info.branch = Some(branch.to_owned());
+            info.detached = false;
+        }
+
+        if let Some(dirty) = value("GIT_TESTAMENT_DIRTY") {
+            match dirty.trim() {
+                "true" | "1" => {
+                    if info.status.is_empty() {
+                        info.status_collected = false;
+                    }
+                }
+                "false" | "0" => {
+                    info.status.clear();
+                    info.status_collected = true;
+                    info.worktree_digest = None;
+                    info.diff = None;
+                }
+                _ => {
+                    return Err(
+                        format!("GIT_TESTAMENT_DIRTY must be true or false, not {dirty:?}").into(),
+                    )
+                }
+            }
+        }
+
+        info.overridden = overrides.iter().map(|(var, _)| *var).collect();
+        Ok(info)
+    }
+
+    /// Acquire information about a workspace of several crates at once.
+    ///
+    /// The repository is probed only once, from `root`, and then the
+    /// information is scoped to each of the `members` in turn (see
+    /// [`GitInformation::scoped_to`]).  This is both faster than probing for
+    /// each member, and guarantees that the members agree on the commit.
+    pub fn acquire_workspace<P>(
+        root: &Path,
+        members: &[P],
+        options: &AcquireOptions,
+    ) -> Result<Vec<Self>, Error>
+    where
+        P: AsRef<Path>,
+    {
+        let info = Self::acquire(root, options)?;
+        members
+            .iter()
+            .map(|member| info.scoped_to(&root.join(member)))
+            .collect()
+    }
+
+    /// Restrict the modifications to those within `dir`.
+    ///
+    /// The commit and branch information is unchanged, so this is useful
+    /// when several crates in one repository want to share a single probe of
+    /// the repository but only be considered dirty by changes to themselves.
+    pub fn scoped_to(&self, dir: &Path) -> Result<Self, Error> {
+        // A bare repository has no working tree for `dir` to be within
+        if self.bare {
+            return Ok(self.clone());
+        }
+        let dir = dir.canonicalize()?;
+        let prefix = relative_to(&dir, &self.toplevel).ok_or_else(|| {
+            format!(
+                "{} is not within the repository at {}",
+                dir.display(),
+                self.toplevel.display()
+            )
+        })?;
+        Ok(Self {
+            status: self
+                .status
+                .iter()
+                .filter(|entry| path_from_bytes(&entry.path).starts_with(prefix))
+                .cloned()
+                .collect(),
+            ..self.clone()
+        })
+    }
+}
+
+/// Today's date (in UTC), formatted in the same way as commit dates
+pub fn today() -> String {
+    OffsetDateTime::now_utc()
+        .format(DATE_FORMAT)
+        .expect("unable to format now")
+}
+
+/// A date given by `SOURCE_DATE_EPOCH`
+#[derive(Clone, Debug)]
+pub struct SourceDate {
+    /// The date, formatted in the same way as commit dates
+    pub date: String,
+    /// The time as an RFC 3339 timestamp in UTC
+    pub timestamp: String,
+}
+
+/// Parse a `SOURCE_DATE_EPOCH`.
+///
+/// Some build systems quote the value or leave whitespace around it, so
+/// those are tolerated.
+pub fn parse_source_date_epoch(sde: &str) -> Result<SourceDate, String> {
+    let sde = sde.trim();
+    let sde = ['"', '\'']
+        .iter()
+        .find_map(|quote| sde.strip_prefix(*quote)?.strip_suffix(*quote))
+        .unwrap_or(sde)
+        .trim();
+    let sde = sde
+        .parse::<i64>()
+        .map_err(|e| format!("not a number of seconds: {e}"))?;
+    let time = OffsetDateTime::from_unix_timestamp(sde).map_err(|e| e.to_string())?;
+    Ok(SourceDate {
+        date: time.format(DATE_FORMAT).map_err(|e| e.to_string())?,
+        timestamp: time.format(&Rfc3339).map_err(|e| e.to_string())?,
+    })
+}
+
+/// The environment variables from which the [`BuildInfo`] is read.
+///
+/// Cargo only sets `TARGET`, `PROFILE` and `OPT_LEVEL` for build scripts, so a
+/// crate reading them at expansion time needs its build script to pass them
+/// on to the compiler.  `RUSTC` chooses the compiler whose version is
+/// recorded, and is `rustc` if it is not set.
+pub const BUILD_INFO_ENV_VARS: &[&str] = &["TARGET", "PROFILE", "OPT_LEVEL", "RUSTC"];
+
+/// The target and toolchain a crate is being built with
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// The target triple, e.g. `x86_64-unknown-linux-gnu`
+    pub target: Option<String>,
+    /// The cargo profile, which is either `debug` or `release`
+    pub profile: Option<String>,
+    /// The optimisation level, e.g. `0` or `3`
+    pub opt_level: Option<String>,
+    /// The output of `rustc --version`
+    pub rustc: Option<String>,
+}
+
+impl BuildInfo {
+    /// Read the build information from the environment (see
+    /// [`BUILD_INFO_ENV_VARS`]), leaving out anything which is not set.
+    pub fn acquire() -> Self {
+        let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let rustc = Command::new(var("RUSTC").unwrap_or_else(|| "rustc".to_owned()))
+            .arg("--version")
+            .stderr(Stdio::null())
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_owned());
+        Self {
+            target: var("TARGET"),
+            profile: var("PROFILE"),
+            opt_level: var("OPT_LEVEL"),
+            rustc,
+        }
+    }
+}
+
+/// The environment variables from which the [`CiInfo`] is detected.
+pub const CI_ENV_VARS: &[&str] = &[
+    "GITHUB_ACTIONS",
+    "GITHUB_RUN_ID",
+    "GITHUB_SERVER_URL",
+    "GITHUB_REPOSITORY",
+    "GITLAB_CI",
+    "CI_PIPELINE_ID",
+    "CI_JOB_URL",
+    "BUILDKITE",
+    "BUILDKITE_BUILD_ID",
+    "BUILDKITE_BUILD_URL",
+    "BUILDKITE_JOB_ID",
+    "JENKINS_URL",
+    "BUILD_ID",
+    "BUILD_URL",
+];
+
+/// The environment variables from which [`ci_branch`] is read.
+pub const CI_BRANCH_ENV_VARS: &[&str] = &[
+    "GITHUB_ACTIONS",
+    "GITHUB_HEAD_REF",
+    "GITHUB_REF_TYPE",
+    "GITHUB_REF_NAME",
+    "GITLAB_CI",
+    "CI_MERGE_REQUEST_SOURCE_BRANCH_NAME",
+    "CI_COMMIT_BRANCH",
+    "BUILDKITE",
+    "BUILDKITE_BRANCH",
+    "JENKINS_URL",
+    "BRANCH_NAME",
+];
+
+/// The branch which the CI system building the crate says is being built,
+/// for when it has checked out a detached `HEAD`.  For a pull or merge
+/// request this is the branch being merged.
+///
+/// This recognises the same CI systems as [`CiInfo::detect`], and is `None`
+/// if the crate is not being built by one of them, or if it is building a
+/// tag rather than a branch.
+pub fn ci_branch() -> Option<String> {
+    let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+    if var("GITHUB_ACTIONS").as_deref() == Some("true") {
+        var("GITHUB_HEAD_REF").or_else(|| {
+            if var("GITHUB_REF_TYPE").as_deref() == Some("branch") {
+                var("GITHUB_REF_NAME")
+            } else {
+                None
+            }
+        })
+    } else if var("GITLAB_CI").as_deref() == Some("true") {
+        var("CI_MERGE_REQUEST_SOURCE_BRANCH_NAME").or_else(|| var("CI_COMMIT_BRANCH"))
+    } else if var("BUILDKITE").as_deref() == Some("true") {
+        var("BUILDKITE_BRANCH")
+    } else if var("JENKINS_URL").is_some() {
+        var("BRANCH_NAME")
+    } else {
+        None
+    }
+}
+
+/// The continuous integration run a crate is being built by
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CiInfo {
+    /// The CI system, one of `github-actions`, `gitlab`, `buildkite` or
+    /// `jenkins`
+    pub provider: String,
+    /// The identifier of the workflow run, pipeline or build
+    pub run_id: Option<String>,
+    /// A link to the job, or to the run if the job cannot be linked to
+    pub job_url: Option<String>,
+}
+
+impl CiInfo {
+    /// Detect the CI system from the environment (see [`CI_ENV_VARS`]),
+    /// returning `None` if the crate is not being built by one which is
+    /// recognised.
+    pub fn detect() -> Option<Self> {
+        let var = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let (provider, run_id, job_url) = if var("GITHUB_ACTIONS").as_deref() == Some("true") {
+            let run_id = var("GITHUB_RUN_ID");
+            let job_url = match (&run_id, var("GITHUB_SERVER_URL"), var("GITHUB_REPOSITORY")) {
+                (Some(run_id), Some(server), Some(repository)) => {
+                    Some(format!("{server}/{repository}/actions/runs/{run_id}"))
+                }
+                _ => None,
+            };
+            ("github-actions", run_id, job_url)
+        } else if var("GITLAB_CI").as_deref() == Some("true") {
+            ("gitlab", var("CI_PIPELINE_ID"), var("CI_JOB_URL"))
+        } else if var("BUILDKITE").as_deref() == Some("true") {
+            // Buildkite links to a job within the build by its id
+            let job_url = var("BUILDKITE_BUILD_URL").map(|url| match var("BUILDKITE_JOB_ID") {
+                Some(job) => format!("{url}#{job}"),
+                None => url,
+            });
+            ("buildkite", var("BUILDKITE_BUILD_ID"), job_url)
+        } else if var("JENKINS_URL").is_some() {
+            ("jenkins", var("BUILD_ID"), var("BUILD_URL"))
+        } else {
+            return None;
+        };
+        Some(Self {
+            provider: provider.to_owned(),
+            run_id,
+            job_url,
+        })
+    }
+}
+
+/// The full hash of the commit at `HEAD` in the git repository containing
+/// `start`, or `None` if there is no repository or no commit.
+///
+/// This is much cheaper than [`GitInformation::acquire`], so it is suitable
+/// for checking whether previously acquired information is still current.
+pub fn head_commit(start: &Path) -> Option<String> {
+    let head = run_git(start, &["rev-parse", "--verify", "-q", "HEAD"]).ok()?;
+    Some(String::from_utf8(head).ok()?.trim_end().to_owned())
+}
+
+/// The paths within the repository's git directory which change when a
+/// commit is made, the branch is switched, or changes are staged.
+///
+/// These are suitable for a build script to pass to `cargo:rerun-if-changed`.
+pub fn metadata_paths(start: &Path) -> Result<Vec<PathBuf>, Error> {
+    const PATHS: &[&str] = &["HEAD", "index", "refs", "packed-refs"];
+    let mut args = vec!["rev-parse"];
+    for path in PATHS {
+        args.extend_from_slice(&["--git-path", path]);
+    }
+    let output = run_git(start, &args)?;
+    Ok(output
+        .split(|b| *b == b'\n')
+        .filter(|path| !path.is_empty())
+        .map(|path| start.join(path_from_bytes(path)))
+        .collect())
+}
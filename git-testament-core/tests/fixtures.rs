@@ -0,0 +1,566 @@
+//! Acquiring information from recorded git output, rather than a repository
+
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
+
+use git_testament_core::{
+    AcquireOptions, DescribeMode, Error, GitInformation, GitRunner, StatusFlag, TestamentError, Vcs,
+};
+
+/// Replays recorded output for commands starting with the given arguments,
+/// and fails any other command as git would if it were given a bad revision.
+struct Recorded(Vec<(&'static str, Result<Vec<u8>, &'static str>)>);
+
+impl GitRunner for Recorded {
+    fn run_git(&self, _dir: &Path, args: &[&str]) -> Result<Vec<u8>, Error> {
+        let command = args.join(" ");
+        let recorded = self
+            .0
+            .iter()
+            .find(|(prefix, _)| command.starts_with(prefix))
+            .map_or(Err("fatal: not recorded"), |(_, output)| output.clone());
+        recorded.map_err(|stderr| TestamentError::CommandFailed {
+            command: format!("git {command}"),
+            stderr: stderr.to_owned(),
+        })
+    }
+
+    /// The recording is of whichever stream git reported on, so it is
+    /// replayed as both
+    fn run_git_with_env(
+        &self,
+        dir: &Path,
+        args: &[&str],
+        _config: &[OsString],
+        _env: &[(&str, &OsStr)],
+    ) -> Result<(Vec<u8>, Vec<u8>), Error> {
+        let output = self.run_git(dir, args)?;
+        Ok((output.clone(), output))
+    }
+}
+
+fn ok(output: &[u8]) -> Result<Vec<u8>, &'static str> {
+    Ok(output.to_vec())
+}
+
+const HASH: &str = "651af89ed0fb1ba2b6bd5fc63a75b20c2d4b1d7a";
+
+/// The commands every acquisition runs, with `HEAD` decorated as given
+fn repository(decorations: &str) -> Vec<(&'static str, Result<Vec<u8>, &'static str>)> {
+    let log =
+        format!("{HASH}\n1554200000\n2019-04-02 11:13:20 +0100\n0123456789abcdef\n{decorations}\n");
+    vec![
+        ("rev-parse --show-toplevel", ok(b"/fixture/repo\n")),
+        ("rev-parse --is-shallow-repository", ok(b"false\n")),
+        ("rev-parse --short", ok(b"651af89\n")),
+        ("log -1", Ok(log.into_bytes())),
+        ("rev-list --count", ok(b"14\n")),
+        ("status", ok(b"")),
+    ]
+}
+
+fn acquire(recorded: Recorded, options: &AcquireOptions) -> GitInformation {
+    GitInformation::acquire_with(&recorded, "/fixture/repo".as_ref(), options)
+        .expect("Unable to acquire from the recorded output")
+}
+
+#[test]
+fn describe_tag_with_dashes() {
+    let mut commands = repository("HEAD -> main, tag: release-1.0-rc1");
+    commands.insert(0, ("describe", ok(b"release-1.0-rc1-3-g651af89-dirty\n")));
+    let info = acquire(Recorded(commands), &AcquireOptions::default());
+    let commitinfo = info.commitinfo.expect("No commit information");
+    assert_eq!(commitinfo.id, HASH);
+    assert_eq!(commitinfo.date, "2019-04-02");
+    assert_eq!(commitinfo.timestamp, "2019-04-02T11:13:20+01:00");
+    assert_eq!(commitinfo.tag, "release-1.0-rc1");
+    assert_eq!(commitinfo.distance, 3);
+    assert_eq!(
+        commitinfo.describe.as_deref(),
+        Some("release-1.0-rc1-3-g651af89-dirty")
+    );
+    assert_eq!(commitinfo.commit_count, 14);
+    assert_eq!(commitinfo.abbrev, 7);
+    assert_eq!(info.branch.as_deref(), Some("main"));
+    assert!(!info.detached);
+}
+
+#[test]
+fn unexpected_describe_output() {
+    let mut commands = repository("HEAD -> main");
+    commands.insert(0, ("describe", ok(b"651af89\n")));
+    let info = acquire(Recorded(commands), &AcquireOptions::default());
+    let commitinfo = info.commitinfo.expect("No commit information");
+    assert_eq!(commitinfo.tag, "");
+    assert_eq!(commitinfo.describe, None);
+    assert!(info
+        .warnings
+        .iter()
+        .any(|warning| warning.starts_with("Unable to describe the commit")));
+}
+
+#[test]
+fn status_renames_and_unusual_paths() {
+    let mut commands = repository("HEAD -> main");
+    commands.insert(
+        0,
+        (
+            "status",
+            ok(
+                b"1 .M N... 100644 100644 100644 aaa aaa src/caf\xc3\xa9.rs\0\
+                 2 R. N... 100644 100644 100644 aaa aaa R100 docs/new name.md\0docs/old name.md\0\
+                 1 A. N... 000000 100644 100644 000 aaa bad\xffname\0\
+                 1 .D N... 100644 100644 000000 aaa aaa gone.rs\0\
+                 ? notes.txt\0\
+                 ! target\0",
+            ),
+        ),
+    );
+    let info = acquire(Recorded(commands), &AcquireOptions::default());
+    let status: Vec<_> = info
+        .status
+        .iter()
+        .map(|entry| {
+            (
+                entry.status,
+                &entry.path[..],
+                entry.original_path.as_deref(),
+            )
+        })
+        .collect();
+    assert!(matches!(
+        status[..],
+        [
+            (StatusFlag::Modified, b"src/caf\xc3\xa9.rs", None),
+            (
+                StatusFlag::Renamed,
+                b"docs/new name.md",
+                Some(b"docs/old name.md")
+            ),
+            (StatusFlag::Added, b"bad\xffname", None),
+            (StatusFlag::Deleted, b"gone.rs", None),
+            (StatusFlag::Untracked, b"notes.txt", None),
+        ]
+    ));
+}
+
+#[test]
+fn annotated_tag_message() {
+    let mut commands = repository("HEAD -> main, tag: v1.0.0");
+    commands.insert(0, ("describe", ok(b"v1.0.0-0-g651af89\n")));
+    commands.insert(
+        0,
+        (
+            "cat-file -p refs/tags/v1.0.0",
+            ok(b"object 651af89ed0fb1ba2b6bd5fc63a75b20c2d4b1d7a\n\
+                 type commit\n\
+                 tag v1.0.0\n\
+                 tagger A U Thor <author@example.com> 1554336000 -0500\n\
+                 \n\
+                 First release\n\
+                 \n\
+                 With a longer description.\n"),
+        ),
+    );
+    let options = AcquireOptions {
+        tag_message: true,
+        ..AcquireOptions::default()
+    };
+    let info = acquire(Recorded(commands), &options);
+    let commitinfo = info.commitinfo.expect("No commit information");
+    assert_eq!(
+        commitinfo.tag_message,
+        Some(("First release".to_owned(), "2019-04-04".to_owned()))
+    );
+}
+
+#[test]
+fn lightweight_tag_has_no_message() {
+    let mut commands = repository("HEAD -> main, tag: v1.0.0");
+    commands.insert(0, ("describe", ok(b"v1.0.0-0-g651af89\n")));
+    commands.insert(
+        0,
+        (
+            "cat-file -p refs/tags/v1.0.0",
+            ok(b"tree 4b825dc642cb6eb9a060e54bf8d69288fbee4904\n\
+                 author A U Thor <author@example.com> 1554200000 +0100\n\
+                 committer A U Thor <author@example.com> 1554200000 +0100\n\
+                 \n\
+                 first\n"),
+        ),
+    );
+    let options = AcquireOptions {
+        tag_message: true,
+        ..AcquireOptions::default()
+    };
+    let info = acquire(Recorded(commands), &options);
+    assert_eq!(info.commitinfo.unwrap().tag_message, None);
+}
+
+#[test]
+fn describe_contains_through_merge() {
+    let mut commands = repository("HEAD -> maint");
+    commands.insert(0, ("describe --contains", ok(b"v1.2.0~2^2~1\n")));
+    commands.insert(
+        0,
+        (
+            "rev-list --count 651af89ed0fb1ba2b6bd5fc63a75b20c2d4b1d7a..refs/tags/v1.2.0",
+            ok(b"5\n"),
+        ),
+    );
+    let options = AcquireOptions {
+        describe_mode: DescribeMode::Contains,
+        ..AcquireOptions::default()
+    };
+    let info = acquire(Recorded(commands), &options);
+    let commitinfo = info.commitinfo.expect("No commit information");
+    assert_eq!(commitinfo.contained_in, Some(("v1.2.0".to_owned(), 5)));
+    // The commit is still described by the tag before it, if there is one
+    assert_eq!(commitinfo.tag, "");
+    assert_eq!(commitinfo.commit_count, 14);
+}
+
+#[test]
+fn describe_contains_without_later_tag() {
+    let mut commands = repository("HEAD -> main");
+    commands.insert(
+        0,
+        (
+            "describe --contains",
+            Err("fatal: cannot describe '651af89ed0fb1ba2b6bd5fc63a75b20c2d4b1d7a'\n"),
+        ),
+    );
+    let options = AcquireOptions {
+        describe_mode: DescribeMode::Contains,
+        ..AcquireOptions::default()
+    };
+    let info = acquire(Recorded(commands), &options);
+    assert_eq!(info.commitinfo.unwrap().contained_in, None);
+    // Having made no tag since is not worth a warning
+    assert!(!info
+        .warnings
+        .iter()
+        .any(|warning| warning.contains("contain")));
+}
+
+#[test]
+fn detached_head_names_nearest_ref() {
+    let mut commands = repository("HEAD, tag: v1.0.0");
+    commands.insert(0, ("name-rev --name-only HEAD", ok(b"tags/v1.0.0^0\n")));
+    let info = acquire(Recorded(commands), &AcquireOptions::default());
+    assert_eq!(info.branch.as_deref(), Some("v1.0.0"));
+    assert!(info.detached);
+}
+
+#[test]
+fn no_repository() {
+    let recorded = Recorded(vec![(
+        "rev-parse",
+        Err("fatal: not a git repository (or any of the parent directories): .git\n"),
+    )]);
+    let err = GitInformation::acquire_with(
+        &recorded,
+        "/fixture/none".as_ref(),
+        &AcquireOptions::default(),
+    )
+    .expect_err("Acquired information without a repository");
+    assert!(err.is_no_repository());
+}
+
+#[test]
+fn revision_file() {
+    let dir = std::env::temp_dir().join(format!("git-testament-revision-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Unable to create the source directory");
+    let recorded = Recorded(vec![(
+        "rev-parse",
+        Err("fatal: not a git repository (or any of the parent directories): .git\n"),
+    )]);
+    let options = AcquireOptions {
+        revision_file: Some(".git-revision".into()),
+        ..AcquireOptions::default()
+    };
+
+    std::fs::write(dir.join(".git-revision"), format!("{HASH}-dirty\n")).unwrap();
+    let info = GitInformation::acquire_with(&recorded, &dir, &options)
+        .expect("Unable to read the revision file");
+    assert_eq!(info.vcs, Vcs::RevisionFile);
+    let vcs_info = info
+        .cargo_vcs_info
+        .expect("No commit from the revision file");
+    assert_eq!(vcs_info.sha1, HASH);
+    assert!(vcs_info.dirty && !info.status_collected);
+
+    std::fs::write(dir.join(".git-revision"), "not a commit\n").unwrap();
+    let err = GitInformation::acquire_with(&recorded, &dir, &options)
+        .expect_err("Accepted a revision file without a hash");
+    assert!(err.is_no_repository());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn old_git_status_format() {
+    let mut commands = repository("HEAD -> main");
+    commands.insert(0, ("--version", ok(b"git version 2.10.5\n")));
+    commands.insert(0, ("rev-parse --git-path shallow", ok(b".git/shallow\n")));
+    commands.insert(
+        0,
+        (
+            "status --porcelain -z",
+            ok(b" M src/caf\xc3\xa9.rs\0\
+                 R  docs/new name.md\0docs/old name.md\0\
+                 A  added.rs\0\
+                 ?? notes.txt\0\
+                 !! target\0"),
+        ),
+    );
+    let info = acquire(Recorded(commands), &AcquireOptions::default());
+    assert_eq!(info.git_version.as_deref(), Some("2.10.5"));
+    assert!(!info.shallow);
+    let status: Vec<_> = info
+        .status
+        .iter()
+        .map(|entry| {
+            (
+                entry.status,
+                &entry.path[..],
+                entry.original_path.as_deref(),
+            )
+        })
+        .collect();
+    assert!(matches!(
+        status[..],
+        [
+            (StatusFlag::Modified, b"src/caf\xc3\xa9.rs", None),
+            (
+                StatusFlag::Renamed,
+                b"docs/new name.md",
+                Some(b"docs/old name.md")
+            ),
+            (StatusFlag::Added, b"added.rs", None),
+            (StatusFlag::Untracked, b"notes.txt", None),
+        ]
+    ));
+}
+
+#[test]
+fn unsupported_git_version() {
+    let mut commands = repository("HEAD -> main");
+    commands.insert(0, ("--version", ok(b"git version 1.9.5.msysgit.1\n")));
+    let info = acquire(Recorded(commands), &AcquireOptions::default());
+    assert_eq!(info.git_version.as_deref(), Some("1.9.5.msysgit.1"));
+    assert!(info
+        .warnings
+        .iter()
+        .any(|warning| warning.starts_with("git 1.9 is older than 2.0")));
+}
+
+#[test]
+fn environment_overrides() {
+    let overrides = |vars: &'static [(&'static str, &'static str)]| {
+        move |var: &str| {
+            vars.iter()
+                .find(|(name, _)| *name == var)
+                .map(|(_, value)| (*value).to_owned())
+        }
+    };
+    let mut commands = repository("HEAD -> main");
+    commands.insert(
+        0,
+        ("status", ok(b"1 .M N... 100644 100644 100644 0 0 a.rs\0")),
+    );
+    let start = Path::new("/fixture/repo");
+
+    // The same commit keeps what was read, and is only reported as clean
+    let info = GitInformation::with_overrides(
+        Ok(acquire(
+            Recorded(commands.clone()),
+            &AcquireOptions::default(),
+        )),
+        start,
+        overrides(&[
+            ("GIT_TESTAMENT_COMMIT", HASH),
+            ("GIT_TESTAMENT_BRANCH", "release"),
+            ("GIT_TESTAMENT_DIRTY", "false"),
+        ]),
+    )
+    .expect("Unable to apply the overrides");
+    assert_eq!(info.commitinfo.expect("No commit").date, "2019-04-02");
+    assert_eq!(info.branch.as_deref(), Some("release"));
+    assert!(info.status.is_empty() && info.status_collected);
+    assert_eq!(
+        info.overridden,
+        [
+            "GIT_TESTAMENT_COMMIT",
+            "GIT_TESTAMENT_BRANCH",
+            "GIT_TESTAMENT_DIRTY"
+        ]
+    );
+
+    // A different commit needs its date, since it cannot be read
+    let err = GitInformation::with_overrides(
+        Ok(acquire(Recorded(commands), &AcquireOptions::default())),
+        start,
+        overrides(&[("GIT_TESTAMENT_COMMIT", "0123456789abcdef")]),
+    )
+    .expect_err("Accepted a commit without a date");
+    assert!(matches!(
+        err,
+        TestamentError::MissingEnv("GIT_TESTAMENT_DATE")
+    ));
+
+    // A tag alone has no commit to describe
+    let err = GitInformation::with_overrides(
+        Err(TestamentError::NoRepository(start.to_owned())),
+        start,
+        overrides(&[("GIT_TESTAMENT_TAG", "v1.0.0")]),
+    )
+    .expect_err("Accepted a tag without a commit");
+    assert!(matches!(
+        err,
+        TestamentError::MissingEnv("GIT_TESTAMENT_COMMIT")
+    ));
+}
+
+#[test]
+fn crlf_output() {
+    let mut commands = repository("HEAD -> feature/crlf, tag: v1.0.0");
+    commands.insert(0, ("describe", ok(b"v1.0.0-2-g651af89\n")));
+    commands.insert(
+        0,
+        (
+            "cat-file -p refs/tags/v1.0.0",
+            ok(b"object 651af89ed0fb1ba2b6bd5fc63a75b20c2d4b1d7a\n\
+                 type commit\n\
+                 tag v1.0.0\n\
+                 tagger A U Thor <author@example.com> 1554336000 -0500\n\
+                 \n\
+                 First release\n"),
+        ),
+    );
+    // As a wrapper around git on Windows might give it
+    let commands = commands
+        .into_iter()
+        .map(|(prefix, output)| {
+            let output = output.map(|output| {
+                String::from_utf8(output)
+                    .unwrap()
+                    .replace('\n', "\r\n")
+                    .into_bytes()
+            });
+            (prefix, output)
+        })
+        .collect();
+    let options = AcquireOptions {
+        tag_message: true,
+        ..AcquireOptions::default()
+    };
+    let info = acquire(Recorded(commands), &options);
+    assert_eq!(info.toplevel, Path::new("/fixture/repo"));
+    assert_eq!(info.branch.as_deref(), Some("feature/crlf"));
+    let commitinfo = info.commitinfo.expect("No commit information");
+    assert_eq!(commitinfo.id, HASH);
+    assert_eq!(commitinfo.tag, "v1.0.0");
+    assert_eq!(commitinfo.distance, 2);
+    assert_eq!(
+        commitinfo.tag_message,
+        Some(("First release".to_owned(), "2019-04-04".to_owned()))
+    );
+}
+
+/// Git reports the top of the working tree as `c:/...`, with whichever case
+/// of drive letter the build was started with, where canonical paths are in
+/// the form `\\?\C:\...`
+#[cfg(windows)]
+#[test]
+fn windows_drive_letter_case() {
+    let top = std::env::temp_dir().join("git testament fixture");
+    let member = top.join("my crate");
+    std::fs::create_dir_all(&member).expect("Unable to create the crate directory");
+    let top = top.canonicalize().expect("Unable to canonicalize");
+    let top = top.to_str().expect("Temporary directory is not UTF-8");
+    let top = top.trim_start_matches(r"\\?\").replace('\\', "/");
+    let reported = format!("{}{}\n", top[..1].to_ascii_lowercase(), &top[1..]);
+
+    let mut commands = repository("HEAD -> main");
+    commands.insert(0, ("rev-parse --show-toplevel", Ok(reported.into_bytes())));
+    commands.insert(
+        0,
+        (
+            "status",
+            ok(
+                b"1 .M N... 100644 100644 100644 aaa aaa my crate/src/lib.rs\0\
+                 1 .M N... 100644 100644 100644 aaa aaa other/src/lib.rs\0",
+            ),
+        ),
+    );
+    let info = acquire(Recorded(commands), &AcquireOptions::default());
+    let scoped = info
+        .scoped_to(&member)
+        .expect("The crate is not within the repository");
+    assert_eq!(scoped.status.len(), 1);
+    assert_eq!(scoped.status[0].path, b"my crate/src/lib.rs");
+}
+
+#[test]
+fn commit_signature_status() {
+    let options = AcquireOptions {
+        verify_signature: true,
+        ..AcquireOptions::default()
+    };
+    for (report, signed, signer) in [
+        (
+            &b"G\nA U Thor <author@example.com>\n"[..],
+            true,
+            Some("A U Thor <author@example.com>"),
+        ),
+        // Good, but the validity of the key is unknown, as for SSH keys
+        (b"U\nauthor@example.com\n", true, Some("author@example.com")),
+        (b"B\nA U Thor <author@example.com>\n", false, None),
+        (b"N\n\n", false, None),
+    ] {
+        let mut commands = repository("HEAD -> main");
+        commands.insert(0, ("show -s --format=%G?%n%GS", ok(report)));
+        let info = acquire(Recorded(commands), &options);
+        let commitinfo = info.commitinfo.expect("No commit information");
+        assert_eq!(commitinfo.signed, signed);
+        assert_eq!(commitinfo.signer.as_deref(), signer);
+    }
+}
+
+#[test]
+fn signed_tag_signers() {
+    let options = AcquireOptions {
+        verify_tags: true,
+        ..AcquireOptions::default()
+    };
+    for (report, signer) in [
+        (
+            &b"[GNUPG:] NEWSIG\n\
+               [GNUPG:] GOODSIG 0123456789ABCDEF A U Thor <author@example.com>\n\
+               [GNUPG:] VALIDSIG 0123456789ABCDEF0123456789ABCDEF01234567\n"[..],
+            Some("A U Thor <author@example.com>"),
+        ),
+        (
+            b"Good \"git\" signature for author@example.com with ED25519 key SHA256:abc\n",
+            Some("author@example.com"),
+        ),
+        (b"", None),
+    ] {
+        let mut commands = repository("HEAD -> main, tag: lightweight, tag: v1.0.0");
+        commands.insert(0, ("tag --points-at", ok(b"lightweight\nv1.0.0\n")));
+        // Lightweight tags cannot be signed, so the next tag is tried
+        commands.insert(
+            0,
+            (
+                "verify-tag --raw lightweight",
+                Err("error: lightweight: cannot verify a non-tag object of type commit.\n"),
+            ),
+        );
+        commands.insert(0, ("verify-tag --raw v1.0.0", ok(report)));
+        let info = acquire(Recorded(commands), &options);
+        let commitinfo = info.commitinfo.expect("No commit information");
+        let (tag, tag_signer) = commitinfo.signed_tag.expect("No signed tag");
+        assert_eq!(tag, "v1.0.0");
+        assert_eq!(tag_signer.as_deref(), signer);
+    }
+}
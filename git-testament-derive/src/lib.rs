@@ -3,27 +3,679 @@
 extern crate proc_macro;
 
 use std::env;
-use std::error::Error;
+use std::fs;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::sync::{Mutex, PoisonError};
 
 use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::quote;
+use syn::ext::IdentExt;
 use syn::parse::{Parse, ParseStream};
-use syn::{parse, Visibility};
-use syn::{parse_macro_input, Ident, LitStr};
+use syn::punctuated::Punctuated;
+use syn::{braced, bracketed, parenthesized, parse, Token, Visibility};
+use syn::{parse_macro_input, Ident, LitBool, LitInt, LitStr};
 
 use log::warn;
 
-use time::{format_description::FormatItem, macros::format_description, OffsetDateTime, UtcOffset};
+use git_testament_core::StatusFlag::*;
+use git_testament_core::{
+    ci_branch, glob_matches, head_commit, parse_source_date_epoch, AcquireOptions, BuildInfo,
+    CiInfo, CommitInfo, DescribeMode, Error, GitInformation, SourceDate, StatusEntry,
+    TestamentError, UntrackedFiles, Vcs, BUILD_INFO_ENV_VARS, CI_BRANCH_ENV_VARS, CI_ENV_VARS,
+    DISCOVERY_ENV_VARS, OVERRIDE_ENV_VARS,
+};
+
+use miniz_oxide::deflate::compress_to_vec_zlib;
+
+use semver::Version;
+
+use time::format_description::{well_known::Rfc3339, FormatItem, OwnedFormatItem};
+use time::{macros::format_description, OffsetDateTime};
 
 const DATE_FORMAT: &[FormatItem<'_>] = format_description!("[year]-[month]-[day]");
 
+/// Set to `1` to print warnings to stderr, or to a level such as `debug` to
+/// print more.
+const DEBUG_ENV_VAR: &str = "GIT_TESTAMENT_DEBUG";
+
+/// The length to which commit hashes are abbreviated unless told otherwise.
+const DEFAULT_SHORT_HASH_LENGTH: usize = 9;
+
+/// A source of repository information, see the `sources` option
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Source {
+    /// Run the `git` command line tool
+    Git,
+    /// Run the `jj` command line tool
+    Jujutsu,
+    /// Run the `hg` command line tool
+    Mercurial,
+}
+
+impl Source {
+    fn parse(name: &LitStr) -> parse::Result<Self> {
+        match name.value().as_str() {
+            "git" => Ok(Source::Git),
+            "jj" => Ok(Source::Jujutsu),
+            "hg" => Ok(Source::Mercurial),
+            other => Err(syn::Error::new(
+                name.span(),
+                format!(
+                    "Unknown or unsupported testament source `{other}`, expected one of \"git\", \"jj\" or \"hg\""
+                ),
+            )),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Source::Git => "git",
+            Source::Jujutsu => "jj",
+            Source::Mercurial => "hg",
+        }
+    }
+}
+
+/// One `"channel" => "pattern"` entry of the `channels` option
+struct ChannelPattern {
+    channel: LitStr,
+    pattern: LitStr,
+}
+
+impl Parse for ChannelPattern {
+    fn parse(input: ParseStream) -> parse::Result<Self> {
+        let channel = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let pattern = input.parse()?;
+        Ok(ChannelPattern { channel, pattern })
+    }
+}
+
+/// How strictly to enforce an option which checks the repository state
+#[derive(Clone, Copy)]
+enum Enforcement {
+    Warn,
+    Error,
+}
+
+/// How much information must be available for the build to succeed, each
+/// level implying those before it
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Requirement {
+    Repo,
+    Commit,
+    Tag,
+}
+
+impl Requirement {
+    fn option_name(self) -> &'static str {
+        match self {
+            Requirement::Repo => "require_repo",
+            Requirement::Commit => "require_commit",
+            Requirement::Tag => "require_tag",
+        }
+    }
+}
+
+/// How much of the repository information may be embedded, each level
+/// withholding everything the levels before it do
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+enum Privacy {
+    #[default]
+    Full,
+    /// Record the modifications, but not the paths which were modified
+    NoPaths,
+    /// Additionally withhold the names of branches, tags and remotes
+    HashOnly,
+}
+
+impl Privacy {
+    /// Remove whatever this level withholds from `gitinfo`
+    fn redact(self, mut gitinfo: GitInformation) -> GitInformation {
+        if self >= Privacy::NoPaths {
+            // The diff names every file it changes
+            gitinfo.diff = None;
+            for entry in &mut gitinfo.status {
+                entry.path.clear();
+                if let Some(original) = &mut entry.original_path {
+                    original.clear();
+                }
+            }
+        }
+        if self >= Privacy::HashOnly {
+            gitinfo.branch = None;
+            gitinfo.remote = None;
+            gitinfo.remote_url = None;
+            if let Some(commitinfo) = &mut gitinfo.commitinfo {
+                commitinfo.tag.clear();
+                commitinfo.distance = 0;
+                commitinfo.describe = None;
+                commitinfo.contained_in = None;
+                commitinfo.tag_message = None;
+                commitinfo.release = None;
+                commitinfo.signed_tag = None;
+                commitinfo.tags_at_head.clear();
+            }
+        }
+        gitinfo
+    }
+}
+
+/// Options which may follow the name in a macro invocation, for example
+/// `git_testament!(TESTAMENT, compress)`.
+#[derive(Default)]
+struct GenerationOptions {
+    compress: bool,
+    short_hash: Option<usize>,
+    scope_to_crate: bool,
+    /// `Some(None)` to place the rendered testament into the default link
+    /// section for the target, or `Some(Some(name))` for a named section.
+    link_section: Option<Option<String>>,
+    /// The symbol to export the rendered testament as, for C
+    export_c: Option<Ident>,
+    /// The prefix of the `extern "C"` accessor functions to generate
+    ffi: Option<String>,
+    /// `Some(None)` to write the artifact file into `OUT_DIR`, or
+    /// `Some(Some(path))` for a path relative to the crate.
+    artifact: Option<Option<String>>,
+    enforce_semver: Option<Enforcement>,
+    /// The strictest of the `require_*` options given
+    require: Option<Requirement>,
+    /// Extra key/value pairs, where the value is `None` if it was to come from
+    /// an environment variable which is not set
+    extras: Vec<(String, Option<String>)>,
+    /// Whether any of the extras were taken from the environment
+    extras_from_env: bool,
+    /// Environment variables which were read while parsing the options
+    tracked_env: Vec<String>,
+    /// A format string for the `NAME_formatted!()` macro
+    format: Option<LitStr>,
+    /// The sources to try, in order, or `None` for the default
+    sources: Option<Vec<Source>>,
+    /// The format of commit and build dates, if not `[year]-[month]-[day]`
+    date_format: Option<OwnedFormatItem>,
+    /// Never fall back to today's date, and take all dates from
+    /// `SOURCE_DATE_EPOCH` when it is set
+    reproducible: bool,
+    /// Glob patterns for the branches to trust, see `render_testament!()`
+    trusted: Vec<LitStr>,
+    /// Fail the build, rather than warn, if the repository could not be read
+    strict: bool,
+    /// Channel names and the glob patterns of the branches or tags which
+    /// belong to them, in the order given
+    channels: Vec<(String, String)>,
+    /// Where to start looking for the repository, relative to the crate
+    repo_path: Option<String>,
+    /// The value of the `repo_env` variable, which overrides `repo_path`
+    repo_env: Option<String>,
+    /// Record the target and toolchain, see `git_testament::BuildInfo`
+    build_info: bool,
+    /// Record the CI run building the crate, see `git_testament::CiInfo`
+    ci: bool,
+    /// Record the version of git which was run
+    git_version: bool,
+    /// Print everything which is logged, including each command run, to
+    /// stderr
+    verbose: bool,
+    privacy: Privacy,
+    acquire: AcquireOptions,
+}
+
+impl GenerationOptions {
+    /// Parse a comma separated list of options, consuming the rest of the input.
+    /// The input is expected to either be empty, or start with a comma.
+    fn parse_rest(input: ParseStream) -> parse::Result<Self> {
+        let mut ret = Self::default();
+        let mut seen = Vec::new();
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            // Some options, such as `match`, are keywords
+            let key = Ident::parse_any(input)?;
+            if seen.contains(&key) {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!("Duplicate testament option `{key}`"),
+                ));
+            }
+            seen.push(key.clone());
+            match key.to_string().as_str() {
+                "compress" => ret.compress = true,
+                "strict" => ret.strict = true,
+                "verbose" => ret.verbose = true,
+                "date_format" => {
+                    input.parse::<Token![=]>()?;
+                    let format: LitStr = input.parse()?;
+                    let parsed = time::format_description::parse_owned::<2>(&format.value())
+                        .map_err(|e| {
+                            syn::Error::new(format.span(), format!("Invalid date_format: {e}"))
+                        })?;
+                    ret.date_format = Some(parsed);
+                }
+                "require_repo" => ret.require = ret.require.max(Some(Requirement::Repo)),
+                "require_commit" => ret.require = ret.require.max(Some(Requirement::Commit)),
+                "require_tag" => ret.require = ret.require.max(Some(Requirement::Tag)),
+                "respect_export_ignore" => ret.acquire.respect_export_ignore = true,
+                "remote_fallback" => ret.acquire.remote_fallback = true,
+                "sources" => {
+                    input.parse::<Token![=]>()?;
+                    let content;
+                    bracketed!(content in input);
+                    let names = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+                    let mut sources = Vec::new();
+                    for name in &names {
+                        let source = Source::parse(name)?;
+                        if sources.contains(&source) {
+                            return Err(syn::Error::new(name.span(), "Duplicate source"));
+                        }
+                        sources.push(source);
+                    }
+                    ret.sources = Some(sources);
+                }
+                "format" => {
+                    input.parse::<Token![=]>()?;
+                    ret.format = Some(input.parse()?);
+                }
+                "parents" => ret.acquire.parents = true,
+                "tags_at_head" => ret.acquire.tags_at_head = true,
+                "submodules" => ret.acquire.submodules = true,
+                "worktree_digest" => ret.acquire.worktree_digest = true,
+                "lfs" => ret.acquire.lfs = true,
+                "git_version" => ret.git_version = true,
+                "ci_branch" => {
+                    ret.acquire.ci_branch = true;
+                    ret.tracked_env
+                        .extend(CI_BRANCH_ENV_VARS.iter().map(|var| var.to_string()));
+                }
+                "build_info" => {
+                    ret.build_info = true;
+                    ret.tracked_env
+                        .extend(BUILD_INFO_ENV_VARS.iter().map(|var| var.to_string()));
+                }
+                "ci" => {
+                    ret.ci = true;
+                    ret.tracked_env
+                        .extend(CI_ENV_VARS.iter().map(|var| var.to_string()));
+                }
+                "reproducible" => {
+                    ret.reproducible = true;
+                    ret.tracked_env.push("SOURCE_DATE_EPOCH".to_owned());
+                }
+                "status" => {
+                    input.parse::<Token![=]>()?;
+                    ret.acquire.skip_status = !input.parse::<LitBool>()?.value;
+                }
+                "untracked" => {
+                    input.parse::<Token![=]>()?;
+                    let mode: LitStr = input.parse()?;
+                    ret.acquire.untracked = match mode.value().as_str() {
+                        "no" => UntrackedFiles::No,
+                        "normal" => UntrackedFiles::Normal,
+                        "all" => UntrackedFiles::All,
+                        _ => {
+                            return Err(syn::Error::new(
+                                mode.span(),
+                                "Expected untracked = \"no\", \"normal\" or \"all\"",
+                            ))
+                        }
+                    };
+                }
+                "verify_signature" => ret.acquire.verify_signature = true,
+                "trust_signed_tags" => ret.acquire.verify_tags = true,
+                "allowed_signers" => {
+                    input.parse::<Token![=]>()?;
+                    ret.acquire.allowed_signers = Some(manifest_relative(input.parse()?));
+                }
+                "gpg_home" => {
+                    input.parse::<Token![=]>()?;
+                    ret.acquire.gpg_home = Some(manifest_relative(input.parse()?));
+                }
+                "remote" => {
+                    ret.acquire.remote_url = Some(if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        input.parse::<LitStr>()?.value()
+                    } else {
+                        "origin".to_owned()
+                    });
+                }
+                "enforce_semver" => {
+                    ret.enforce_semver = Some(if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        let level: LitStr = input.parse()?;
+                        match level.value().as_str() {
+                            "warn" => Enforcement::Warn,
+                            "error" => Enforcement::Error,
+                            _ => {
+                                return Err(syn::Error::new(
+                                    level.span(),
+                                    "enforce_semver must be either \"warn\" or \"error\"",
+                                ))
+                            }
+                        }
+                    } else {
+                        Enforcement::Error
+                    });
+                }
+                "link_section" => {
+                    ret.link_section = Some(if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        Some(input.parse::<LitStr>()?.value())
+                    } else {
+                        None
+                    });
+                }
+                "export_c" => {
+                    input.parse::<Token![=]>()?;
+                    let symbol: LitStr = input.parse()?;
+                    ret.export_c = Some(symbol.parse().map_err(|_| {
+                        syn::Error::new(symbol.span(), "Expected export_c = \"symbol_name\"")
+                    })?);
+                }
+                "ffi" => {
+                    let prefix = if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        let prefix: LitStr = input.parse()?;
+                        if prefix.parse::<Ident>().is_err() {
+                            return Err(syn::Error::new(
+                                prefix.span(),
+                                "Expected ffi = \"prefix\"",
+                            ));
+                        }
+                        prefix.value()
+                    } else {
+                        "git_testament".to_owned()
+                    };
+                    ret.ffi = Some(prefix);
+                }
+                "artifact" => {
+                    ret.artifact = Some(if input.peek(Token![=]) {
+                        input.parse::<Token![=]>()?;
+                        Some(input.parse::<LitStr>()?.value())
+                    } else {
+                        None
+                    });
+                }
+                "release_tag" => {
+                    input.parse::<Token![=]>()?;
+                    ret.acquire.release_tag = Some(input.parse::<LitStr>()?.value());
+                }
+                "match" => {
+                    input.parse::<Token![=]>()?;
+                    ret.acquire.describe_match = Some(input.parse::<LitStr>()?.value());
+                }
+                "exclude" => {
+                    input.parse::<Token![=]>()?;
+                    ret.acquire.describe_exclude = Some(input.parse::<LitStr>()?.value());
+                }
+                "first_parent" => ret.acquire.describe_first_parent = true,
+                "tag_message" => ret.acquire.tag_message = true,
+                "describe_mode" => {
+                    input.parse::<Token![=]>()?;
+                    let mode: LitStr = input.parse()?;
+                    ret.acquire.describe_mode = match mode.value().as_str() {
+                        "since" => DescribeMode::Since,
+                        "contains" => DescribeMode::Contains,
+                        _ => {
+                            return Err(syn::Error::new(
+                                mode.span(),
+                                "Expected describe_mode = \"since\" or \"contains\"",
+                            ))
+                        }
+                    };
+                }
+                "candidates" => {
+                    input.parse::<Token![=]>()?;
+                    let candidates: LitInt = input.parse()?;
+                    ret.acquire.describe_candidates = Some(candidates.base10_parse()?);
+                }
+                "trusted" => {
+                    input.parse::<Token![=]>()?;
+                    ret.trusted = parse_trusted(input)?;
+                }
+                "channels" => {
+                    input.parse::<Token![=]>()?;
+                    let content;
+                    braced!(content in input);
+                    let pairs =
+                        Punctuated::<ChannelPattern, Token![,]>::parse_terminated(&content)?;
+                    ret.channels = pairs
+                        .into_iter()
+                        .map(|pair| (pair.channel.value(), pair.pattern.value()))
+                        .collect();
+                }
+                "repo_path" => {
+                    input.parse::<Token![=]>()?;
+                    ret.repo_path = Some(input.parse::<LitStr>()?.value());
+                }
+                "repo_env" => {
+                    input.parse::<Token![=]>()?;
+                    let var: LitStr = input.parse()?;
+                    ret.tracked_env.push(var.value());
+                    ret.repo_env = env::var(var.value()).ok().filter(|v| !v.is_empty());
+                }
+                "revision_file" => {
+                    input.parse::<Token![=]>()?;
+                    let path: LitStr = input.parse()?;
+                    ret.acquire.revision_file = Some(path.value().into());
+                }
+                "privacy" => {
+                    input.parse::<Token![=]>()?;
+                    let level: LitStr = input.parse()?;
+                    ret.privacy = match level.value().as_str() {
+                        "full" => Privacy::Full,
+                        "no_paths" => Privacy::NoPaths,
+                        "hash_only" => Privacy::HashOnly,
+                        _ => {
+                            return Err(syn::Error::new(
+                                level.span(),
+                                "privacy must be \"full\", \"no_paths\" or \"hash_only\"",
+                            ))
+                        }
+                    };
+                }
+                "scope" => {
+                    input.parse::<Token![=]>()?;
+                    let scope: LitStr = input.parse()?;
+                    ret.scope_to_crate = match scope.value().as_str() {
+                        "crate" => true,
+                        "repository" => false,
+                        _ => {
+                            return Err(syn::Error::new(
+                                scope.span(),
+                                "scope must be either \"crate\" or \"repository\"",
+                            ))
+                        }
+                    };
+                }
+                "ignore" => {
+                    input.parse::<Token![=]>()?;
+                    let content;
+                    bracketed!(content in input);
+                    let patterns = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+                    ret.acquire
+                        .ignore
+                        .extend(patterns.iter().map(LitStr::value));
+                }
+                "extra" => {
+                    input.parse::<Token![=]>()?;
+                    let content;
+                    // Either a list of pairs, or a map of keys to values
+                    let map = if input.peek(syn::token::Brace) {
+                        braced!(content in input);
+                        true
+                    } else {
+                        bracketed!(content in input);
+                        false
+                    };
+                    while !content.is_empty() {
+                        let (key, value) = if map {
+                            let key: LitStr = content.parse()?;
+                            content.parse::<Token![=>]>()?;
+                            (key, ret.parse_extra_value(&content)?)
+                        } else {
+                            let pair;
+                            parenthesized!(pair in content);
+                            let key: LitStr = pair.parse()?;
+                            pair.parse::<Token![,]>()?;
+                            let value = ret.parse_extra_value(&pair)?;
+                            if !pair.is_empty() {
+                                return Err(pair.error("unexpected tokens after extra value"));
+                            }
+                            (key, value)
+                        };
+                        ret.extras.push((key.value(), value));
+                        if content.is_empty() {
+                            break;
+                        }
+                        content.parse::<Token![,]>()?;
+                    }
+                }
+                "embed_diff" => {
+                    input.parse::<Token![=]>()?;
+                    let limit: LitInt = input.parse()?;
+                    ret.acquire.embed_diff = Some(limit.base10_parse()?);
+                }
+                "short_hash" => {
+                    input.parse::<Token![=]>()?;
+                    let len: LitInt = input.parse()?;
+                    let value = len.base10_parse::<usize>()?;
+                    if !(4..=40).contains(&value) {
+                        return Err(syn::Error::new(
+                            len.span(),
+                            "short_hash must be between 4 and 40 characters",
+                        ));
+                    }
+                    ret.short_hash = Some(value);
+                }
+                other => {
+                    return Err(syn::Error::new(
+                        key.span(),
+                        format!("Unknown testament option `{other}`"),
+                    ))
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Parse the value of an `extra` pair, which is either a string or
+    /// `env("VARIABLE")` (or `env "VARIABLE"`).  The value is `None` if the
+    /// variable is not set.
+    fn parse_extra_value(&mut self, input: ParseStream) -> parse::Result<Option<String>> {
+        if input.peek(LitStr) {
+            return Ok(Some(input.parse::<LitStr>()?.value()));
+        }
+        let func: Ident = input.parse()?;
+        if func != "env" {
+            return Err(syn::Error::new(
+                func.span(),
+                "extra values must be a string or env(\"VARIABLE\")",
+            ));
+        }
+        let var: LitStr = if input.peek(LitStr) {
+            input.parse()?
+        } else {
+            let args;
+            parenthesized!(args in input);
+            args.parse()?
+        };
+        self.tracked_env.push(var.value());
+        let value = env::var(var.value()).ok();
+        self.extras_from_env |= value.is_some();
+        Ok(value)
+    }
+
+    /// The base of a generated testament, which records where each of the
+    /// `fields` came from, along with the extras and build information (see
+    /// `git_testament::Provenance`)
+    fn testament_base(&self, crate_: &Ident, fields: &[(&str, &str)]) -> proc_macro2::TokenStream {
+        let extras = match (
+            self.extras.iter().any(|(_, v)| v.is_some()),
+            self.extras_from_env,
+        ) {
+            (false, _) => None,
+            (true, true) => Some(("extras", "Environment")),
+            (true, false) => Some(("extras", "Invocation")),
+        };
+        let build_info = self.build_info.then_some(("build_info", "Environment"));
+        let ci = self.ci.then_some(("ci", "Environment"));
+        let fields = fields
+            .iter()
+            .chain(extras.iter())
+            .chain(build_info.iter())
+            .chain(ci.iter())
+            .map(|(field, source)| {
+                let field = Ident::new(field, Span::call_site());
+                let source = Ident::new(source, Span::call_site());
+                quote! { #field = #source }
+            });
+        quote! { #crate_::__testament_base!(#(#fields),*) }
+    }
+
+    /// The directory from which to search for the repository.  Relative paths
+    /// are relative to the crate's manifest directory.
+    fn repo_root(&self) -> PathBuf {
+        let manifest_dir =
+            env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR env variable not set");
+        let manifest_dir = Path::new(&manifest_dir);
+        match self.repo_env.as_ref().or(self.repo_path.as_ref()) {
+            Some(path) => manifest_dir.join(path),
+            None => manifest_dir.to_owned(),
+        }
+    }
+
+    /// The first channel with a pattern matching either the tag at the commit
+    /// itself, or the branch
+    fn channel(&self, gitinfo: Option<&GitInformation>) -> Option<&str> {
+        let gitinfo = gitinfo?;
+        let tag = gitinfo
+            .commitinfo
+            .as_ref()
+            .filter(|ci| !ci.tag.is_empty() && ci.distance == 0)
+            .map(|ci| ci.tag.as_str());
+        let names = [tag, gitinfo.branch.as_deref()];
+        self.channels
+            .iter()
+            .find(|(_, pattern)| {
+                names
+                    .iter()
+                    .flatten()
+                    .any(|name| glob_matches(pattern, name))
+            })
+            .map(|(channel, _)| channel.as_str())
+    }
+
+    fn sources(&self) -> &[Source] {
+        // A colocated Jujutsu repository is also a git repository, and git
+        // gives the more complete information, so it goes first
+        self.sources
+            .as_deref()
+            .unwrap_or(&[Source::Git, Source::Jujutsu, Source::Mercurial])
+    }
+
+    fn short_hash_length(&self) -> usize {
+        self.short_hash.unwrap_or(DEFAULT_SHORT_HASH_LENGTH)
+    }
+
+    /// Format the time of an RFC 3339 timestamp as the `date_format` option
+    /// asks, if it was given
+    fn reformat_date(&self, timestamp: &str) -> Option<String> {
+        let format = self.date_format.as_ref()?;
+        OffsetDateTime::parse(timestamp, &Rfc3339)
+            .ok()?
+            .format(format)
+            .ok()
+    }
+}
+
 struct TestamentOptions {
     crate_: Ident,
     name: Ident,
     vis: Option<Visibility>,
+    options: GenerationOptions,
 }
 
 impl Parse for TestamentOptions {
@@ -35,333 +687,587 @@ impl Parse for TestamentOptions {
         } else {
             Some(input.parse()?)
         };
-        Ok(TestamentOptions { crate_, name, vis })
+        let options = GenerationOptions::parse_rest(input)?;
+        Ok(TestamentOptions {
+            crate_,
+            name,
+            vis,
+            options,
+        })
     }
 }
 
 struct StaticTestamentOptions {
     crate_: Ident,
     name: Ident,
-    trusted: Option<LitStr>,
+    trusted: Vec<LitStr>,
+    options: GenerationOptions,
+}
+
+/// Parse the branches to trust, either a single glob pattern or a list of them
+fn parse_trusted(input: ParseStream) -> parse::Result<Vec<LitStr>> {
+    if input.peek(LitStr) {
+        return Ok(vec![input.parse()?]);
+    }
+    let content;
+    bracketed!(content in input);
+    let patterns = Punctuated::<LitStr, Token![,]>::parse_terminated(&content)?;
+    Ok(patterns.into_iter().collect())
+}
+
+/// The path given by `path`, relative to the crate's manifest directory
+fn manifest_relative(path: LitStr) -> PathBuf {
+    let manifest_dir =
+        env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR env variable not set");
+    Path::new(&manifest_dir).join(path.value())
+}
+
+/// Whether `branch` matches any of the `trusted` patterns
+fn is_trusted(trusted: &[String], branch: Option<&str>) -> bool {
+    branch.is_some_and(|branch| trusted.iter().any(|pattern| glob_matches(pattern, branch)))
 }
 
 impl Parse for StaticTestamentOptions {
     fn parse(input: ParseStream) -> parse::Result<Self> {
+        let crate_ = input.parse()?;
+        let name = input.parse()?;
+        let trusted =
+            if input.peek(Token![,]) && (input.peek2(LitStr) || input.peek2(syn::token::Bracket)) {
+                input.parse::<Token![,]>()?;
+                parse_trusted(input)?
+            } else {
+                vec![]
+            };
+        let mut options = GenerationOptions::parse_rest(input)?;
+        // The trusted branches may be given either way, but not both
+        let named = std::mem::take(&mut options.trusted);
+        let trusted = match (trusted.is_empty(), named.first()) {
+            (false, Some(named)) => {
+                return Err(syn::Error::new(
+                    named.span(),
+                    "The trusted branch was already given",
+                ))
+            }
+            (false, None) => trusted,
+            (true, _) => named,
+        };
         Ok(StaticTestamentOptions {
-            crate_: input.parse()?,
-            name: input.parse()?,
-            trusted: input.parse()?,
+            crate_,
+            name,
+            trusted,
+            options,
         })
     }
 }
 
-fn run_git<GD>(dir: GD, args: &[&str]) -> Result<Vec<u8>, Box<dyn Error>>
-where
-    GD: AsRef<Path>,
-{
-    let output = Command::new("git")
-        .args(args)
-        .stdin(Stdio::null())
-        .current_dir(dir)
-        .output()?;
-    if output.status.success() {
-        Ok(output.stdout)
-    } else {
-        Err(String::from_utf8(output.stderr)?.into())
+/// Encode the status entries into the table format understood by
+/// `git_testament::CompressedModifications` and then deflate it.
+///
+/// Each entry is a kind byte, followed by the path length as a little-endian
+/// `u32`, followed by the path bytes.  A rename or copy records the original
+/// path first, and then the new path in the same way.
+fn compress_status(status: &[StatusEntry]) -> Vec<u8> {
+    let mut table = Vec::new();
+    for entry in status {
+        table.push(match entry.status {
+            Added => 0u8,
+            Deleted => 1,
+            Modified => 2,
+            Untracked => 3,
+            Renamed => 4,
+            Copied => 5,
+        });
+        let mut push_path = |path: &[u8]| {
+            table.extend_from_slice(&(path.len() as u32).to_le_bytes());
+            table.extend_from_slice(path);
+        };
+        if let Renamed | Copied = entry.status {
+            push_path(entry.original_path.as_deref().unwrap_or_default());
+        }
+        push_path(&entry.path);
     }
+    compress_to_vec_zlib(&table, 9)
 }
 
-fn find_git_dir() -> Result<PathBuf, Box<dyn Error>> {
-    // run git rev-parse --show-toplevel in the MANIFEST DIR
-    let dir = run_git(
-        env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR env variable not set"),
-        &["rev-parse", "--show-toplevel"],
+/// Acquire the information from the configured sources, with any
+/// `GIT_TESTAMENT_*` overrides from the environment taking precedence.
+///
+/// For a `reproducible` testament, `source_date` replaces the commit dates.
+fn acquire_git_information(
+    options: &GenerationOptions,
+    source_date: Option<&SourceDate>,
+) -> Result<GitInformation, Error> {
+    let manifest_dir =
+        env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR env variable not set");
+    let manifest_dir = Path::new(&manifest_dir);
+    let repo_root = options.repo_root();
+    let mut info = GitInformation::with_overrides(
+        acquire_from_sources(options, &repo_root),
+        &repo_root,
+        |var| env::var(var).ok(),
     )?;
-    // TODO: Find a way to go from the stdout to a pathbuf cleanly
-    // without relying on utf8ness
-    Ok(String::from_utf8(dir)?.trim_end().into())
-}
-
-fn revparse_single(git_dir: &Path, refname: &str) -> Result<(String, i64, i32), Box<dyn Error>> {
-    // TODO: Again, try and remove UTF8 assumptions somehow
-    let sha = String::from_utf8(run_git(git_dir, &["rev-parse", refname])?)?
-        .trim_end()
-        .to_owned();
-    let show = String::from_utf8(run_git(git_dir, &["cat-file", "-p", &sha])?)?;
-
-    for line in show.lines() {
-        if line.starts_with("committer ") {
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 2 {
-                return Err(format!("Insufficient committer data in {line}").into());
-            }
-            let time: i64 = parts[parts.len() - 2].parse()?;
-            let offset: &str = parts[parts.len() - 1];
-            if offset.len() != 5 {
-                return Err(
-                    format!("Insufficient/Incorrect data in timezone offset: {offset}").into(),
-                );
-            }
-            let hours: i32 = offset[1..=2].parse()?;
-            let mins: i32 = offset[3..=4].parse()?;
-            let absoffset: i32 = mins + (hours * 60);
-            let offset: i32 = if offset.starts_with('-') {
-                // Negative...
-                -absoffset
-            } else {
-                // Positive...
-                absoffset
-            };
-            return Ok((sha, time, offset));
-        } else if line.is_empty() {
-            // Ran out of input, without finding committer
-            return Err(format!("Unable to find committer information in {refname}").into());
+    if let (true, Some(source_date)) = (options.reproducible, source_date) {
+        if let Some(commitinfo) = &mut info.commitinfo {
+            commitinfo.date = source_date.date.clone();
+            commitinfo.timestamp = source_date.timestamp.clone();
+        }
+        if let Some(remote) = &mut info.remote {
+            remote.date = source_date.date.clone();
         }
     }
-
-    Err("Somehow fell off the end of the commit data".into())
+    if let Some(commitinfo) = &mut info.commitinfo {
+        if let Some(date) = options.reformat_date(&commitinfo.timestamp) {
+            commitinfo.date = date;
+        }
+    }
+    if options.scope_to_crate {
+        return info.scoped_to(manifest_dir);
+    }
+    Ok(info)
 }
 
-fn branch_name(dir: &Path) -> Result<Option<String>, Box<dyn Error>> {
-    let symref = match run_git(dir, &["symbolic-ref", "-q", "HEAD"]) {
-        Ok(s) => s,
-        Err(_) => run_git(dir, &["name-rev", "--name-only", "HEAD"])?,
-    };
-    let mut name = String::from_utf8(symref)?.trim().to_owned();
-    if name.starts_with("refs/heads/") {
-        name = name[11..].to_owned();
+/// Try each of the configured sources in turn, returning the information from
+/// the first which succeeds, or the errors from all of them.
+fn acquire_from_sources(
+    options: &GenerationOptions,
+    repo_root: &Path,
+) -> Result<GitInformation, Error> {
+    let mut errors = Vec::new();
+    for &source in options.sources() {
+        let info = match source {
+            Source::Git => acquire_git_cached(repo_root, &options.acquire),
+            Source::Jujutsu => GitInformation::acquire_jujutsu(repo_root, &options.acquire),
+            Source::Mercurial => GitInformation::acquire_mercurial(repo_root, &options.acquire),
+        };
+        match info {
+            Ok(info) => return Ok(info),
+            Err(e) => errors.push((source.name(), e)),
+        }
     }
-    if name.is_empty() {
-        Ok(None)
-    } else {
-        Ok(Some(name))
+    if errors.is_empty() {
+        return Err(TestamentError::NoSources);
     }
+    Err(TestamentError::AllSourcesFailed(errors))
 }
 
-fn describe(dir: &Path, sha: &str) -> Result<String, Box<dyn Error>> {
-    // TODO: Work out a way to not use UTF8?
-    Ok(
-        String::from_utf8(run_git(dir, &["describe", "--tags", "--long", sha])?)?
-            .trim_end()
-            .to_owned(),
-    )
+/// Repository information already acquired by this process, along with where
+/// and how it was acquired and the commit at `HEAD` at the time.
+type GitCache = Vec<(PathBuf, AcquireOptions, String, GitInformation)>;
+
+/// The compiler loads a procedural macro crate once and expands every use of
+/// it in a crate from that one process, so several testaments in one crate can
+/// share a single probe of the repository.
+static GIT_CACHE: Mutex<GitCache> = Mutex::new(Vec::new());
+
+/// Acquire information from git, reusing any information already acquired in
+/// the same way by this process.
+///
+/// Checking that `HEAD` has not moved is cheap, and ensures that a long lived
+/// process, such as an editor's macro expander, notices new commits.  It will
+/// not notice edits which leave `HEAD` alone until the next commit though.
+fn acquire_git_cached(start: &Path, options: &AcquireOptions) -> Result<GitInformation, Error> {
+    let head = match head_commit(start) {
+        Some(head) => head,
+        // Without a commit there is little to probe, so no need to cache
+        None => return GitInformation::acquire(start, options),
+    };
+    let mut cache = GIT_CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    let cached = cache
+        .iter()
+        .find(|(path, opts, id, _)| path == start && opts == options && *id == head);
+    if let Some((_, _, _, info)) = cached {
+        return Ok(info.clone());
+    }
+    let info = GitInformation::acquire(start, options)?;
+    cache.retain(|(path, opts, _, _)| !(path == start && opts == options));
+    cache.push((start.to_owned(), options.clone(), head, info.clone()));
+    Ok(info)
 }
 
-#[derive(Clone, Copy)]
-enum StatusFlag {
-    Added,
-    Deleted,
-    Modified,
-    Untracked,
+/// The `source` to record for information which was acquired successfully
+fn testament_source(crate_: &Ident, gitinfo: &GitInformation) -> proc_macro2::TokenStream {
+    match gitinfo.vcs {
+        Vcs::RevisionFile => quote! {#crate_::TestamentSource::RevisionFile},
+        _ if gitinfo.cargo_vcs_info.is_some() => quote! {#crate_::TestamentSource::CargoVcsInfo},
+        Vcs::Git => quote! {#crate_::TestamentSource::Git},
+        Vcs::Jujutsu => quote! {#crate_::TestamentSource::Jujutsu},
+        Vcs::Mercurial => quote! {#crate_::TestamentSource::Mercurial},
+        Vcs::Environment => quote! {#crate_::TestamentSource::Environment},
+    }
 }
-use StatusFlag::*;
 
-#[derive(Clone)]
-struct StatusEntry {
-    path: String,
-    status: StatusFlag,
-}
-
-fn status(dir: &Path) -> Result<Vec<StatusEntry>, Box<dyn Error>> {
-    // TODO: Work out a way to not use UTF8?
-    let info = String::from_utf8(run_git(
-        dir,
-        &[
-            "status",
-            "--porcelain",
-            "--untracked-files=normal",
-            "--ignore-submodules=all",
-        ],
-    )?)?;
-
-    let mut ret = Vec::new();
-
-    for line in info.lines() {
-        let index_change = line.chars().next().unwrap();
-        let worktree_change = line.chars().nth(1).unwrap();
-        match (index_change, worktree_change) {
-            ('?', _) | (_, '?') => ret.push(StatusEntry {
-                path: line[3..].to_owned(),
-                status: Untracked,
-            }),
-            ('A', _) | (_, 'A') => ret.push(StatusEntry {
-                path: line[3..].to_owned(),
-                status: Added,
-            }),
-            ('M', _) | (_, 'M') => ret.push(StatusEntry {
-                path: line[3..].to_owned(),
-                status: Modified,
-            }),
-            ('D', _) | (_, 'D') => ret.push(StatusEntry {
-                path: line[3..].to_owned(),
-                status: Deleted,
-            }),
-            _ => {}
-        }
-    }
-
-    Ok(ret)
+/// Record that the fields which the `GIT_TESTAMENT_*` overrides replaced came
+/// from the environment
+fn mark_overridden<'a>(gitinfo: &GitInformation, provenance: &mut Vec<(&'a str, &'a str)>) {
+    for var in &gitinfo.overridden {
+        let field = match *var {
+            "GIT_TESTAMENT_BRANCH" => "branch_name",
+            "GIT_TESTAMENT_DIRTY" => "modifications",
+            _ => "commit",
+        };
+        match provenance.iter_mut().find(|(name, _)| *name == field) {
+            Some(entry) => entry.1 = "Environment",
+            None => provenance.push((field, "Environment")),
+        }
+    }
 }
 
+#[derive(Clone)]
 struct InvocationInformation {
     pkgver: String,
     now: String,
+    /// The date given by `SOURCE_DATE_EPOCH`, if it was set and understood
+    source_date: Option<SourceDate>,
+    /// A warning to surface to the user, since the build is not what they
+    /// asked for (e.g. a `SOURCE_DATE_EPOCH` which could not be understood)
+    warning: proc_macro2::TokenStream,
 }
 
-impl InvocationInformation {
-    fn acquire() -> Self {
-        let pkgver = env::var("CARGO_PKG_VERSION").unwrap_or_else(|_| "?.?.?".to_owned());
-        let now = OffsetDateTime::now_utc();
-        let now = now.format(DATE_FORMAT).expect("unable to format now");
-        let sde = match env::var("SOURCE_DATE_EPOCH") {
-            Ok(sde) => match sde.parse::<i64>() {
-                Ok(sde) => Some(
-                    OffsetDateTime::from_unix_timestamp(sde)
-                        .expect("couldn't contruct datetime from source date epoch")
-                        .format(DATE_FORMAT)
-                        .expect("couldn't format source date epoch datetime"),
-                ),
-                Err(_) => None,
-            },
-            Err(_) => None,
-        };
-        let now = sde.unwrap_or(now);
+/// Nothing installs a logger in a procedural macro, so without this anything
+/// logged while generating a testament would never be seen.  This prints it
+/// to stderr, which cargo shows when the crate is built.
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
 
-        Self { pkgver, now }
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("git-testament {}: {}", record.level(), record.args());
+        }
     }
+
+    fn flush(&self) {}
 }
 
-#[derive(Clone)]
-struct CommitInfo {
-    id: String,
-    date: String,
-    tag: String,
-    distance: usize,
+static LOGGER: StderrLogger = StderrLogger;
+
+/// Log to stderr at the level chosen by `GIT_TESTAMENT_DEBUG`, or everything
+/// if the `verbose` option was given.  The level is chosen afresh for each
+/// invocation, since the macro is loaded once for the whole crate.
+fn init_logging(verbose: bool) {
+    // This only fails if a logger is already installed, which is then used
+    let _ = log::set_logger(&LOGGER);
+    let level = match env::var(DEBUG_ENV_VAR).as_deref() {
+        Err(_) | Ok("" | "0") => log::LevelFilter::Off,
+        Ok(level) => level.parse().unwrap_or(log::LevelFilter::Warn),
+    };
+    log::set_max_level(if verbose {
+        log::LevelFilter::Trace
+    } else {
+        level
+    });
 }
 
-#[derive(Clone)]
-struct GitInformation {
-    branch: Option<String>,
-    commitinfo: Option<CommitInfo>,
-    status: Vec<StatusEntry>,
-}
-
-impl GitInformation {
-    fn acquire() -> Result<Self, Box<dyn std::error::Error>> {
-        let git_dir = find_git_dir()?;
-        let branch = match branch_name(&git_dir) {
-            Ok(b) => b,
-            Err(e) => {
-                warn!("Unable to determine branch name: {e}");
-                None
+impl InvocationInformation {
+    fn acquire(options: &GenerationOptions) -> Self {
+        init_logging(options.verbose);
+        let pkgver = env::var("CARGO_PKG_VERSION").unwrap_or_else(|_| "?.?.?".to_owned());
+        let now = if options.reproducible {
+            // Today is the one thing which is certain to differ between builds
+            "unknown".to_owned()
+        } else {
+            let now = OffsetDateTime::now_utc();
+            match &options.date_format {
+                Some(format) => now.format(format),
+                None => now.format(DATE_FORMAT),
             }
+            .expect("unable to format now")
         };
-
-        let commitinfo = (|| {
-            let (commit, commit_time, commit_offset) = match revparse_single(&git_dir, "HEAD") {
-                Ok(commit_data) => commit_data,
-                Err(e) => {
-                    warn!("No commit at HEAD: {e}");
-                    return None;
-                }
-            };
-            // Acquire the commit info
-            let commit_id = commit;
-            let naive =
-                OffsetDateTime::from_unix_timestamp(commit_time).expect("Invalid commit time");
-            let offset = UtcOffset::from_whole_seconds(commit_offset * 60)
-                .expect("Invalid UTC offset (seconds)");
-            let commit_time = naive.replace_offset(offset);
-            let commit_date = commit_time
-                .format(DATE_FORMAT)
-                .expect("unable to format commit date");
-
-            let (tag, distance) = match describe(&git_dir, &commit_id) {
-                Ok(res) => {
-                    let res = &res[..res.rfind('-').expect("No commit info in describe!")];
-                    let tag_name = &res[..res.rfind('-').expect("No commit count in describe!")];
-                    let commit_count = res[tag_name.len() + 1..]
-                        .parse::<usize>()
-                        .expect("Unable to parse commit count in describe!");
-                    (tag_name.to_owned(), commit_count)
+        let (now, source_date, warning) = match env::var("SOURCE_DATE_EPOCH") {
+            Ok(sde) => match parse_source_date_epoch(&sde) {
+                Ok(sde) => (
+                    options
+                        .reformat_date(&sde.timestamp)
+                        .unwrap_or_else(|| sde.date.clone()),
+                    Some(sde),
+                    quote! {},
+                ),
+                Err(e) if options.reproducible => {
+                    let message = format!(
+                        "Invalid SOURCE_DATE_EPOCH {sde:?} for a reproducible testament: {e}"
+                    );
+                    (now, None, quote! { ::core::compile_error!(#message); })
                 }
                 Err(e) => {
-                    warn!("No tag info found!\n{:?}", e);
-                    ("".to_owned(), 0)
+                    let message =
+                        format!("Ignoring SOURCE_DATE_EPOCH {sde:?}, using today instead: {e}");
+                    warn!("{message}");
+                    (
+                        now,
+                        None,
+                        build_warning("GitTestamentSourceDateEpochIgnored", &message),
+                    )
                 }
-            };
+            },
+            Err(_) => (now, None, quote! {}),
+        };
 
-            Some(CommitInfo {
-                id: commit_id,
-                date: commit_date,
-                tag,
-                distance,
-            })
-        })();
+        Self {
+            pkgver,
+            now,
+            source_date,
+            warning,
+        }
+    }
+}
 
-        let status = if commitinfo.is_some() {
-            status(&git_dir).expect("Unable to generate status information")
-        } else {
-            vec![]
+/// There is no stable way for a procedural macro to emit a warning, so use
+/// of a deprecated item, named for the problem, will have to do.
+fn build_warning(name: &str, message: &str) -> proc_macro2::TokenStream {
+    let name = Ident::new(name, Span::call_site());
+    quote! {
+        const _: () = {
+            #[deprecated(note = #message)]
+            struct #name;
+            let _ = #name;
         };
-
-        Ok(Self {
-            branch,
-            commitinfo,
-            status,
-        })
     }
 }
 
+/// Report why the testament is less complete than it could be: either the
+/// repository could not be read at all, or some of the information in it
+/// could not be.  The absence of a repository is not reported, since that is
+/// normal for a crate downloaded from crates.io, and nor is `sources = []`.
+///
+/// These are warnings, or errors with the `strict` option.
+fn diagnostics(
+    options: &GenerationOptions,
+    gitinfo: Result<&GitInformation, &Error>,
+) -> proc_macro2::TokenStream {
+    let messages = match gitinfo {
+        Ok(gi) => gi
+            .warnings
+            .iter()
+            .map(|w| format!("The testament may be incomplete: {w}"))
+            .collect(),
+        Err(e) if e.is_no_repository() || matches!(e, TestamentError::NoSources) => vec![],
+        Err(e) => vec![format!(
+            "Unable to read the repository, so the testament only records the crate's version: {e}"
+        )],
+    };
+    let diagnostics = messages.iter().map(|message| {
+        if options.strict {
+            quote! { ::core::compile_error!(#message); }
+        } else {
+            build_warning("GitTestamentIncomplete", message)
+        }
+    });
+    quote! { #(#diagnostics)* }
+}
+
 #[proc_macro]
 pub fn git_testament(input: TokenStream) -> TokenStream {
-    let TestamentOptions { crate_, name, vis } = parse_macro_input!(input);
+    let TestamentOptions {
+        crate_,
+        name,
+        vis,
+        options,
+    } = parse_macro_input!(input);
+
+    if let Some(format) = &options.format {
+        return syn::Error::new(
+            format.span(),
+            "the format option is only supported by git_testament_macros!(), \
+             use render_testament!(NAME, format = \"...\") instead",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let invocation = InvocationInformation::acquire(&options);
+    let gitinfo = acquire_git_information(&options, invocation.source_date.as_ref());
+    testament_const(&crate_, &name, vis, &options, invocation, gitinfo).into()
+}
 
-    let InvocationInformation { pkgver, now } = InvocationInformation::acquire();
-    let gitinfo = match GitInformation::acquire() {
+/// The declaration of the testament `name`, along with anything the options
+/// ask for alongside it, from the information acquired for the invocation
+fn testament_const(
+    crate_: &Ident,
+    name: &Ident,
+    vis: Option<Visibility>,
+    options: &GenerationOptions,
+    invocation: InvocationInformation,
+    gitinfo: Result<GitInformation, Error>,
+) -> proc_macro2::TokenStream {
+    let InvocationInformation {
+        pkgver,
+        now,
+        source_date,
+        warning,
+    } = invocation;
+    // The checks see everything, but only what the privacy level allows is
+    // embedded
+    let channel = match options.channel(gitinfo.as_ref().ok()) {
+        Some(channel) => quote! {#crate_::__core::option::Option::Some(#channel)},
+        None => quote! {#crate_::__core::option::Option::None},
+    };
+    let semver_check = semver_check(options, gitinfo.as_ref().ok(), &pkgver);
+    let requirement_check = requirement_check(options, gitinfo.as_ref().ok());
+    let diagnostics = diagnostics(options, gitinfo.as_ref());
+    let gitinfo = gitinfo.map(|gitinfo| options.privacy.redact(gitinfo));
+    let trusted: Vec<String> = options.trusted.iter().map(LitStr::value).collect();
+    let rendered = render(
+        gitinfo.as_ref().ok(),
+        &pkgver,
+        &now,
+        &trusted,
+        options.short_hash_length(),
+    );
+    // The testament only has room for one pattern, so record the one which
+    // matches the branch, since the branch cannot change after the build
+    let branch = gitinfo.as_ref().ok().and_then(|gi| gi.branch.as_deref());
+    let trusted_branch = trusted
+        .iter()
+        .find(|pattern| is_trusted(std::slice::from_ref(pattern), branch))
+        .or(trusted.first());
+    let trusted_branch = match trusted_branch {
+        Some(trusted) => quote! {#crate_::__core::option::Option::Some(#trusted)},
+        None => quote! {#crate_::__core::option::Option::None},
+    };
+    let link_section = link_section_static(name, options, || rendered.clone());
+    let export_c = export_c_static(options, || rendered.clone());
+    let ffi = ffi_functions(crate_, options, gitinfo.as_ref().ok(), &rendered);
+    let artifact = write_artifact(
+        name,
+        options,
+        gitinfo.as_ref().ok(),
+        (&pkgver, &now, &trusted),
+        &rendered,
+    );
+    let env_tracking = env_tracking(crate_, options);
+    let extra_items = quote! {
+        #warning #diagnostics #link_section #export_c #ffi #artifact #semver_check
+        #requirement_check #env_tracking
+    };
+    let extras = options.extras.iter().filter_map(|(key, value)| {
+        let value = value.as_ref()?;
+        Some(quote! { (#key, #value) })
+    });
+    let build_info = build_info(crate_, options.build_info.then(BuildInfo::acquire).as_ref());
+    let ci = ci(crate_, options.ci.then(CiInfo::detect).flatten().as_ref());
+    let extras = quote! { extras: &[#(#extras),*], build_info: #build_info, ci: #ci, };
+    let build_date_source = match (&source_date, options.reproducible) {
+        (Some(_), _) => "Environment",
+        (None, true) => "Default",
+        (None, false) => "Build",
+    };
+    let gitinfo = match gitinfo {
         Ok(gi) => gi,
         Err(e) => {
             warn!(
                 "Unable to open a repo at {}: {}",
-                env::var("CARGO_MANIFEST_DIR").unwrap(),
+                options.repo_root().display(),
                 e
             );
-            return (quote! {
+            let base = options.testament_base(crate_, &[("commit", build_date_source)]);
+            return quote! {
+                #extra_items
                 #[allow(clippy::needless_update)]
                 #vis const #name: #crate_::GitTestament<'static> = #crate_::GitTestament {
                     commit: #crate_::CommitKind::NoRepository(#pkgver, #now),
-                    .. #crate_::EMPTY_TESTAMENT
+                    rendered: #rendered,
+                    trusted_branch: #trusted_branch,
+                    channel: #channel,
+                    #extras
+                    .. #base
                 };
-            })
-            .into();
+            };
         }
     };
 
+    let source = testament_source(crate_, &gitinfo);
+    let overridden = !gitinfo.overridden.is_empty();
+
     // Second simple preliminary step: attempt to get a branch name to report
     let branch_name = {
-        if let Some(branch) = gitinfo.branch {
+        if let Some(branch) = &gitinfo.branch {
             quote! {#crate_::__core::option::Option::Some(#branch)}
         } else {
             quote! {#crate_::__core::option::Option::None}
         }
     };
+    let git_version = match &gitinfo.git_version {
+        Some(version) if options.git_version => {
+            quote! {#crate_::__core::option::Option::Some(#version)}
+        }
+        _ => quote! {#crate_::__core::option::Option::None},
+    };
+    let remote_url = match &gitinfo.remote_url {
+        Some(url) => quote! {#crate_::__core::option::Option::Some(#url)},
+        None => quote! {#crate_::__core::option::Option::None},
+    };
+    // The CI system only names the branch when `HEAD` is detached
+    let branch_source = if gitinfo.detached && options.acquire.ci_branch && ci_branch().is_some() {
+        "Environment"
+    } else {
+        "Git"
+    };
+    let mut provenance = vec![("branch_name", branch_source)];
+    if options.acquire.remote_url.is_some() {
+        provenance.push(("remote_url", "Git"));
+    }
+    let modifications_collected = gitinfo.status_collected;
 
     // Step one, determine the current commit ID and the date of that commit
     if gitinfo.commitinfo.is_none() {
-        return (quote! {
+        let commit = match (&gitinfo.remote, &gitinfo.cargo_vcs_info) {
+            (Some(remote), _) => {
+                let (remote, id, date) = (&remote.name, &remote.id, &remote.date);
+                quote! {#crate_::CommitKind::FromRemote(#pkgver, #remote, #id, #date)}
+            }
+            (None, Some(vcs_info)) => {
+                let id = &vcs_info.sha1;
+                quote! {#crate_::CommitKind::FromTarball(#id, #pkgver, #now)}
+            }
+            (None, None) => quote! {#crate_::CommitKind::NoCommit(#pkgver, #now)},
+        };
+        let commit_source = match (&gitinfo.remote, &gitinfo.cargo_vcs_info) {
+            (None, None) => build_date_source,
+            _ => "Git",
+        };
+        provenance.push(("commit", commit_source));
+        mark_overridden(&gitinfo, &mut provenance);
+        let base = options.testament_base(crate_, &provenance);
+        return quote! {
+            #extra_items
             #[allow(clippy::needless_update)]
             #vis const #name: #crate_::GitTestament<'static> = #crate_::GitTestament {
-                commit: #crate_::CommitKind::NoCommit(#pkgver, #now),
+                commit: #commit,
+                rendered: #rendered,
+                trusted_branch: #trusted_branch,
+                channel: #channel,
+                #extras
                 branch_name: #branch_name,
-                .. #crate_::EMPTY_TESTAMENT
+                remote_url: #remote_url,
+                git_version: #git_version,
+                source: #source,
+                overridden: #overridden,
+                modifications_collected: #modifications_collected,
+                .. #base
             };
-        })
-        .into();
+        };
     }
 
     let commitinfo = gitinfo.commitinfo.as_ref().unwrap();
+    let worktree_digest = match &gitinfo.worktree_digest {
+        Some(digest) => quote! {#crate_::__core::option::Option::Some(#digest)},
+        None => quote! {#crate_::__core::option::Option::None},
+    };
+    let diff = diff(crate_, gitinfo.diff.as_deref());
+    let lfs_incomplete = gitinfo.lfs_incomplete;
 
-    let commit = if !commitinfo.tag.is_empty() {
+    let commit = if let Some((tag, distance)) = &commitinfo.contained_in {
+        // A later tag contains the commit, which may itself be tagged
+        let (id, date) = (&commitinfo.id, &commitinfo.date);
+        if *distance == 0 {
+            quote! {#crate_::CommitKind::FromTag(#tag, #id, #date, 0usize)}
+        } else {
+            quote! {#crate_::CommitKind::BeforeTag(#tag, #id, #date, #distance)}
+        }
+    } else if !commitinfo.tag.is_empty() {
         // We've a tag
         let (tag, id, date, distance) = (
             &commitinfo.tag,
@@ -372,6 +1278,11 @@ pub fn git_testament(input: TokenStream) -> TokenStream {
         quote! {
             #crate_::CommitKind::FromTag(#tag, #id, #date, #distance)
         }
+    } else if gitinfo.shallow {
+        let (id, date) = (&commitinfo.id, &commitinfo.date);
+        quote! {
+            #crate_::CommitKind::Shallow(#pkgver, #id, #date)
+        }
     } else {
         let (id, date) = (&commitinfo.id, &commitinfo.date);
         quote! {
@@ -379,12 +1290,89 @@ pub fn git_testament(input: TokenStream) -> TokenStream {
         }
     };
 
+    let commit_timestamp = &commitinfo.timestamp;
+    let describe = match &commitinfo.describe {
+        Some(describe) => quote! {#crate_::__core::option::Option::Some(#describe)},
+        None => quote! {#crate_::__core::option::Option::None},
+    };
+    let (tag_message, tag_date) = match &commitinfo.tag_message {
+        Some((message, date)) => (
+            match message.as_str() {
+                "" => quote! {#crate_::__core::option::Option::None},
+                message => quote! {#crate_::__core::option::Option::Some(#message)},
+            },
+            quote! {#crate_::__core::option::Option::Some(#date)},
+        ),
+        None => (
+            quote! {#crate_::__core::option::Option::None},
+            quote! {#crate_::__core::option::Option::None},
+        ),
+    };
+    let commit_count = commitinfo.commit_count;
+    let short_hash_length = options.short_hash_length();
+    let describe_hash_length = commitinfo.abbrev;
+    let parents = &commitinfo.parents;
+    let tags_at_head = &commitinfo.tags_at_head;
+    let is_merge = commitinfo.merge;
+    let shallow = gitinfo.shallow;
+    let worktree = gitinfo.worktree;
+    let bare = gitinfo.bare;
+    let detached = gitinfo.detached;
+    let signed = commitinfo.signed;
+    let signer = match &commitinfo.signer {
+        Some(signer) => quote! {#crate_::__core::option::Option::Some(#signer)},
+        None => quote! {#crate_::__core::option::Option::None},
+    };
+    let (signed_tag, tag_signer) = match &commitinfo.signed_tag {
+        Some((tag, signer)) => (
+            quote! {#crate_::__core::option::Option::Some(#tag)},
+            match signer {
+                Some(signer) => quote! {#crate_::__core::option::Option::Some(#signer)},
+                None => quote! {#crate_::__core::option::Option::None},
+            },
+        ),
+        None => (
+            quote! {#crate_::__core::option::Option::None},
+            quote! {#crate_::__core::option::Option::None},
+        ),
+    };
+
+    let submodules = gitinfo.submodules.iter().map(|submodule| {
+        let (path, commit, dirty) = (&submodule.path, &submodule.commit, submodule.dirty);
+        quote! {
+            #crate_::SubmoduleInfo {
+                path: #path,
+                commit: #commit,
+                dirty: #dirty,
+            }
+        }
+    });
+
+    let release = match &commitinfo.release {
+        Some((tag, distance)) => quote! {
+            #crate_::__core::option::Option::Some(#crate_::ReleaseTag {
+                tag: #tag,
+                distance: #distance,
+            })
+        },
+        None => quote! {#crate_::__core::option::Option::None},
+    };
+
     // Finally, we need to gather the modifications to the tree...
-    let statuses: Vec<_> = gitinfo
-        .status
-        .iter()
-        .map(|status| {
-            let path = status.path.clone().into_bytes();
+    let modifications = if options.compress && !gitinfo.status.is_empty() {
+        let count = gitinfo.status.len();
+        let data = compress_status(&gitinfo.status);
+        quote! {
+            compressed_modifications: #crate_::__core::option::Option::Some(
+                #crate_::CompressedModifications {
+                    count: #count,
+                    data: &[#(#data),*],
+                }
+            ),
+        }
+    } else {
+        let statuses = gitinfo.status.iter().map(|status| {
+            let path = status.path.clone();
             match status.status {
                 Untracked => quote! {
                     #crate_::GitModification::Untracked(&[#(#path),*])
@@ -398,20 +1386,94 @@ pub fn git_testament(input: TokenStream) -> TokenStream {
                 Deleted => quote! {
                     #crate_::GitModification::Removed(&[#(#path),*])
                 },
+                Renamed => {
+                    let original = status.original_path.clone().unwrap_or_default();
+                    quote! {
+                        #crate_::GitModification::Renamed(&[#(#original),*], &[#(#path),*])
+                    }
+                }
+                Copied => {
+                    let original = status.original_path.clone().unwrap_or_default();
+                    quote! {
+                        #crate_::GitModification::Copied(&[#(#original),*], &[#(#path),*])
+                    }
+                }
             }
-        })
-        .collect();
+        });
+        quote! {
+            modifications: &[#(#statuses),*],
+        }
+    };
 
-    (quote! {
+    provenance.extend_from_slice(&[
+        ("commit", "Git"),
+        ("modifications", "Git"),
+        ("shallow", "Git"),
+        ("worktree", "Git"),
+    ]);
+    if options.acquire.release_tag.is_some() {
+        provenance.push(("release", "Git"));
+    }
+    if options.acquire.tag_message {
+        provenance.push(("tag_message", "Git"));
+    }
+    if options.acquire.parents {
+        provenance.push(("parents", "Git"));
+    }
+    if options.acquire.tags_at_head {
+        provenance.push(("tags_at_head", "Git"));
+    }
+    if options.acquire.verify_signature || options.acquire.verify_tags {
+        provenance.push(("signature", "Git"));
+    }
+    if options.acquire.submodules {
+        provenance.push(("submodules", "Git"));
+    }
+    mark_overridden(&gitinfo, &mut provenance);
+    let base = options.testament_base(crate_, &provenance);
+
+    quote! {
+        #extra_items
         #[allow(clippy::needless_update)]
         #vis const #name: #crate_::GitTestament<'static> = #crate_::GitTestament {
             commit: #commit,
-            modifications: &[#(#statuses),*],
+            rendered: #rendered,
+            trusted_branch: #trusted_branch,
+            channel: #channel,
+            #modifications
+            #extras
             branch_name: #branch_name,
-            .. #crate_::EMPTY_TESTAMENT
+            remote_url: #remote_url,
+            git_version: #git_version,
+            source: #source,
+            short_hash_length: #short_hash_length,
+            describe_hash_length: #describe_hash_length,
+            shallow: #shallow,
+            worktree: #worktree,
+            bare: #bare,
+            detached: #detached,
+            signed: #signed,
+            signer: #signer,
+            signed_tag: #signed_tag,
+            tag_signer: #tag_signer,
+            commit_timestamp: #crate_::__core::option::Option::Some(#commit_timestamp),
+            describe: #describe,
+            tag_message: #tag_message,
+            tag_date: #tag_date,
+            commit_count: #crate_::__core::option::Option::Some(#commit_count),
+            parents: &[#(#parents),*],
+            tags_at_head: &[#(#tags_at_head),*],
+            is_merge: #is_merge,
+            submodules: &[#(#submodules),*],
+            release: #release,
+            worktree_digest: #worktree_digest,
+            diff: #diff,
+            lfs_incomplete: #lfs_incomplete,
+            modifications_collected: #modifications_collected,
+            overridden: #overridden,
+            .. #base
         };
-    })
-    .into()
+    }
 }
 
 #[proc_macro]
@@ -420,55 +1482,537 @@ pub fn git_testament_macros(input: TokenStream) -> TokenStream {
         crate_,
         name,
         trusted,
+        options,
     } = parse_macro_input!(input);
+    let invocation = InvocationInformation::acquire(&options);
+    let gitinfo = acquire_git_information(&options, invocation.source_date.as_ref());
+    let warning = invocation.warning.clone();
+    let diagnostics = diagnostics(&options, gitinfo.as_ref());
+    let gitinfo = match gitinfo {
+        Ok(gi) => Some(gi),
+        Err(e) => {
+            warn!(
+                "Unable to open a repo at {}: {}",
+                options.repo_root().display(),
+                e
+            );
+            None
+        }
+    };
+    let trusted: Vec<String> = trusted.iter().map(LitStr::value).collect();
+    let (extra_items, macros) =
+        testament_macros(&crate_, &name, &trusted, &options, invocation, gitinfo);
+
+    (quote! {
+        #warning
+        #diagnostics
+        #extra_items
+        #macros
+    })
+    .into()
+}
+
+/// Generate a testament as both a constant and a set of static string
+/// macros, see `git_testament::git_testament_full!()`
+#[proc_macro]
+pub fn git_testament_full(input: TokenStream) -> TokenStream {
+    let TestamentOptions {
+        crate_,
+        name,
+        vis,
+        options,
+    } = parse_macro_input!(input);
+    // Both are generated from the one acquisition, so they cannot disagree
+    let invocation = InvocationInformation::acquire(&options);
+    let gitinfo = acquire_git_information(&options, invocation.source_date.as_ref());
+    let trusted: Vec<String> = options.trusted.iter().map(LitStr::value).collect();
+    // The constant carries the checks and anything else the options ask for
+    let (_, macros) = testament_macros(
+        &crate_,
+        &name,
+        &trusted,
+        &options,
+        invocation.clone(),
+        gitinfo.as_ref().ok().cloned(),
+    );
+    let testament = testament_const(&crate_, &name, vis, &options, invocation, gitinfo);
+
+    (quote! {
+        #testament
+        #macros
+    })
+    .into()
+}
+
+/// The static string macros for the testament `name`, and separately
+/// anything the options ask for alongside them
+fn testament_macros(
+    crate_: &Ident,
+    name: &Ident,
+    trusted: &[String],
+    options: &GenerationOptions,
+    invocation: InvocationInformation,
+    gitinfo: Option<GitInformation>,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
     let sname = name.to_string();
-    let (pkgver, now, gitinfo, macros) = macro_content(&crate_, &sname);
+    let (pkgver, now, gitinfo, macros) =
+        macro_content(crate_, &sname, options, invocation, gitinfo);
+    let hash_len = options.short_hash_length();
+
+    let embedded = gitinfo
+        .clone()
+        .map(|gitinfo| options.privacy.redact(gitinfo));
+    let testament = render(embedded.as_ref(), &pkgver, &now, trusted, hash_len);
 
-    // Render the testament string
-    let testament = if let Some(gitinfo) = gitinfo {
+    let link_section = link_section_static(name, options, || testament.clone());
+    let export_c = export_c_static(options, || testament.clone());
+    let ffi = ffi_functions(crate_, options, embedded.as_ref(), &testament);
+    let artifact = write_artifact(
+        name,
+        options,
+        embedded.as_ref(),
+        (&pkgver, &now, trusted),
+        &testament,
+    );
+    let semver_check = semver_check(options, gitinfo.as_ref(), &pkgver);
+    let requirement_check = requirement_check(options, gitinfo.as_ref());
+    let env_tracking = env_tracking(crate_, options);
+    let extra_items = quote! {
+        #link_section #export_c #ffi #artifact #semver_check #requirement_check #env_tracking
+    };
+    let mac_testament = concat_ident(&sname, "testament");
+
+    let macros = quote! {
+        #macros
+        #[allow(unused_macros)]
+        macro_rules! #mac_testament { () => {#testament}}
+    };
+    (extra_items, macros)
+}
+
+fn macro_content(
+    crate_: &Ident,
+    prefix: &str,
+    options: &GenerationOptions,
+    invocation: InvocationInformation,
+    gitinfo: Option<GitInformation>,
+) -> (String, String, Option<GitInformation>, impl quote::ToTokens) {
+    let InvocationInformation { pkgver, now, .. } = invocation;
+    let channel = options.channel(gitinfo.as_ref()).map(str::to_owned);
+    // The full information is returned for the checks, but only what the
+    // privacy level allows is embedded
+    let embedded = gitinfo
+        .clone()
+        .map(|gitinfo| options.privacy.redact(gitinfo));
+    let commitinfo = embedded.as_ref().and_then(|gi| gi.commitinfo.as_ref());
+    let taginfo = commitinfo.and_then(described_tag);
+
+    let some_str = |value: Option<&String>| match value {
+        Some(value) => quote! {#crate_::__core::option::Option::Some(#value)},
+        None => quote! {#crate_::__core::option::Option::None},
+    };
+
+    let vcs_info = embedded.as_ref().and_then(|gi| gi.cargo_vcs_info.as_ref());
+    let repo_present = embedded.is_some() && vcs_info.is_none();
+    let remote_url = some_str(embedded.as_ref().and_then(|gi| gi.remote_url.as_ref()));
+    let git_version = some_str(
+        embedded
+            .as_ref()
+            .and_then(|gi| gi.git_version.as_ref())
+            .filter(|_| options.git_version),
+    );
+    let branch_name = some_str(embedded.as_ref().and_then(|gi| gi.branch.as_ref()));
+    let commit_present = commitinfo.is_some() || vcs_info.is_some();
+    let shallow = embedded.as_ref().is_some_and(|gi| gi.shallow);
+    let worktree = embedded.as_ref().is_some_and(|gi| gi.worktree);
+    let bare = embedded.as_ref().is_some_and(|gi| gi.bare);
+    let detached = embedded.as_ref().is_some_and(|gi| gi.detached);
+    let (commit_hash, commit_hash_short, commit_date, commit_timestamp) =
+        match (commitinfo, vcs_info) {
+            (Some(ci), _) => (
+                ci.id.as_str(),
+                short_hash(&ci.id, options.short_hash_length()),
+                ci.date.as_str(),
+                ci.timestamp.as_str(),
+            ),
+            (None, Some(vcs_info)) => (
+                vcs_info.sha1.as_str(),
+                short_hash(&vcs_info.sha1, options.short_hash_length()),
+                now.as_str(),
+                "",
+            ),
+            (None, None) => (pkgver.as_str(), pkgver.as_str(), now.as_str(), ""),
+        };
+    let tag_present = taginfo.is_some();
+    let (tag_name, tag_distance) = taginfo.unwrap_or((pkgver.as_str(), 0));
+    let describe = some_str(commitinfo.and_then(|ci| ci.describe.as_ref()));
+    let tag_message = commitinfo.and_then(|ci| ci.tag_message.as_ref());
+    let tag_date = some_str(tag_message.map(|(_, date)| date));
+    let tag_message = some_str(
+        tag_message
+            .map(|(message, _)| message)
+            .filter(|m| !m.is_empty()),
+    );
+    let commit_count = commitinfo.map_or(0, |ci| ci.commit_count);
+    let release = commitinfo.and_then(|ci| ci.release.as_ref());
+    let release_tag = some_str(release.map(|(tag, _)| tag));
+    let release_distance = release.map(|(_, distance)| *distance).unwrap_or(0);
+    let parents = commitinfo.map_or(&[][..], |ci| &ci.parents[..]);
+    let tags_at_head = commitinfo.map_or(&[][..], |ci| &ci.tags_at_head[..]);
+    let is_merge = commitinfo.is_some_and(|ci| ci.merge);
+    let signed = commitinfo.is_some_and(|ci| ci.signed);
+    let signer = some_str(commitinfo.and_then(|ci| ci.signer.as_ref()));
+    let signed_tag = commitinfo.and_then(|ci| ci.signed_tag.as_ref());
+    let tag_signer = some_str(signed_tag.and_then(|(_, signer)| signer.as_ref()));
+    let signed_tag = some_str(signed_tag.map(|(tag, _)| tag));
+    let channel = some_str(channel.as_ref());
+    let worktree_digest = some_str(embedded.as_ref().and_then(|gi| gi.worktree_digest.as_ref()));
+    let dirty_count = embedded.as_ref().map_or(0, |gi| gi.status.len());
+    let diff = diff(crate_, embedded.as_ref().and_then(|gi| gi.diff.as_deref()));
+    let lfs_incomplete = embedded.as_ref().is_some_and(|gi| gi.lfs_incomplete);
+    let modifications_collected = embedded.as_ref().is_none_or(|gi| gi.status_collected);
+    let overridden = embedded
+        .as_ref()
+        .is_some_and(|gi| !gi.overridden.is_empty());
+    let build_info = options.build_info.then(BuildInfo::acquire);
+    let build_info = build_info.as_ref();
+    let ci = options.ci.then(CiInfo::detect).flatten();
+    let ci = ci.as_ref();
+    let dirty = dirty_count > 0;
+
+    let macros = [
+        ("branch", branch_name),
+        ("repo_present", quote! {#repo_present}),
+        ("commit_present", quote! {#commit_present}),
+        ("tag_present", quote! {#tag_present}),
+        ("commit_hash", quote! {#commit_hash}),
+        ("commit_hash_short", quote! {#commit_hash_short}),
+        ("commit_date", quote! {#commit_date}),
+        ("commit_timestamp", quote! {#commit_timestamp}),
+        ("tag_name", quote! {#tag_name}),
+        ("tag_distance", quote! {#tag_distance}),
+        ("describe", describe),
+        ("commit_count", quote! {#commit_count}),
+        ("tag_message", tag_message),
+        ("tag_date", tag_date),
+        ("release_tag", release_tag),
+        ("release_distance", quote! {#release_distance}),
+        ("parents", quote! {&[#(#parents),*]}),
+        ("tags_at_head", quote! {&[#(#tags_at_head),*]}),
+        ("is_merge", quote! {#is_merge}),
+        ("shallow", quote! {#shallow}),
+        ("worktree", quote! {#worktree}),
+        ("bare", quote! {#bare}),
+        ("detached", quote! {#detached}),
+        ("remote_url", remote_url),
+        ("git_version", git_version),
+        ("signed", quote! {#signed}),
+        ("signer", signer),
+        ("signed_tag", signed_tag),
+        ("tag_signer", tag_signer),
+        ("channel", channel),
+        ("dirty", quote! {#dirty}),
+        ("dirty_count", quote! {#dirty_count}),
+        ("modifications_collected", quote! {#modifications_collected}),
+        ("overridden", quote! {#overridden}),
+        ("worktree_digest", worktree_digest),
+        ("diff", diff),
+        ("lfs_incomplete", quote! {#lfs_incomplete}),
+        (
+            "target",
+            some_str(build_info.and_then(|bi| bi.target.as_ref())),
+        ),
+        (
+            "profile",
+            some_str(build_info.and_then(|bi| bi.profile.as_ref())),
+        ),
+        (
+            "opt_level",
+            some_str(build_info.and_then(|bi| bi.opt_level.as_ref())),
+        ),
+        (
+            "rustc",
+            some_str(build_info.and_then(|bi| bi.rustc.as_ref())),
+        ),
+        ("ci_provider", some_str(ci.map(|ci| &ci.provider))),
+        ("ci_run_id", some_str(ci.and_then(|ci| ci.run_id.as_ref()))),
+        (
+            "ci_job_url",
+            some_str(ci.and_then(|ci| ci.job_url.as_ref())),
+        ),
+    ];
+    let macros = macros.iter().map(|(suffix, value)| {
+        let mac = concat_ident(prefix, suffix);
+        quote! {
+            #[allow(unused_macros)]
+            macro_rules! #mac { () => {#value}}
+        }
+    });
+
+    let formatted = options.format.as_ref().map(|format| {
+        let mac = concat_ident(prefix, "formatted");
+        let value = render_format(&format.value(), embedded.as_ref(), &pkgver, &now);
+        quote! {
+            #[allow(unused_macros)]
+            macro_rules! #mac { () => {#value}}
+        }
+    });
+
+    let mac_extra = concat_ident(prefix, "extra");
+    let extras = options.extras.iter().filter_map(|(key, value)| {
+        let value = value.as_ref()?;
+        Some(quote! { (#key) => {#value}; })
+    });
+
+    let macros = quote! {
+        #(#macros)*
+        #formatted
+        #[allow(unused_macros)]
+        macro_rules! #mac_extra {
+            #(#extras)*
+            ($key:literal) => {
+                #crate_::__core::compile_error!(
+                    #crate_::__core::concat!("No extra value was recorded for ", $key)
+                )
+            };
+        }
+    };
+    (pkgver, now, gitinfo, macros)
+}
+
+/// The `diff` member of a testament, as a byte string literal if there is one
+fn diff(crate_: &Ident, diff: Option<&[u8]>) -> proc_macro2::TokenStream {
+    match diff {
+        Some(diff) => {
+            let diff = proc_macro2::Literal::byte_string(diff);
+            quote! {#crate_::__core::option::Option::Some(#diff)}
+        }
+        None => quote! {#crate_::__core::option::Option::None},
+    }
+}
+
+/// The `build_info` member of a testament, given the information if the
+/// `build_info` option was given
+fn build_info(crate_: &Ident, info: Option<&BuildInfo>) -> proc_macro2::TokenStream {
+    let info = match info {
+        Some(info) => info,
+        None => return quote! {#crate_::__core::option::Option::None},
+    };
+    let [target, profile, opt_level, rustc] =
+        [&info.target, &info.profile, &info.opt_level, &info.rustc].map(|value| match value {
+            Some(value) => quote! {#crate_::__core::option::Option::Some(#value)},
+            None => quote! {#crate_::__core::option::Option::None},
+        });
+    quote! {
+        #crate_::__core::option::Option::Some(#crate_::BuildInfo {
+            target: #target,
+            profile: #profile,
+            opt_level: #opt_level,
+            rustc: #rustc,
+        })
+    }
+}
+
+/// The `ci` member of a testament, given the CI run if the `ci` option was
+/// given and one was detected
+fn ci(crate_: &Ident, info: Option<&CiInfo>) -> proc_macro2::TokenStream {
+    let info = match info {
+        Some(info) => info,
+        None => return quote! {#crate_::__core::option::Option::None},
+    };
+    let provider = &info.provider;
+    let [run_id, job_url] = [&info.run_id, &info.job_url].map(|value| match value {
+        Some(value) => quote! {#crate_::__core::option::Option::Some(#value)},
+        None => quote! {#crate_::__core::option::Option::None},
+    });
+    quote! {
+        #crate_::__core::option::Option::Some(#crate_::CiInfo {
+            provider: #provider,
+            run_id: #run_id,
+            job_url: #job_url,
+        })
+    }
+}
+
+/// Render a format string in the same way as `GitTestament::render_format()`
+/// would for the same information.
+fn render_format(
+    format: &str,
+    gitinfo: Option<&GitInformation>,
+    pkgver: &str,
+    now: &str,
+) -> String {
+    let commitinfo = gitinfo.and_then(|gi| gi.commitinfo.as_ref());
+    let placeholder = |placeholder: &str| -> Option<String> {
+        let (name, arg) = match placeholder.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (placeholder, None),
+        };
+        let hash = match (
+            commitinfo,
+            gitinfo.and_then(|gi| gi.cargo_vcs_info.as_ref()),
+        ) {
+            (Some(ci), _) => ci.id.as_str(),
+            (None, Some(vcs_info)) => vcs_info.sha1.as_str(),
+            (None, None) => "",
+        };
+        Some(match (name, arg) {
+            ("tag", None) => match commitinfo {
+                Some(ci) if described_tag(ci).is_some() => described_tag(ci)?.0.to_owned(),
+                Some(_) if !gitinfo?.shallow => "unknown".to_owned(),
+                _ => pkgver.to_owned(),
+            },
+            ("hash", None) => hash.to_owned(),
+            ("hash", Some(len)) => short_hash(hash, len.parse().ok()?).to_owned(),
+            ("date", None) => match (commitinfo, gitinfo) {
+                (Some(ci), _) => ci.date.clone(),
+                (None, Some(gi)) if gi.remote.is_some() => String::new(),
+                _ => now.to_owned(),
+            },
+            ("distance", None) => commitinfo
+                .and_then(described_tag)
+                .map_or(0, |(_, distance)| distance)
+                .to_string(),
+            ("dirty", None) => match gitinfo {
+                Some(gi) if !gi.status.is_empty() => "dirty".to_owned(),
+                _ => String::new(),
+            },
+            ("branch", None) => gitinfo.and_then(|gi| gi.branch.clone()).unwrap_or_default(),
+            _ => return None,
+        })
+    };
+
+    let mut out = String::with_capacity(format.len());
+    let mut rest = format;
+    while let Some(pos) = rest.find(['{', '}']) {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if let Some(after) = rest.strip_prefix("{{") {
+            out.push('{');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("}}") {
+            out.push('}');
+            rest = after;
+        } else {
+            match rest[1..].split_once('}') {
+                Some((name, after)) if rest.starts_with('{') => match placeholder(name) {
+                    Some(value) => {
+                        out.push_str(&value);
+                        rest = after;
+                    }
+                    None => {
+                        out.push('{');
+                        rest = &rest[1..];
+                    }
+                },
+                _ => {
+                    // Not something we understand, so leave it be
+                    out.push_str(&rest[..1]);
+                    rest = &rest[1..];
+                }
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The tag the commit is described by and the distance from it, as the
+/// generated `CommitKind` records them.  With `describe_mode = "contains"`
+/// this is the tag containing the commit, which the commit is before.
+fn described_tag(commitinfo: &CommitInfo) -> Option<(&str, usize)> {
+    match &commitinfo.contained_in {
+        Some((tag, distance)) => Some((tag, *distance)),
+        None if commitinfo.tag.is_empty() => None,
+        None => Some((&commitinfo.tag, commitinfo.distance)),
+    }
+}
+
+/// Whether a tagged commit is rendered with the crate's version because it
+/// was built from a clean tree on a trusted branch, or at a signed tag
+fn trusted_build(gitinfo: &GitInformation, commitinfo: &CommitInfo, trusted: &[String]) -> bool {
+    (is_trusted(trusted, gitinfo.branch.as_deref()) || commitinfo.signed_tag.is_some())
+        && gitinfo.status_collected
+        && gitinfo.status.is_empty()
+}
+
+/// Render the testament string in the same way as `render_testament!()`
+/// would render the `git_testament!()` for the same information.
+fn render(
+    gitinfo: Option<&GitInformation>,
+    pkgver: &str,
+    now: &str,
+    trusted: &[String],
+    hash_len: usize,
+) -> String {
+    if let Some(gitinfo) = gitinfo {
         let commitstr = if let Some(ref commitinfo) = gitinfo.commitinfo {
-            if commitinfo.tag.is_empty() {
+            match described_tag(commitinfo) {
+                // No tag, but that's likely because of a shallow clone
+                None if gitinfo.shallow => format!(
+                    "{} (shallow {} {})",
+                    pkgver,
+                    short_hash(&commitinfo.id, hash_len),
+                    commitinfo.date
+                ),
                 // No tag
-                format!("unknown ({} {})", &commitinfo.id[..9], commitinfo.date)
-            } else {
-                let trusted = if gitinfo.branch == trusted.map(|v| v.value()) {
-                    gitinfo.status.is_empty()
-                } else {
-                    false
-                };
+                None => format!(
+                    "unknown ({} {})",
+                    short_hash(&commitinfo.id, hash_len),
+                    commitinfo.date
+                ),
                 // Full behaviour
-                if trusted {
-                    format!("{} ({} {})", pkgver, &commitinfo.id[..9], commitinfo.date)
-                } else {
-                    let basis = if commitinfo.distance > 0 {
-                        format!(
-                            "{}+{} ({} {})",
-                            commitinfo.tag,
-                            commitinfo.distance,
-                            &commitinfo.id[..9],
-                            commitinfo.date
-                        )
-                    } else {
-                        // Not dirty
-                        format!(
-                            "{} ({} {})",
-                            commitinfo.tag,
-                            &commitinfo.id[..9],
-                            commitinfo.date
-                        )
+                Some(_) if trusted_build(gitinfo, commitinfo, trusted) => format!(
+                    "{} ({} {})",
+                    pkgver,
+                    short_hash(&commitinfo.id, hash_len),
+                    commitinfo.date
+                ),
+                Some((tag, distance)) => {
+                    // Before a containing tag, or after the nearest one
+                    let position = match (distance, &commitinfo.contained_in) {
+                        (0, _) => String::new(),
+                        (_, Some(_)) => format!("~{distance}"),
+                        (_, None) => format!("+{distance}"),
                     };
-                    if commitinfo.tag.contains(&pkgver) {
+                    let basis = format!(
+                        "{}{} ({} {})",
+                        tag,
+                        position,
+                        short_hash(&commitinfo.id, hash_len),
+                        commitinfo.date
+                    );
+                    if tag.contains(pkgver) {
                         basis
                     } else {
                         format!("{pkgver} :: {basis}")
                     }
                 }
             }
+        } else if let Some(ref remote) = gitinfo.remote {
+            // We're in a repo with no commit, but we know what it's based on
+            format!(
+                "{} (uncommitted, {} {} {})",
+                pkgver,
+                remote.name,
+                short_hash(&remote.id, hash_len),
+                remote.date
+            )
+        } else if let Some(ref vcs_info) = gitinfo.cargo_vcs_info {
+            // We're in a package, which knows the commit it was made from
+            format!(
+                "{} (tarball {} {})",
+                pkgver,
+                short_hash(&vcs_info.sha1, hash_len),
+                now
+            )
         } else {
             // We're in a repo, but with no commit
             format!("{pkgver} (uncommitted {now})")
         };
-        if gitinfo.status.is_empty() {
+        if !gitinfo.status_collected {
+            format!("{commitstr} maybe dirty")
+        } else if gitinfo.status.is_empty() {
             commitstr
         } else {
             format!(
@@ -481,144 +2025,401 @@ pub fn git_testament_macros(input: TokenStream) -> TokenStream {
     } else {
         // No git information whatsoever
         format!("{pkgver} ({now})")
+    }
+}
+
+/// If the `enforce_semver` option was given, check that the crate's version is
+/// consistent with the nearest tag, if that tag is a semantic version.
+///
+/// At the tag itself the versions must be equal, and after the tag the crate's
+/// version must not be older than the tag.  Any inconsistency produces either
+/// a compile error or a deprecation warning, depending on the enforcement.
+fn semver_check(
+    options: &GenerationOptions,
+    gitinfo: Option<&GitInformation>,
+    pkgver: &str,
+) -> proc_macro2::TokenStream {
+    let enforcement = match options.enforce_semver {
+        Some(enforcement) => enforcement,
+        None => return quote! {},
+    };
+    let commitinfo = match gitinfo.and_then(|gi| gi.commitinfo.as_ref()) {
+        Some(ci) if !ci.tag.is_empty() => ci,
+        _ => return quote! {},
+    };
+    let tag = commitinfo
+        .tag
+        .strip_prefix(['v', 'V'])
+        .unwrap_or(&commitinfo.tag);
+    let (tag_version, pkg_version) = match (Version::parse(tag), Version::parse(pkgver)) {
+        (Ok(tag_version), Ok(pkg_version)) => (tag_version, pkg_version),
+        _ => return quote! {},
     };
+    let consistent = if commitinfo.distance == 0 {
+        pkg_version == tag_version
+    } else {
+        pkg_version >= tag_version
+    };
+    if consistent {
+        return quote! {};
+    }
+    let message = format!(
+        "crate version {} is inconsistent with tag {}{}",
+        pkgver,
+        commitinfo.tag,
+        if commitinfo.distance == 0 {
+            String::new()
+        } else {
+            format!(" ({} commits ago)", commitinfo.distance)
+        }
+    );
+    match enforcement {
+        Enforcement::Error => syn::Error::new(Span::call_site(), message).to_compile_error(),
+        Enforcement::Warn => build_warning("GitTestamentSemverMismatch", &message),
+    }
+}
 
-    let mac_testament = concat_ident(&sname, "testament");
+/// If one of the `require_*` options was given, fail the build if the
+/// information it requires is not available.
+///
+/// A package made by `cargo package` counts as having a repository and a
+/// commit, but not a tag.
+fn requirement_check(
+    options: &GenerationOptions,
+    gitinfo: Option<&GitInformation>,
+) -> proc_macro2::TokenStream {
+    let requirement = match options.require {
+        Some(requirement) => requirement,
+        None => return quote! {},
+    };
+    let option = requirement.option_name();
+    let problem = match gitinfo {
+        None => format!(
+            "no repository was found at {}",
+            options.repo_root().display()
+        ),
+        Some(gi) if gi.cargo_vcs_info.is_some() => match requirement {
+            Requirement::Tag => {
+                "the crate was built from a package, which records no tag".to_owned()
+            }
+            _ => return quote! {},
+        },
+        Some(gi) => match &gi.commitinfo {
+            None if requirement >= Requirement::Commit => {
+                "the git repository has no commits".to_owned()
+            }
+            Some(ci) if requirement == Requirement::Tag && ci.tag.is_empty() => {
+                let mut problem = format!("no tag was found in the history of {}", ci.id);
+                if gi.shallow {
+                    problem
+                        .push_str(" (this is a shallow clone, try `git fetch --unshallow --tags`)");
+                }
+                problem
+            }
+            _ => return quote! {},
+        },
+    };
+    syn::Error::new(
+        Span::call_site(),
+        format!("{problem}, but the `{option}` option was given"),
+    )
+    .to_compile_error()
+}
 
-    (quote! {
-            #macros
-            #[allow(unused_macros)]
-            macro_rules! #mac_testament { () => {#testament}}
-    })
-    .into()
+/// Procedural macros cannot tell cargo which environment variables they read,
+/// so use `option_env!()` on them to have the crate rebuilt when they change.
+fn env_tracking(crate_: &Ident, options: &GenerationOptions) -> proc_macro2::TokenStream {
+    let vars = options
+        .tracked_env
+        .iter()
+        .map(String::as_str)
+        .chain(DISCOVERY_ENV_VARS.iter().copied())
+        .chain(OVERRIDE_ENV_VARS.iter().copied())
+        .chain([DEBUG_ENV_VAR]);
+    quote! {
+        #(
+            const _: #crate_::__core::option::Option<&str> =
+                #crate_::__core::option_env!(#vars);
+        )*
+    }
 }
 
-fn macro_content(
+/// If the `link_section` option was given, produce a static containing the
+/// NUL terminated rendered testament, placed into the requested link section
+/// so that it can be found in the binary without running it.
+fn link_section_static(
+    name: &Ident,
+    options: &GenerationOptions,
+    rendered: impl FnOnce() -> String,
+) -> proc_macro2::TokenStream {
+    let section = match &options.link_section {
+        None => return quote! {},
+        Some(Some(section)) => quote! {
+            #[unsafe(link_section = #section)]
+        },
+        Some(None) => quote! {
+            #[cfg_attr(target_vendor = "apple", unsafe(link_section = "__DATA,__git_testament"))]
+            #[cfg_attr(windows, unsafe(link_section = ".gittest"))]
+            #[cfg_attr(
+                not(any(target_vendor = "apple", windows)),
+                unsafe(link_section = ".git_testament")
+            )]
+        },
+    };
+    let mut bytes = rendered().into_bytes();
+    bytes.push(0);
+    let len = bytes.len();
+    let bytes = proc_macro2::Literal::byte_string(&bytes);
+    let ident = concat_ident("__GIT_TESTAMENT_SECTION", &name.to_string());
+    quote! {
+        #[used]
+        #section
+        #[allow(non_upper_case_globals)]
+        static #ident: [u8; #len] = *#bytes;
+    }
+}
+
+/// If the `export_c` option was given, produce a static containing the NUL
+/// terminated rendered testament, exported unmangled under the given symbol
+/// so that C code linked into the same binary can read it.
+fn export_c_static(
+    options: &GenerationOptions,
+    rendered: impl FnOnce() -> String,
+) -> proc_macro2::TokenStream {
+    let Some(symbol) = &options.export_c else {
+        return quote! {};
+    };
+    let mut bytes = rendered().into_bytes();
+    bytes.push(0);
+    let len = bytes.len();
+    let bytes = proc_macro2::Literal::byte_string(&bytes);
+    quote! {
+        #[unsafe(no_mangle)]
+        #[allow(non_upper_case_globals)]
+        pub static #symbol: [u8; #len] = *#bytes;
+    }
+}
+
+/// If the `ffi` option was given, produce `extern "C"` functions, named with
+/// the given prefix, which return the parts of the testament for C code.
+/// Strings are NUL terminated and static, or NULL if they were not recorded.
+fn ffi_functions(
     crate_: &Ident,
-    prefix: &str,
-) -> (String, String, Option<GitInformation>, impl quote::ToTokens) {
-    let InvocationInformation { pkgver, now } = InvocationInformation::acquire();
-    let mac_branch = concat_ident(prefix, "branch");
-    let mac_repo_present = concat_ident(prefix, "repo_present");
-    let mac_commit_present = concat_ident(prefix, "commit_present");
-    let mac_tag_present = concat_ident(prefix, "tag_present");
-    let mac_commit_hash = concat_ident(prefix, "commit_hash");
-    let mac_commit_date = concat_ident(prefix, "commit_date");
-    let mac_tag_name = concat_ident(prefix, "tag_name");
-    let mac_tag_distance = concat_ident(prefix, "tag_distance");
-    let gitinfo = match GitInformation::acquire() {
-        Ok(gi) => gi,
-        Err(e) => {
-            warn!(
-                "Unable to open a repo at {}: {}",
-                env::var("CARGO_MANIFEST_DIR").unwrap(),
-                e
-            );
-            return (
-                pkgver.clone(),
-                now.clone(),
-                None,
-                quote! {
-                    #[allow(unused_macros)]
-                    macro_rules! #mac_branch { () => {None}}
-                    #[allow(unused_macros)]
-                    macro_rules! #mac_repo_present { () => {false}}
-                    #[allow(unused_macros)]
-                    macro_rules! #mac_commit_present { () => {false}}
-                    #[allow(unused_macros)]
-                    macro_rules! #mac_tag_present { () => {false}}
-                    #[allow(unused_macros)]
-                    macro_rules! #mac_commit_hash { () => {#pkgver}}
-                    #[allow(unused_macros)]
-                    macro_rules! #mac_commit_date { () => {#now}}
-                    #[allow(unused_macros)]
-                    macro_rules! #mac_tag_name { () => {#pkgver}}
-                    #[allow(unused_macros)]
-                    macro_rules! #mac_tag_distance { () => {0}}
-                },
-            );
+    options: &GenerationOptions,
+    gitinfo: Option<&GitInformation>,
+    rendered: &str,
+) -> proc_macro2::TokenStream {
+    let Some(prefix) = &options.ffi else {
+        return quote! {};
+    };
+    let c_str = |value: Option<&str>| match value {
+        Some(value) => {
+            let mut bytes = value.as_bytes().to_vec();
+            bytes.push(0);
+            let bytes = proc_macro2::Literal::byte_string(&bytes);
+            quote! { #bytes.as_ptr().cast() }
         }
+        None => quote! { #crate_::__core::ptr::null() },
     };
+    let commitinfo = gitinfo.and_then(|gi| gi.commitinfo.as_ref());
+    let vcs_info = gitinfo.and_then(|gi| gi.cargo_vcs_info.as_ref());
+    let taginfo = commitinfo.and_then(described_tag);
+    let commit_hash = c_str(
+        commitinfo
+            .map(|ci| ci.id.as_str())
+            .or(vcs_info.map(|vcs_info| vcs_info.sha1.as_str())),
+    );
+    let commit_date = c_str(commitinfo.map(|ci| ci.date.as_str()));
+    let tag = c_str(taginfo.map(|(tag, _)| tag));
+    let tag_distance = taginfo.map_or(-1, |(_, distance)| distance as i64);
+    let branch = c_str(gitinfo.and_then(|gi| gi.branch.as_deref()));
+    let modification_count = gitinfo.map_or(0, |gi| gi.status.len());
+    let is_dirty = modification_count > 0;
+    let rendered = c_str(Some(rendered));
 
-    let branch_name = {
-        if let Some(ref branch) = gitinfo.branch {
-            quote! {#crate_::__core::option::Option::Some(#branch)}
-        } else {
-            quote! {#crate_::__core::option::Option::None}
+    let strings = [
+        ("rendered", rendered),
+        ("commit_hash", commit_hash),
+        ("commit_date", commit_date),
+        ("tag", tag),
+        ("branch", branch),
+    ];
+    let strings = strings.iter().map(|(suffix, value)| {
+        let function = concat_ident(prefix, suffix);
+        quote! {
+            #[unsafe(no_mangle)]
+            pub extern "C" fn #function() -> *const #crate_::__core::ffi::c_char {
+                #value
+            }
         }
+    });
+    let tag_distance_fn = concat_ident(prefix, "tag_distance");
+    let is_dirty_fn = concat_ident(prefix, "is_dirty");
+    let modification_count_fn = concat_ident(prefix, "modification_count");
+    quote! {
+        const _: () = {
+            #(#strings)*
+            #[unsafe(no_mangle)]
+            pub extern "C" fn #tag_distance_fn() -> i64 {
+                #tag_distance
+            }
+            #[unsafe(no_mangle)]
+            pub extern "C" fn #is_dirty_fn() -> bool {
+                #is_dirty
+            }
+            #[unsafe(no_mangle)]
+            pub extern "C" fn #modification_count_fn() -> usize {
+                #modification_count
+            }
+        };
+    }
+}
+
+/// Write the testament to the file chosen by the `artifact` option, as JSON
+/// with the same keys as `render_testament_json!()` and the rendered testament
+/// as `rendered`.  The file is only rewritten if it would change.
+fn write_artifact(
+    name: &Ident,
+    options: &GenerationOptions,
+    gitinfo: Option<&GitInformation>,
+    (pkgver, now, trusted): (&str, &str, &[String]),
+    rendered: &str,
+) -> proc_macro2::TokenStream {
+    let path = match &options.artifact {
+        None => return quote! {},
+        Some(Some(path)) => {
+            let manifest_dir =
+                env::var_os("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR env variable not set");
+            Path::new(&manifest_dir).join(path)
+        }
+        Some(None) => match env::var_os("OUT_DIR") {
+            Some(out_dir) => Path::new(&out_dir).join(format!("{name}.json")),
+            None => {
+                return quote! {
+                    ::core::compile_error!(
+                        "OUT_DIR is only set for crates with a build script, \
+                         give a path with artifact = \"...\" instead"
+                    );
+                }
+            }
+        },
     };
+    let json = artifact_json(gitinfo, pkgver, now, trusted, rendered);
+    if fs::read_to_string(&path).is_ok_and(|existing| existing == json) {
+        return quote! {};
+    }
+    let written = match path.parent() {
+        Some(parent) => fs::create_dir_all(parent),
+        None => Ok(()),
+    }
+    .and_then(|()| fs::write(&path, json));
+    match written {
+        Ok(()) => quote! {},
+        Err(e) => {
+            let message = format!("Unable to write {}: {e}", path.display());
+            quote! { ::core::compile_error!(#message); }
+        }
+    }
+}
 
-    let basics = quote! {
-        #[allow(unused_macros)]
-        macro_rules! #mac_repo_present { () => {true}}
-        #[allow(unused_macros)]
-        macro_rules! #mac_branch { () => {#branch_name}}
+/// The JSON written by [`write_artifact`], which matches what
+/// `GitTestament::to_json` produces for the same testament
+fn artifact_json(
+    gitinfo: Option<&GitInformation>,
+    pkgver: &str,
+    now: &str,
+    trusted: &[String],
+    rendered: &str,
+) -> String {
+    let commitinfo = gitinfo.and_then(|gi| gi.commitinfo.as_ref());
+    let remote = gitinfo.and_then(|gi| gi.remote.as_ref());
+    let vcs_info = gitinfo.and_then(|gi| gi.cargo_vcs_info.as_ref());
+    let (commit, tag, date) = match (commitinfo, remote, vcs_info) {
+        (Some(ci), ..) => {
+            let tag = match (gitinfo, described_tag(ci)) {
+                (_, None) => None,
+                (Some(gi), Some(_)) if trusted_build(gi, ci, trusted) => Some((pkgver, 0)),
+                (_, tag) => tag,
+            };
+            (Some(&ci.id), tag, Some(ci.date.as_str()))
+        }
+        (None, Some(remote), _) => (Some(&remote.id), None, None),
+        (None, None, Some(vcs_info)) => (Some(&vcs_info.sha1), None, Some(now)),
+        (None, None, None) => (None, None, Some(now)),
     };
+    let status = gitinfo.map_or(&[][..], |gi| &gi.status[..]);
 
-    // Step one, determine the current commit ID and the date of that commit
-    if gitinfo.commitinfo.is_none() {
-        return (
-            pkgver.clone(),
-            now.clone(),
-            Some(gitinfo),
-            quote! {
-                #basics
-                #[allow(unused_macros)]
-                macro_rules! #mac_commit_present { () => {false}}
-                #[allow(unused_macros)]
-                macro_rules! #mac_tag_present { () => {false}}
-                #[allow(unused_macros)]
-                macro_rules! #mac_commit_hash { () => {#pkgver}}
-                #[allow(unused_macros)]
-                macro_rules! #mac_commit_date { () => {#now}}
-                #[allow(unused_macros)]
-                macro_rules! #mac_tag_name { () => {#pkgver}}
-                #[allow(unused_macros)]
-                macro_rules! #mac_tag_distance { () => {0}}
-            },
-        );
+    let mut out = String::from("{\"version\":");
+    push_json_string(&mut out, pkgver);
+    out.push_str(",\"commit\":");
+    push_json_optional(&mut out, commit.map(String::as_str));
+    out.push_str(",\"tag\":");
+    push_json_optional(&mut out, tag.map(|(tag, _)| tag));
+    out.push_str(",\"distance\":");
+    match tag {
+        Some((_, distance)) => out.push_str(&distance.to_string()),
+        None => out.push_str("null"),
     }
+    out.push_str(",\"date\":");
+    push_json_optional(&mut out, date);
+    out.push_str(",\"branch\":");
+    push_json_optional(&mut out, gitinfo.and_then(|gi| gi.branch.as_deref()));
+    out.push_str(&format!(
+        ",\"dirty\":{},\"modifications\":[",
+        !status.is_empty()
+    ));
+    for (i, entry) in status.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let kind = match entry.status {
+            Added => "added",
+            Deleted => "removed",
+            Modified => "modified",
+            Renamed => "renamed",
+            Copied => "copied",
+            Untracked => "untracked",
+        };
+        out.push_str(&format!("{{\"kind\":\"{kind}\",\"path\":"));
+        push_json_string(&mut out, &String::from_utf8_lossy(&entry.path));
+        if let Some(original) = &entry.original_path {
+            out.push_str(",\"from\":");
+            push_json_string(&mut out, &String::from_utf8_lossy(original));
+        }
+        out.push('}');
+    }
+    out.push_str("],\"rendered\":");
+    push_json_string(&mut out, rendered);
+    out.push('}');
+    out
+}
 
-    let commitinfo = gitinfo.commitinfo.as_ref().unwrap();
-    let (commit_hash, commit_date) = (&commitinfo.id, &commitinfo.date);
-    let (tag, distance) = (&commitinfo.tag, commitinfo.distance);
+fn push_json_optional(out: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => push_json_string(out, value),
+        None => out.push_str("null"),
+    }
+}
 
-    let basics = quote! {
-        #basics
-        #[allow(unused_macros)]
-        macro_rules! #mac_commit_present { () => {true}}
-        #[allow(unused_macros)]
-        macro_rules! #mac_commit_hash { () => {#commit_hash}}
-        #[allow(unused_macros)]
-        macro_rules! #mac_commit_date { () => {#commit_date}}
-    };
+/// Push `value` as a quoted JSON string, escaping as needed
+fn push_json_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
 
-    (
-        pkgver.clone(),
-        now,
-        Some(gitinfo.clone()),
-        if commitinfo.tag.is_empty() {
-            quote! {
-                #basics
-                #[allow(unused_macros)]
-                macro_rules! #mac_tag_present { () => {false}}
-                #[allow(unused_macros)]
-                macro_rules! #mac_tag_name { () => {#pkgver}}
-                #[allow(unused_macros)]
-                macro_rules! #mac_tag_distance { () => {0}}
-            }
-        } else {
-            quote! {
-                #basics
-                #[allow(unused_macros)]
-                macro_rules! #mac_tag_present { () => {true}}
-                #[allow(unused_macros)]
-                macro_rules! #mac_tag_name { () => {#tag}}
-                #[allow(unused_macros)]
-                macro_rules! #mac_tag_distance { () => {#distance}}
-            }
-        },
-    )
+fn short_hash(id: &str, len: usize) -> &str {
+    id.get(..len).unwrap_or(id)
 }
 
 fn concat_ident(prefix: &str, suffix: &str) -> Ident {
@@ -0,0 +1,104 @@
+//! Integration with [`clap`](https://docs.rs/clap) for `--version` output
+//!
+//! The [`version!`] macro renders a testament as [`render_testament!`] would,
+//! for use as a command's version.  The [`long_version!`] macro additionally
+//! lists the full commit hash, the branch, and any modifications to the
+//! working tree, for use as the long version shown by `--version` when the
+//! command also has a short `-V`.
+//!
+//! ```
+//! use git_testament::git_testament;
+//!
+//! git_testament!(TESTAMENT);
+//!
+//! # fn main() {
+//! let command = clap::Command::new("my-program")
+//!     .version(git_testament::clap::version!(TESTAMENT))
+//!     .long_version(git_testament::clap::long_version!(TESTAMENT));
+//! # let _ = command;
+//! # }
+//! ```
+//!
+//! Both macros accept a trusted branch as a second argument, just as
+//! [`render_testament!`] does.
+//!
+//! [`version!`]: crate::clap::version
+//! [`long_version!`]: crate::clap::long_version
+//! [`render_testament!`]: crate::render_testament
+
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::{GitModification, GitTestament};
+
+#[doc(inline)]
+pub use crate::__clap_long_version as long_version;
+#[doc(inline)]
+pub use crate::__clap_version as version;
+
+/// Render a testament for use as a command's version, see the
+/// [module documentation](crate::clap).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __clap_version {
+    ($testament:expr $(, $($trusted_branch:tt)+)?) => {
+        $crate::__clap::builder::Str::from(
+            $crate::render_testament!($testament $(, $($trusted_branch)+)?),
+        )
+    };
+}
+
+/// Render a testament, with the commit, branch and modifications, for use as
+/// a command's long version, see the [module documentation](crate::clap).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __clap_long_version {
+    ($testament:expr $(, $($trusted_branch:tt)+)?) => {
+        $crate::clap::_long_version(
+            &$testament,
+            $crate::render_testament!($testament $(, $($trusted_branch)+)?),
+        )
+    };
+}
+
+#[doc(hidden)]
+pub fn _long_version(testament: &GitTestament, rendered: String) -> ::clap::builder::Str {
+    let mut out = rendered;
+    if let Some(commit) = testament.commit_hash() {
+        let _ = write!(out, "\ncommit: {commit}");
+    }
+    if let Some(branch) = testament.branch_name {
+        let _ = write!(out, "\nbranch: {branch}");
+    }
+    match testament.compressed_modifications {
+        #[cfg(feature = "compression")]
+        Some(compressed) => {
+            if let Some(table) = compressed.decompress() {
+                table.iter().for_each(|m| list_modification(&mut out, &m));
+            }
+        }
+        #[cfg(not(feature = "compression"))]
+        Some(_) => {}
+        None => testament
+            .modifications
+            .iter()
+            .for_each(|m| list_modification(&mut out, m)),
+    }
+    out.into()
+}
+
+fn list_modification(out: &mut String, modification: &GitModification) {
+    let kind = match modification {
+        GitModification::Added(_) => "added",
+        GitModification::Removed(_) => "removed",
+        GitModification::Modified(_) => "modified",
+        GitModification::Renamed(..) => "renamed",
+        GitModification::Copied(..) => "copied",
+        GitModification::Untracked(_) => "untracked",
+    };
+    let _ = write!(out, "\n{kind}: ");
+    if let Some(original) = modification.original_path_bytes() {
+        let _ = write!(out, "{} -> ", String::from_utf8_lossy(original));
+    }
+    let _ = write!(out, "{}", modification.path_lossy());
+}
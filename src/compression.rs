@@ -0,0 +1,94 @@
+//! Decompression of modification tables
+//!
+//! The derive macro encodes each modification as a kind byte, followed by the
+//! length of the path as a little-endian `u32`, followed by the path bytes.
+//! A rename or copy is followed by a second length and path, the new path.
+//! The resulting table is then zlib compressed.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use miniz_oxide::inflate::decompress_to_vec_zlib;
+
+use crate::{CompressedModifications, GitModification};
+
+/// A decompressed table of modifications to a working tree.
+///
+/// This is produced by [`CompressedModifications::decompress`] and owns the
+/// decompressed path data.  Use [`ModificationTable::iter`] to retrieve the
+/// modifications themselves.
+#[derive(Debug)]
+pub struct ModificationTable {
+    data: Vec<u8>,
+    entries: Vec<(u8, Range<usize>, Option<Range<usize>>)>,
+}
+
+impl<'a> CompressedModifications<'a> {
+    /// Decompress the modification table.
+    ///
+    /// This returns `None` if the compressed data could not be decoded, which
+    /// should only happen if the testament was generated by an incompatible
+    /// version of the derive macro.
+    pub fn decompress(&self) -> Option<ModificationTable> {
+        let data = decompress_to_vec_zlib(self.data).ok()?;
+        let mut entries = Vec::with_capacity(self.count);
+        let mut pos = 0;
+        while pos < data.len() {
+            let kind = data[pos];
+            if kind > 5 {
+                return None;
+            }
+            let path = read_path(&data, pos + 1)?;
+            pos = path.end;
+            let new_path = if kind >= 4 {
+                let new_path = read_path(&data, pos)?;
+                pos = new_path.end;
+                Some(new_path)
+            } else {
+                None
+            };
+            entries.push((kind, path, new_path));
+        }
+        Some(ModificationTable { data, entries })
+    }
+}
+
+/// Read a length prefixed path starting at `pos`, returning its range
+fn read_path(data: &[u8], pos: usize) -> Option<Range<usize>> {
+    let len = data.get(pos..pos.checked_add(4)?)?;
+    let len = u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize;
+    let start = pos + 4;
+    let end = start.checked_add(len)?;
+    if end > data.len() {
+        return None;
+    }
+    Some(start..end)
+}
+
+impl ModificationTable {
+    /// The number of modifications in the table
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the table is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate the modifications in the table
+    pub fn iter(&self) -> impl Iterator<Item = GitModification<'_>> {
+        self.entries.iter().map(move |(kind, range, new_path)| {
+            let path = &self.data[range.clone()];
+            let new_path = || &self.data[new_path.clone().unwrap_or_default()];
+            match kind {
+                0 => GitModification::Added(path),
+                1 => GitModification::Removed(path),
+                2 => GitModification::Modified(path),
+                4 => GitModification::Renamed(path, new_path()),
+                5 => GitModification::Copied(path, new_path()),
+                _ => GitModification::Untracked(path),
+            }
+        })
+    }
+}
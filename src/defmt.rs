@@ -0,0 +1,138 @@
+//! Formatting testaments with [`defmt`](https://docs.rs/defmt) for logging
+//! from embedded targets
+//!
+//! The [`Format`] implementations produce the same text as the `Display`
+//! implementations, but leave the formatting to the host so that only the
+//! strings and numbers are sent over the wire.  A modification is formatted
+//! as its kind and path, such as `modified: src/main.rs`.
+
+use ::defmt::{write, Format, Formatter};
+
+use crate::{
+    short_hash, CommitKind, GitModification, GitTestament, RenderStrings, DEFAULT_SHORT_HASH_LENGTH,
+};
+
+impl Format for GitTestament<'_> {
+    fn format(&self, f: Formatter) {
+        let strings = &RenderStrings::ENGLISH;
+        format_commit(&self.commit, f, self.short_hash_length, strings);
+        let count = self.modification_count();
+        if !self.modifications_collected {
+            write!(f, " {=str}", strings.maybe_dirty);
+        } else if count > 0 {
+            write!(
+                f,
+                " {=str} {=usize} {=str}",
+                strings.dirty,
+                count,
+                (strings.modifications)(count)
+            );
+        }
+    }
+}
+
+impl Format for CommitKind<'_> {
+    fn format(&self, f: Formatter) {
+        format_commit(self, f, DEFAULT_SHORT_HASH_LENGTH, &RenderStrings::ENGLISH);
+    }
+}
+
+impl Format for GitModification<'_> {
+    fn format(&self, f: Formatter) {
+        let kind = match self {
+            GitModification::Added(_) => "added",
+            GitModification::Removed(_) => "removed",
+            GitModification::Modified(_) => "modified",
+            GitModification::Renamed(..) => "renamed",
+            GitModification::Copied(..) => "copied",
+            GitModification::Untracked(_) => "untracked",
+        };
+        write!(f, "{=str}: ", kind);
+        if let Some(original) = self.original_path_bytes() {
+            format_path(original, f);
+            write!(f, " -> ");
+        }
+        format_path(self.path_bytes(), f);
+    }
+}
+
+/// Format a path as a string if it is UTF-8, and as bytes otherwise
+fn format_path(path: &[u8], f: Formatter) {
+    match core::str::from_utf8(path) {
+        Ok(path) => write!(f, "{=str}", path),
+        Err(_) => write!(f, "{=[u8]:a}", path),
+    }
+}
+
+/// Format a commit just as its `Display` implementation would
+fn format_commit(commit: &CommitKind, f: Formatter, hash_len: usize, strings: &RenderStrings) {
+    match *commit {
+        CommitKind::NoRepository(crate_ver, build_date) => {
+            write!(f, "{=str} ({=str})", crate_ver, build_date)
+        }
+        CommitKind::NoCommit(crate_ver, build_date) => write!(
+            f,
+            "{=str} ({=str} {=str})",
+            crate_ver, strings.uncommitted, build_date
+        ),
+        CommitKind::Shallow(crate_ver, commit, when) => write!(
+            f,
+            "{=str} ({=str} {=str} {=str})",
+            crate_ver,
+            strings.shallow,
+            short_hash(commit, hash_len),
+            when
+        ),
+        CommitKind::NoTags(commit, when) => write!(
+            f,
+            "{=str} ({=str} {=str})",
+            strings.unknown,
+            short_hash(commit, hash_len),
+            when
+        ),
+        CommitKind::FromTag(tag, commit, when, depth) => {
+            let commit = short_hash(commit, hash_len);
+            if depth > 0 {
+                write!(
+                    f,
+                    "{=str}+{=usize} ({=str} {=str})",
+                    tag, depth, commit, when
+                )
+            } else {
+                write!(f, "{=str} ({=str} {=str})", tag, commit, when)
+            }
+        }
+        CommitKind::BeforeTag(tag, commit, when, depth) => write!(
+            f,
+            "{=str}~{=usize} ({=str} {=str})",
+            tag,
+            depth,
+            short_hash(commit, hash_len),
+            when
+        ),
+        CommitKind::FromTrustedBranch(version, _, commit, when) => write!(
+            f,
+            "{=str} ({=str} {=str})",
+            version,
+            short_hash(commit, hash_len),
+            when
+        ),
+        CommitKind::FromRemote(crate_ver, remote, commit, when) => write!(
+            f,
+            "{=str} ({=str}, {=str} {=str} {=str})",
+            crate_ver,
+            strings.uncommitted,
+            remote,
+            short_hash(commit, hash_len),
+            when
+        ),
+        CommitKind::FromTarball(commit, crate_ver, build_date) => write!(
+            f,
+            "{=str} ({=str} {=str} {=str})",
+            crate_ver,
+            strings.tarball,
+            short_hash(commit, hash_len),
+            build_date
+        ),
+    }
+}
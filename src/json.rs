@@ -0,0 +1,156 @@
+//! Rendering testaments as JSON
+//!
+//! This is deliberately hand written, rather than using `serde`, so that it
+//! is available to any crate with the `alloc` feature.  The schema is an
+//! object with the following keys, all of which are always present:
+//!
+//! * `version` -> The crate's version, or `null` from [`GitTestament::to_json`]
+//! * `commit` -> The full commit hash, or `null`
+//! * `tag` -> The tag, or `null`
+//! * `distance` -> The number of commits since the tag, or `null`
+//! * `date` -> The commit date, or the build date if there was no commit
+//! * `branch` -> The branch name, or `null`
+//! * `dirty` -> `true` if there were any modifications, or `null` if they
+//!   were not collected
+//! * `modifications` -> An array of `{"kind": ..., "path": ...}` objects,
+//!   where `kind` is one of `added`, `removed`, `modified`, `renamed`,
+//!   `copied`, or `untracked`.  A `renamed` or `copied` modification also has
+//!   a `from` key with the path the file was renamed or copied from.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::{GitModification, GitTestament};
+
+impl<'a> GitTestament<'a> {
+    /// Render this testament as a single line of JSON, for tooling which
+    /// would otherwise have to parse the human readable rendering.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitModification, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::FromTag("v1.2.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 3),
+    ///     modifications: &[GitModification::Modified(b"src/main.rs")],
+    ///     branch_name: Some("main"),
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// assert_eq!(
+    ///     testament.to_json(),
+    ///     concat!(
+    ///         r#"{"version":null,"commit":"763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3","#,
+    ///         r#""tag":"v1.2.0","distance":3,"date":"2019-04-02","branch":"main","dirty":true,"#,
+    ///         r#""modifications":[{"kind":"modified","path":"src/main.rs"}]}"#,
+    ///     )
+    /// );
+    /// ```
+    ///
+    /// The `version` is only known when rendering with
+    /// [`render_testament_json!`](crate::render_testament_json).  If the
+    /// modifications were compressed and the `compression` feature is not
+    /// enabled, then the list of modifications is empty even if `dirty` is
+    /// `true`.
+    pub fn to_json(&self) -> String {
+        self.write_json(None)
+    }
+
+    #[doc(hidden)]
+    pub fn _to_json_with_version(
+        &self,
+        pkg_version: &str,
+        trusted_branches: &[&'static str],
+    ) -> String {
+        match self.trusting_any_branch(pkg_version, trusted_branches) {
+            Some(trusted) => trusted.write_json(Some(pkg_version)),
+            None => self.write_json(Some(pkg_version)),
+        }
+    }
+
+    fn write_json(&self, pkg_version: Option<&str>) -> String {
+        let mut out = String::from("{\"version\":");
+        push_optional(&mut out, pkg_version);
+        out.push_str(",\"commit\":");
+        push_optional(&mut out, self.commit_hash());
+        out.push_str(",\"tag\":");
+        push_optional(&mut out, self.tag());
+        out.push_str(",\"distance\":");
+        match self.tag_distance() {
+            Some(distance) => {
+                let _ = write!(out, "{distance}");
+            }
+            None => out.push_str("null"),
+        }
+        out.push_str(",\"date\":");
+        push_optional(&mut out, self.commit_date().or_else(|| self.build_date()));
+        out.push_str(",\"branch\":");
+        push_optional(&mut out, self.branch_name);
+        // Whether uncollected modifications were dirty is not known
+        if self.modifications_collected {
+            let _ = write!(out, ",\"dirty\":{}", self.is_dirty());
+        } else {
+            out.push_str(",\"dirty\":null");
+        }
+        out.push_str(",\"modifications\":[");
+        let mut first = true;
+        let mut push_modification = |modification: &GitModification| {
+            if !first {
+                out.push(',');
+            }
+            first = false;
+            let kind = match modification {
+                GitModification::Added(_) => "added",
+                GitModification::Removed(_) => "removed",
+                GitModification::Modified(_) => "modified",
+                GitModification::Renamed(..) => "renamed",
+                GitModification::Copied(..) => "copied",
+                GitModification::Untracked(_) => "untracked",
+            };
+            let _ = write!(out, "{{\"kind\":\"{kind}\",\"path\":");
+            push_string(&mut out, &modification.path_lossy());
+            if let Some(original) = modification.original_path_bytes() {
+                out.push_str(",\"from\":");
+                push_string(&mut out, &String::from_utf8_lossy(original));
+            }
+            out.push('}');
+        };
+        match self.compressed_modifications {
+            #[cfg(feature = "compression")]
+            Some(compressed) => {
+                if let Some(table) = compressed.decompress() {
+                    table.iter().for_each(|m| push_modification(&m));
+                }
+            }
+            #[cfg(not(feature = "compression"))]
+            Some(_) => {}
+            None => self.modifications.iter().for_each(&mut push_modification),
+        }
+        out.push_str("]}");
+        out
+    }
+}
+
+pub(crate) fn push_optional(out: &mut String, value: Option<&str>) {
+    match value {
+        Some(value) => push_string(out, value),
+        None => out.push_str("null"),
+    }
+}
+
+/// Push `value` as a quoted JSON string, escaping as needed
+pub(crate) fn push_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
@@ -6,12 +6,65 @@
 //! [git_testament]: macro.git_testament.html
 //! [git_testament_macros]: macro.git_testament_macros.html
 //!
-//! If you build this library with the default `alloc` feature disabled then while
-//! the non-macro form of the testaments are offered, they cannot be rendered
+//! If you build this library with the default `alloc` feature disabled then
+//! the non-macro form of the testaments are still offered, but they can only
+//! be rendered into a buffer of your own with [GitTestament::render_into],
 //! and the [render_testament] macro will not be provided.
 //!
+//! [GitTestament::render_into]: struct.GitTestament.html#method.render_into
+//!
 //! [render_testament]: macro.render_testament.html
 //!
+//! If you build this library with the `compression` feature then testaments
+//! generated with the `compress` option can have their modification tables
+//! decompressed at runtime.  See [CompressedModifications] for details.
+//!
+//! [CompressedModifications]: struct.CompressedModifications.html
+//!
+//! If you build this library with the `runtime` feature then you can also
+//! capture testaments for arbitrary repositories when your program runs, via
+//! [GitTestament::capture].  This requires the standard library.  The
+//! `tokio` feature adds [GitTestament::capture_async], which captures without
+//! blocking a tokio runtime.
+//!
+//! [GitTestament::capture]: struct.GitTestament.html#method.capture
+//! [GitTestament::capture_async]: struct.GitTestament.html#method.capture_async
+//!
+//! If you build this library with the `testing` feature then the [testing]
+//! module provides helpers for checking, in integration tests, that your
+//! binaries report testaments which match the repository.
+//!
+//! [testing]: testing/index.html
+//!
+//! If you build this library with the `provenance` feature then each testament
+//! also records where each of its fields came from, see [Provenance].
+//!
+//! [Provenance]: struct.Provenance.html
+//!
+//! If you build this library with the `clap` feature then the [clap] module
+//! provides macros to use a testament as the version of a [clap] command.
+//!
+//! If you build this library with the `defmt` feature then testaments, their
+//! commits and their modifications implement [`defmt::Format`], so that they
+//! can be logged from embedded targets as they would be displayed.
+//!
+//! [`defmt::Format`]: https://docs.rs/defmt/latest/defmt/trait.Format.html
+//!
+//! If you build this library with the `proto` feature then the [proto] module
+//! provides protocol buffer messages for testaments, for services to return
+//! from their gRPC health or information endpoints.
+//!
+//! [proto]: proto/index.html
+//!
+//! If you build this library with the `semver` feature then
+//! [`GitTestament::version_semver`] gives the crate's version with the commit
+//! recorded in its build metadata, as a [`semver::Version`].
+//!
+//! [`GitTestament::version_semver`]: struct.GitTestament.html#method.version_semver
+//! [`semver::Version`]: https://docs.rs/semver/latest/semver/struct.Version.html
+//!
+//! [clap]: clap/index.html
+//!
 //! ## Trusted branches
 //!
 //! In both [render_testament] and [git_testament_macros] you will find mention
@@ -25,13 +78,61 @@
 #![no_std]
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub extern crate alloc as __alloc;
+#[cfg(feature = "clap")]
+#[doc(hidden)]
+pub extern crate clap as __clap;
 #[doc(hidden)]
 pub extern crate core as __core;
 #[doc(hidden)]
 pub extern crate git_testament_derive as __derive;
+#[cfg(feature = "runtime")]
+extern crate std;
 
 use core::fmt::{self, Display, Formatter};
 
+#[cfg(feature = "compression")]
+mod compression;
+#[cfg(feature = "compression")]
+pub use compression::ModificationTable;
+
+mod version;
+
+#[cfg(feature = "alloc")]
+mod json;
+
+#[cfg(feature = "alloc")]
+mod slsa;
+
+#[cfg(feature = "alloc")]
+mod parse;
+#[cfg(feature = "alloc")]
+pub use parse::{ParseError, ParsedKind, ParsedTestament};
+
+#[cfg(feature = "runtime")]
+mod runtime;
+#[cfg(feature = "runtime")]
+pub use runtime::{CaptureError, OwnedGitTestament};
+
+#[cfg(feature = "testing")]
+pub mod testing;
+
+#[cfg(feature = "provenance")]
+mod provenance;
+#[cfg(feature = "provenance")]
+pub use provenance::{FieldSource, Provenance};
+
+#[cfg(feature = "clap")]
+pub mod clap;
+
+#[cfg(feature = "defmt")]
+mod defmt;
+
+#[cfg(feature = "proto")]
+pub mod proto;
+
 // Clippy thinks our fn main() is needless, but it is needed because otherwise
 // we cannot have the invocation of the procedural macro (yet)
 #[allow(clippy::needless_doctest_main)]
@@ -62,16 +163,290 @@ use core::fmt::{self, Display, Formatter};
 /// ```
 ///
 /// See [`GitTestament`] for the type of the defined `TESTAMENT`.
+///
+/// After the name, a number of options may be given, separated by commas:
+///
+/// * `compress` -> Store the modifications to the working tree as a compressed
+///   table in the `compressed_modifications` member rather than in the
+///   `modifications` member.  See [`CompressedModifications`] for details.
+/// * `short_hash = N` -> Abbreviate the commit hash to `N` characters rather
+///   than the default of nine when rendering the testament.
+/// * `date_format = "..."` -> Format the commit date and the build date with
+///   the given [`time` format description] rather than as
+///   `[year]-[month]-[day]`, for example
+///   `date_format = "[year]-[month]-[day]T[hour]:[minute]:[second][offset_hour sign:mandatory]:[offset_minute]"`
+///   for a full ISO 8601 timestamp.  Commit dates are in the committer's
+///   timezone and build dates are in UTC.  The description is checked when
+///   the testament is generated.  A testament whose dates contain spaces
+///   cannot be parsed by [`GitTestament::parse_rendered`].
+/// * `respect_export_ignore` -> Do not consider paths which are marked
+///   `export-ignore` in the repository's `.gitattributes` as modifications,
+///   since the repository itself declares them irrelevant to released artifacts.
+/// * `release_tag = "pattern"` -> Additionally record the most recent tag which
+///   matches the given glob pattern, and the distance to it, in the `release`
+///   member.  See [`ReleaseTag`] for details.
+/// * `ignore = ["pattern", ...]` -> Do not consider paths which match any of
+///   the given glob patterns as modifications.  Patterns are matched against
+///   the path from the top of the repository, and `*` may match across
+///   directories, so for example `ignore = ["*.orig", "docs/coverage/*"]`.
+/// * `link_section` -> Additionally place the rendered testament, as a NUL
+///   terminated string, into a link section of the binary so that it can be
+///   read with tools such as `readelf -p .git_testament` or `strings` without
+///   running the program.  The section is `.git_testament` on ELF platforms,
+///   `__DATA,__git_testament` on Apple platforms and `.gittest` on Windows.
+///   Use `link_section = "name"` to choose the section yourself.
+/// * `export_c = "symbol"` -> Additionally export the rendered testament, as a
+///   NUL terminated string, as an unmangled `pub static` named `symbol`, so
+///   that C code in the same binary can read it after declaring it as
+///   `extern const char symbol[];`.  As with any unmangled symbol, the name
+///   must be unique within the binary.
+/// * `ffi` -> Additionally export `extern "C"` functions which return the
+///   parts of the testament, so that C code in the same binary can query them
+///   as Rust code would.  They are named with the prefix `git_testament`, or
+///   with `ffi = "prefix"` another prefix which must be unique within the
+///   binary, and may be declared in C as:
+///
+///   ```c
+///   const char *git_testament_rendered(void);     /* as render_testament!() */
+///   const char *git_testament_commit_hash(void);  /* NULL if no commit */
+///   const char *git_testament_commit_date(void);  /* NULL if no commit */
+///   const char *git_testament_tag(void);          /* NULL if no tag */
+///   int64_t git_testament_tag_distance(void);     /* -1 if no tag */
+///   const char *git_testament_branch(void);       /* NULL if not known */
+///   bool git_testament_is_dirty(void);
+///   size_t git_testament_modification_count(void);
+///   ```
+///
+///   The strings are static and NUL terminated, and must not be freed.  The
+///   rendered testament trusts only the branches given with `trusted`.
+/// * `artifact` -> Additionally write the testament to `NAME.json` in
+///   `OUT_DIR` when the crate is built, so that packaging steps can pick up
+///   the same version string as the binary reports without running it.  The
+///   file has the same keys as [`render_testament_json!`] produces, along
+///   with `rendered` holding the testament as [`render_testament!`] renders
+///   it, trusting only the branches given with `trusted`.  `OUT_DIR` is only
+///   set for crates with a build script, so use `artifact = "path"` to write
+///   to a path relative to the crate's directory instead.
+/// * `remote_fallback` -> If the repository has no commits yet, but does have
+///   remote-tracking branches (for example it was initialised and fetched but
+///   nothing has been checked out yet), record the remote's default branch as
+///   [`CommitKind::FromRemote`] rather than [`CommitKind::NoCommit`].
+/// * `enforce_semver` -> If the nearest tag is a semantic version (optionally
+///   with a leading `v`), fail the build if the crate's version is not
+///   consistent with it.  At the tag the versions must be equal, and after the
+///   tag the crate's version must be at least that of the tag, so forgotten
+///   version bumps are caught.  Use `enforce_semver = "warn"` to emit a
+///   warning instead of failing the build.
+/// * `extra = [("key", "value"), ("key", env("VARIABLE")), ...]` -> Record
+///   extra key/value pairs in the `extras` member, see [`GitTestament::extra`].
+///   Values may be given literally or taken from environment variables at
+///   build time.  Pairs whose environment variable is not set are omitted.
+///   The pairs may also be written as a map, as in
+///   `extra = { "ci_job" => env "CI_JOB_ID", "flavor" => "qa" }`.  See
+///   [`GitTestament::render_extended`] to render them with the testament.
+/// * `parents` -> Record the full hashes of the parents of the commit in the
+///   `parents` member, with the first parent first.  For a merge commit this
+///   allows the build to be traced back to each of the merged branches.
+/// * `tags_at_head` -> Record every tag which points directly at the commit
+///   in the `tags_at_head` member, for a commit with several tags such as
+///   `v1.2.3` and `latest`, rather than only the one `git describe` chose.
+///   This costs another run of git, so is not done by default.
+/// * `remote` -> Record the URL of the `origin` remote in the `remote_url`
+///   member, so that it is possible to tell which fork or mirror the crate was
+///   built from.  Use `remote = "name"` to record a different remote.  Any
+///   credentials in the URL are removed, but consider whether the URL itself
+///   is something you wish to publish before enabling this.
+/// * `verify_signature` -> Verify the GPG or SSH signature of the commit, as
+///   `git verify-commit` would, and record whether it is good in the `signed`
+///   member and who it was signed by in the `signer` member.  This relies on
+///   the keys being known to git on the machine building the crate.
+/// * `trust_signed_tags` -> Trust the build when the commit has an annotated
+///   tag with a good signature, as `git verify-tag` would check it, and the
+///   working tree is clean.  The tag is recorded in the `signed_tag` member
+///   and who it was signed by in the `tag_signer` member, and the testament is
+///   rendered just as for a trusted branch, so no branch need be trusted at
+///   all.  Use `allowed_signers = "path"` to verify SSH signatures against the
+///   given allowed signers file, and `gpg_home = "path"` to verify GPG
+///   signatures against the keyring in the given GnuPG home directory, rather
+///   than whatever git is configured with.  Both paths are relative to the
+///   crate's directory, and also apply to `verify_signature`.
+/// * `submodules` -> Record the commit checked out in each initialised
+///   submodule, and whether it has modifications, in the `submodules` member.
+///   Submodules are otherwise ignored, and even with this option a dirty
+///   submodule does not make the testament itself dirty.
+/// * `reproducible` -> Make the testament depend only on the source code, so
+///   that it is identical however many times the crate is built.  If the
+///   [`SOURCE_DATE_EPOCH`] environment variable is set then every date in the
+///   testament, including that of the commit, is taken from it, and a value
+///   which cannot be understood fails the build rather than falling back to
+///   today.  Without it the build date is recorded as `unknown`.
+/// * `sources = ["git", ...]` -> The sources of information to try, in order,
+///   with the first to succeed being recorded in the `source` member.  If none
+///   succeed then the testament falls back to [`CommitKind::NoRepository`].
+///   The sources are `"git"`, `"jj"` and `"hg"`, which run the `git`,
+///   Jujutsu and Mercurial command line tools respectively, and the default
+///   is to try each of them in that order.  Options which only make sense
+///   for git, such as `verify_signature` or `submodules`, are ignored by the
+///   other sources.  An empty list (`sources = []`) never consults the
+///   repository at all.
+/// * `untracked = "all"` -> Count every untracked file as a modification,
+///   including each file within an untracked directory, as
+///   `git status --untracked-files=all` would.  The default is
+///   `untracked = "normal"`, where an untracked directory counts once, and
+///   `untracked = "no"` does not count untracked files at all, so that files
+///   which other tools generate in the source tree do not make it dirty.
+/// * `status = false` -> Do not run `git status` at all, since in a very
+///   large repository it can take far longer than everything else put
+///   together.  The modifications are then unknown rather than none, so the
+///   `modifications_collected` member is false and the testament renders as
+///   `1.0.0 (763aa159d 2019-04-02) maybe dirty`.  The `worktree_digest` and
+///   `embed_diff` options are unaffected.
+/// * `scope = "crate"` -> Only consider modifications within the crate's own
+///   directory (`CARGO_MANIFEST_DIR`), so that changes elsewhere in a large
+///   workspace do not mark the testament as dirty.  The commit and tag are
+///   still those of the whole repository.  The default is `scope = "repository"`.
+/// * `strict` -> Fail the build if the repository was found but could not
+///   be read, or if some of the information asked for could not be
+///   determined, for example the URL of a `remote` which does not exist.
+///   Without it these problems are reported as warnings (of the use of a
+///   deprecated `GitTestamentIncomplete`) and the testament records what it
+///   can.  The absence of a repository is never reported, see `require_repo`.
+/// * `verbose` -> Print each command run while generating the testament, and
+///   everything else logged, to stderr, which cargo shows when it builds the
+///   crate.  This is for debugging a testament which is not what you expect.
+///   Setting `GIT_TESTAMENT_DEBUG=1` when building instead prints any
+///   warnings from every testament, or `GIT_TESTAMENT_DEBUG=debug` (or any
+///   other level) prints more, and rebuilds the crate to do so.
+/// * `channels = { "stable" => "v*", "nightly" => "main", ... }` -> Record
+///   the release channel in the `channel` member, so that an application can
+///   decide which channel to update from.  The first channel with a glob
+///   pattern matching either the tag at the commit itself, or the branch, is
+///   chosen.  If none match then there is no channel.
+/// * `repo_path = "../.."` -> Search for the repository starting from the
+///   given directory, relative to the crate's directory, rather than from the
+///   crate's directory itself.  This is useful when the crate is vendored into
+///   a larger project whose history is the one of interest.
+/// * `repo_env = "VARIABLE"` -> Search for the repository starting from the
+///   directory named by the given environment variable at build time, which
+///   takes precedence over `repo_path` when it is set and not empty.
+/// * `revision_file = ".git-revision"` -> If no repository is found, read the
+///   hash of the commit from the given file, relative to the directory the
+///   repository is searched for from, as for a Nix build whose sources have
+///   no `.git`.  The testament is then [`CommitKind::FromTarball`], with the
+///   source [`TestamentSource::RevisionFile`].  A hash ending in `-dirty`, as
+///   Nix's `dirtyRev` gives, means the testament is not claimed to be clean,
+///   so `modifications_collected` is false.  When building under Nix, which
+///   sets `NIX_BUILD_TOP`, `.git-revision` is read without this option.
+/// * `trusted = "branch"` -> Trust the given branch when rendering the
+///   testament, as though it had been passed to [`render_testament!`], so that
+///   each testament in a crate may trust a different branch.  Any branch
+///   passed to [`render_testament!`] itself takes precedence.  This may also
+///   be a glob pattern, or a list of them, as in
+///   `trusted = ["main", "release/*"]`.
+/// * `require_repo`, `require_commit`, `require_tag` -> Fail the build, rather
+///   than falling back to recording the crate's version, if the crate is not
+///   in a git repository, the repository has no commits, or there is no tag in
+///   the history of the commit, respectively.  Each implies the ones before
+///   it.  A crate built from a package made by `cargo package`, such as one
+///   from crates.io, has a commit but no tag.
+/// * `match = "pattern"` -> Only consider tags matching the given glob pattern
+///   when describing the commit, as `git describe --match` would, for example
+///   `match = "v*"` to skip tags which are not versions.
+/// * `exclude = "pattern"` -> Never use tags matching the given glob pattern
+///   when describing the commit, as `git describe --exclude` would.
+/// * `first_parent` -> Only follow the first parent of merge commits when
+///   describing the commit, as `git describe --first-parent` would, so that
+///   tags on branches which were merged in are not used.
+/// * `candidates = N` -> Consider up to `N` candidate tags when describing
+///   the commit, as `git describe --candidates=N` would.  `candidates = 0`
+///   only uses a tag which points at the commit itself.
+/// * `describe_mode = "contains"` -> Describe the commit by the earliest tag
+///   which contains it, as `git describe --contains` would, rather than by the
+///   most recent tag before it, for versioning with the upcoming release on
+///   a maintenance branch.  Such a commit is recorded as
+///   [`CommitKind::BeforeTag`] and renders as `v1.2.0~3 (...)`.  If no tag
+///   has been made since the commit, the tag before it is used as usual.
+///   `match` and `exclude` apply to these tags too.  The default is
+///   `describe_mode = "since"`.
+/// * `tag_message` -> If the tag describing the commit is an annotated tag,
+///   record the first line of its message in the `tag_message` member and the
+///   date it was tagged in the `tag_date` member.  This costs another run of
+///   git, so is not done by default.
+/// * `worktree_digest` -> If tracked files have been changed, record a digest
+///   of the changes in the `worktree_digest` member, so that two dirty builds
+///   with the same number of modifications can be told apart.  The digest is
+///   the object id git would give the output of `git diff HEAD` as a blob,
+///   with any `ignore` patterns excluded.  Untracked files are not included.
+/// * `embed_diff = 64_000` -> If tracked files have been changed, record up to
+///   that many bytes of `git diff HEAD` in the `diff` member, so that the
+///   exact changes behind a dirty build can be recovered from the binary
+///   itself.  The diff is taken as for `worktree_digest`, and is cut off,
+///   possibly part way through a line, once it reaches the limit.  It is
+///   never recorded with `privacy = "no_paths"` or stricter.
+/// * `lfs` -> Check whether any files stored with Git LFS are only pointers,
+///   because their objects were never fetched, and record this in the
+///   `lfs_incomplete` member, since the binary may then contain placeholders
+///   in place of assets while the working tree looks clean.  This needs
+///   git-lfs to be installed if the repository has any such files, and
+///   otherwise assumes that they were not fetched.
+/// * `git_version` -> Record the version of git which was run, as `git
+///   --version` describes it, in the `git_version` member, to help explain
+///   testaments built on unusual machines.  Releases of git older than 2.11
+///   report the modifications in an older format, which is understood, but
+///   the version is not recorded by default since it would make otherwise
+///   identical builds on different machines differ.
+/// * `privacy = "no_paths"` -> Limit what is embedded in the binary.  With
+///   `"no_paths"` the modifications are still counted, but the paths which
+///   were modified are recorded as empty.  With `"hash_only"` the branch, the
+///   tags, and any remote are also withheld, so the testament records little
+///   more than the commit hash, its date, and whether the working tree was
+///   dirty.  Checks such as `require_tag` and `enforce_semver` still see
+///   everything.  The default is `privacy = "full"`.
+/// * `build_info` -> Record the target triple, the cargo profile, the
+///   optimisation level and the output of `rustc --version` in the
+///   `build_info` member, see [`BuildInfo`], so that crash reports can say
+///   how a binary was built as well as what it was built from.  Cargo only
+///   tells build scripts the target, profile and optimisation level, so the
+///   crate needs a build script which calls
+///   `git_testament_build::forward_build_info()` to record them.
+/// * `ci` -> Record the CI system, run id and job link in the `ci` member,
+///   see [`CiInfo`], if the crate is built by GitHub Actions, GitLab CI,
+///   Buildkite or Jenkins, so that a binary can be traced back to the run
+///   which built it.
+/// * `ci_branch` -> If `HEAD` is detached, as it is when CI systems check out
+///   a commit rather than a branch, record the branch which the CI system
+///   says it is building, or for a pull request the branch being merged.
+///   This recognises the same CI systems as `ci`.  Otherwise the branch of a
+///   detached `HEAD` is the nearest branch or tag, if any.
+///
+/// The repository is found just as git would find it, so the `GIT_DIR`,
+/// `GIT_WORK_TREE` and `GIT_CEILING_DIRECTORIES` environment variables are
+/// honoured, and the crate is rebuilt if they change.  Relative paths in
+/// `GIT_DIR` and `GIT_WORK_TREE` are relative to the directory cargo runs the
+/// compiler in, which is the top of the workspace.  If `GIT_DIR` names a
+/// bare repository, as when building from a mirror, the commit is recorded
+/// but the sources are never considered modified, since there is no working
+/// tree to compare them with.
+///
+/// Where the repository is not available at all, as in a Nix or Bazel
+/// sandbox, whatever orchestrates the build can provide the information
+/// itself through environment variables, which take precedence over anything
+/// read from a repository.  `GIT_TESTAMENT_COMMIT` gives the full commit hash,
+/// which requires `GIT_TESTAMENT_DATE` (as `YYYY-MM-DD` or RFC 3339) unless it
+/// is the commit the repository is at anyway.  `GIT_TESTAMENT_TAG` and
+/// `GIT_TESTAMENT_DISTANCE` give the nearest tag and the number of commits
+/// since it, `GIT_TESTAMENT_BRANCH` the branch, and `GIT_TESTAMENT_DIRTY`
+/// (`true` or `false`) whether the sources were modified.  When any of these
+/// are set the `overridden` member is true and the `source` member is
+/// [`TestamentSource::Environment`] if there was no repository at all.
+///
+/// [`SOURCE_DATE_EPOCH`]: https://reproducible-builds.org/docs/source-date-epoch/
+/// [`time` format description]: https://time-rs.github.io/book/api/format-description.html
 #[macro_export]
 macro_rules! git_testament {
-    ($vis:vis $name:ident) => {
-        $crate::__derive::git_testament! {
-            $crate $name $vis
-        }
-    };
-    ($name:ident) => {
+    ($vis:vis $name:ident $(, $($option:tt)*)?) => {
         $crate::__derive::git_testament! {
-            $crate $name
+            $crate $name $vis $(, $($option)*)?
         }
     };
 }
@@ -131,6 +506,11 @@ macro_rules! git_testament {
 /// # }
 /// ```
 ///
+/// The trusted branch may instead be given as `trusted = "stable"`, alongside
+/// any of the options accepted by [`git_testament!`], but not both ways at once.
+/// Either way it may be a glob pattern, or a list of them such as
+/// `["main", "release/*"]`, as with [`render_testament!`].
+///
 /// The set of macros defined is:
 ///
 /// * `NAME_testament!()` -> produces a string similar but not guaranteed to be
@@ -140,20 +520,223 @@ macro_rules! git_testament {
 /// * `NAME_commit_present!()` -> A boolean indicating if there is a commit present at all
 /// * `NAME_tag_present!()` -> A boolean indicating if there is a tag present
 /// * `NAME_commit_hash!()` -> A string of the commit hash (or crate version if commit not present)
+/// * `NAME_commit_hash_short!()` -> The commit hash abbreviated as it is in the testament string
+///   (or crate version if commit not present)
 /// * `NAME_commit_date!()` -> A string of the commit date (or build date if no commit present)
+/// * `NAME_worktree!()` -> A boolean, true if built from a linked worktree
+///   created by `git worktree add`, rather than the main working tree
+/// * `NAME_bare!()` -> A boolean, true if built with `GIT_DIR` naming a bare repository
+/// * `NAME_detached!()` -> A boolean, true if `HEAD` was detached rather than on a branch
+/// * `NAME_commit_timestamp!()` -> A string of the commit time as an RFC 3339
+///   timestamp in the committer's timezone (or empty if no commit present)
 /// * `NAME_tag_name!()` -> The tag name if present (or crate version if commit not present)
 /// * `NAME_tag_distance!()` -> The number of commits since the tag if present (zero otherwise)
+/// * `NAME_describe!()` -> An Option<&str> of the output of `git describe --tags --long --dirty`
+///   if a tag is present
+/// * `NAME_commit_count!()` -> The number of commits reachable from the commit, including
+///   itself, as a `usize` (zero if no commit present)
+/// * `NAME_tag_message!()` -> An Option<&str> of the first line of the message of the
+///   annotated tag, if the `tag_message` option was given
+/// * `NAME_tag_date!()` -> An Option<&str> of the date of the annotated tag, if the
+///   `tag_message` option was given
+/// * `NAME_release_tag!()` -> An Option<&str> of the release tag if the `release_tag` option
+///   was given and a matching tag was found
+/// * `NAME_release_distance!()` -> The number of commits since the release tag if present
+///   (zero otherwise)
+/// * `NAME_shallow!()` -> A boolean indicating if the repository was a shallow clone
+/// * `NAME_remote_url!()` -> An Option<&str> of the remote URL if the `remote` option was given
+/// * `NAME_git_version!()` -> An Option<&str> of the version of git which was run, if the
+///   `git_version` option was given
+/// * `NAME_signed!()` -> A boolean indicating if the commit had a good signature, if the
+///   `verify_signature` option was given
+/// * `NAME_signer!()` -> An Option<&str> of the signer of the commit if it had a good signature
+/// * `NAME_signed_tag!()` -> An Option<&str> of the annotated tag at the commit with a good
+///   signature, if the `trust_signed_tags` option was given
+/// * `NAME_tag_signer!()` -> An Option<&str> of the signer of that tag, if it is known
+/// * `NAME_channel!()` -> An Option<&str> of the release channel chosen by the `channels`
+///   option
+/// * `NAME_dirty!()` -> A boolean indicating if the working tree had any modifications
+/// * `NAME_dirty_count!()` -> The number of modifications to the working tree, as a `usize`
+/// * `NAME_overridden!()` -> A boolean, true if any `GIT_TESTAMENT_*` environment variables
+///   overrode what was read from the repository
+/// * `NAME_modifications_collected!()` -> A boolean, false if the `status = false` option
+///   was given, in which case the working tree may have had modifications after all
+/// * `NAME_worktree_digest!()` -> An Option<&str> of the digest of the changes to tracked
+///   files, if the `worktree_digest` option was given
+/// * `NAME_diff!()` -> An Option<&[u8]> of the start of the changes to tracked files, if
+///   the `embed_diff` option was given
+/// * `NAME_lfs_incomplete!()` -> A boolean, true if the `lfs` option was given and any
+///   files stored with Git LFS had not been fetched
+/// * `NAME_parents!()` -> A `&[&str]` of the parent commit hashes if the `parents` option
+///   was given (empty otherwise)
+/// * `NAME_is_merge!()` -> A boolean indicating if the commit is a merge, with more than one
+///   parent
+/// * `NAME_tags_at_head!()` -> A `&[&str]` of every tag at the commit if the `tags_at_head`
+///   option was given (empty otherwise)
+/// * `NAME_target!()`, `NAME_profile!()`, `NAME_opt_level!()` and `NAME_rustc!()` -> An
+///   Option<&str> of each part of the [`BuildInfo`] if the `build_info` option was given
+/// * `NAME_ci_provider!()`, `NAME_ci_run_id!()` and `NAME_ci_job_url!()` -> An Option<&str>
+///   of each part of the [`CiInfo`] if the `ci` option was given and a CI run was detected
+/// * `NAME_formatted!()` -> The testament rendered according to the `format = "..."` option,
+///   which is only available to this macro.  The placeholders are the same as for
+///   [`GitTestament::render_format`]
+/// * `NAME_extra!("key")` -> The value of the given `extra` option pair as a string
+///
+/// After the name, and the trusted branch if given, the same options as
+/// [`git_testament!`] may be given where they are relevant.  For example
+/// `git_testament_macros!(version, "stable", short_hash = 12)`.
 #[macro_export]
 macro_rules! git_testament_macros {
-    ($name:ident $(, $trusted:literal)?) => {
+    ($name:ident $(, $($option:tt)*)?) => {
         $crate::__derive::git_testament_macros! {
-            $crate $name $($trusted)?
+            $crate $name $(, $($option)*)?
+        }
+    };
+}
+
+// Clippy thinks our fn main() is needless, but it is needed because otherwise
+// we cannot have the invocation of the procedural macro (yet)
+#[allow(clippy::needless_doctest_main)]
+/// Generate a testament for the working tree both as a constant and as a set
+/// of static string macros.
+///
+/// This is the same as using [`git_testament!`] and [`git_testament_macros!`]
+/// with the same name, except that the repository is only examined once, so
+/// the two cannot disagree and git is not run twice.
+///
+/// ```
+/// use git_testament::git_testament_full;
+///
+/// git_testament_full!(TESTAMENT);
+///
+/// const APP_VERSION: &str = concat!("app version ", TESTAMENT_testament!());
+/// # fn main() {
+///
+/// assert_eq!(TESTAMENT.commit_hash().is_some(), TESTAMENT_commit_present!());
+/// println!("{APP_VERSION} ({TESTAMENT})");
+/// # }
+/// ```
+///
+/// The constant may be given a visibility, which the macros do not have, and
+/// the options of both [`git_testament!`] and [`git_testament_macros!`] may be
+/// given, such as `format = "..."`.  The trusted branch of the macros may only
+/// be given as `trusted = "..."`.  Checks such as `enforce_semver` are only
+/// made once.
+#[macro_export]
+macro_rules! git_testament_full {
+    ($vis:vis $name:ident $(, $($option:tt)*)?) => {
+        $crate::__derive::git_testament_full! {
+            $crate $name $vis $(, $($option)*)?
+        }
+    };
+}
+
+/// Generate a module containing a testament and constants derived from it
+///
+/// This is an alternative to [`git_testament!`] and [`git_testament_macros!`]
+/// which gives a tidy namespaced API.  The module contains:
+///
+/// * `TESTAMENT` -> The [`GitTestament`] itself
+/// * `COMMIT` -> An `Option<&str>` of the full commit hash
+/// * `COMMIT_DATE` -> An `Option<&str>` of the commit date
+/// * `TAG` -> An `Option<&str>` of the tag name
+/// * `TAG_DISTANCE` -> An `Option<usize>` of the number of commits since the tag
+/// * `BRANCH` -> An `Option<&str>` of the branch name
+/// * `DIRTY` -> A boolean indicating if the working tree had modifications
+/// * `render()` -> The testament rendered as by [`render_testament!`], if the
+///   `alloc` feature is enabled
+///
+/// ```
+/// use git_testament::git_testament_mod;
+///
+/// git_testament_mod!(build_info);
+///
+/// # fn main() {
+/// if let Some(commit) = build_info::COMMIT {
+///     println!("Built from commit {commit}");
+/// }
+/// println!("app version {}", build_info::render());
+/// # }
+/// ```
+///
+/// The module may be given a visibility, and the same options as
+/// [`git_testament!`] may follow the name, for example
+/// `git_testament_mod!(pub build_info, short_hash = 12)`.
+#[macro_export]
+macro_rules! git_testament_mod {
+    ($vis:vis $name:ident $(, $($option:tt)*)?) => {
+        $vis mod $name {
+            $crate::git_testament!(pub TESTAMENT $(, $($option)*)?);
+
+            /// The full commit hash, if there was a commit
+            pub const COMMIT: $crate::__core::option::Option<&str> = TESTAMENT.commit_hash();
+            /// The commit date, if there was a commit
+            pub const COMMIT_DATE: $crate::__core::option::Option<&str> = TESTAMENT.commit_date();
+            /// The tag name, if there was a tag
+            pub const TAG: $crate::__core::option::Option<&str> = TESTAMENT.tag();
+            /// The number of commits since the tag, if there was a tag
+            pub const TAG_DISTANCE: $crate::__core::option::Option<usize> = TESTAMENT.tag_distance();
+            /// The branch name, if known
+            pub const BRANCH: $crate::__core::option::Option<&str> = TESTAMENT.branch_name;
+            /// Whether the working tree had modifications
+            pub const DIRTY: bool = TESTAMENT.is_dirty();
+
+            $crate::__git_testament_mod_render!();
+        }
+    };
+}
+
+/// The base of the testaments generated by the derive macro, which records
+/// the provenance of each field when the `provenance` feature is enabled.
+#[cfg(feature = "provenance")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __testament_base {
+    ($($field:ident = $source:ident),* $(,)?) => {
+        $crate::GitTestament {
+            provenance: $crate::Provenance {
+                $($field: $crate::FieldSource::$source,)*
+                ..$crate::Provenance::DEFAULT
+            },
+            ..$crate::EMPTY_TESTAMENT
+        }
+    };
+}
+
+#[cfg(not(feature = "provenance"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __testament_base {
+    ($($field:ident = $source:ident),* $(,)?) => {
+        $crate::EMPTY_TESTAMENT
+    };
+}
+
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __git_testament_mod_render {
+    () => {
+        /// Render the testament, as by `git_testament::render_testament!()`
+        pub fn render() -> $crate::__alloc::string::String {
+            $crate::render_testament!(TESTAMENT)
         }
     };
 }
 
+#[cfg(not(feature = "alloc"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __git_testament_mod_render {
+    () => {};
+}
+
 /// A modification to a working tree, recorded when the testament was created.
-#[derive(Debug)]
+///
+/// More kinds of modification may be recorded in future, so matches on this
+/// should include a wildcard arm.
+#[derive(Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub enum GitModification<'a> {
     /// A file or directory was added but not committed
     Added(&'a [u8]),
@@ -161,12 +744,223 @@ pub enum GitModification<'a> {
     Removed(&'a [u8]),
     /// A file was modified in some way, either content or permissions
     Modified(&'a [u8]),
+    /// A file was renamed, from the first path to the second, but not
+    /// committed
+    Renamed(&'a [u8], &'a [u8]),
+    /// A file was copied, from the first path to the second, but not
+    /// committed
+    Copied(&'a [u8], &'a [u8]),
     /// A file or directory was present but untracked
     Untracked(&'a [u8]),
 }
 
+/// The kind of a [`GitModification`], without the path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ModificationKind {
+    /// A file or directory was added but not committed
+    Added,
+    /// A file or directory was removed but not committed
+    Removed,
+    /// A file was modified in some way, either content or permissions
+    Modified,
+    /// A file was renamed but not committed
+    Renamed,
+    /// A file was copied but not committed
+    Copied,
+    /// A file or directory was present but untracked
+    Untracked,
+}
+
+/// The number of modifications of each kind, see
+/// [`GitTestament::modification_summary`].
+///
+/// This displays as the counts which are not zero, most significant first, as
+/// in `2 modified, 1 untracked`, or as nothing if there were no
+/// modifications.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ModificationSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub modified: usize,
+    pub renamed: usize,
+    pub copied: usize,
+    pub untracked: usize,
+    /// Modifications whose kind is not known, because they were compressed
+    /// and the `compression` feature is not enabled
+    pub unknown: usize,
+}
+
+impl ModificationSummary {
+    /// The total number of modifications, of whatever kind
+    pub const fn total(&self) -> usize {
+        self.added
+            + self.removed
+            + self.modified
+            + self.renamed
+            + self.copied
+            + self.untracked
+            + self.unknown
+    }
+
+    fn add(&mut self, kind: ModificationKind) {
+        match kind {
+            ModificationKind::Added => self.added += 1,
+            ModificationKind::Removed => self.removed += 1,
+            ModificationKind::Modified => self.modified += 1,
+            ModificationKind::Renamed => self.renamed += 1,
+            ModificationKind::Copied => self.copied += 1,
+            ModificationKind::Untracked => self.untracked += 1,
+        }
+    }
+}
+
+impl Display for ModificationSummary {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let counts = [
+            (self.modified, "modified"),
+            (self.added, "added"),
+            (self.removed, "removed"),
+            (self.renamed, "renamed"),
+            (self.copied, "copied"),
+            (self.untracked, "untracked"),
+            (self.unknown, "unknown"),
+        ];
+        let mut separator = "";
+        for (count, kind) in counts.iter().filter(|(count, _)| *count > 0) {
+            write!(fmt, "{separator}{count} {kind}")?;
+            separator = ", ";
+        }
+        Ok(())
+    }
+}
+
+impl<'a> GitModification<'a> {
+    /// The path which was modified, as bytes.
+    ///
+    /// Paths are recorded as git reports them, relative to the top of the
+    /// repository.  They are usually, but not necessarily, UTF-8.  For a
+    /// rename or copy, this is the new path.
+    pub fn path_bytes(&self) -> &'a [u8] {
+        match *self {
+            GitModification::Added(path)
+            | GitModification::Removed(path)
+            | GitModification::Modified(path)
+            | GitModification::Renamed(_, path)
+            | GitModification::Copied(_, path)
+            | GitModification::Untracked(path) => path,
+        }
+    }
+
+    /// For a rename or copy, the path the file was renamed or copied from, as
+    /// bytes.
+    pub fn original_path_bytes(&self) -> Option<&'a [u8]> {
+        match *self {
+            GitModification::Renamed(original, _) | GitModification::Copied(original, _) => {
+                Some(original)
+            }
+            _ => None,
+        }
+    }
+
+    /// The path which was modified, with any invalid UTF-8 replaced.
+    #[cfg(feature = "alloc")]
+    pub fn path_lossy(&self) -> alloc::borrow::Cow<'a, str> {
+        alloc::string::String::from_utf8_lossy(self.path_bytes())
+    }
+
+    /// The kind of modification which was made.
+    pub fn kind(&self) -> ModificationKind {
+        match self {
+            GitModification::Added(_) => ModificationKind::Added,
+            GitModification::Removed(_) => ModificationKind::Removed,
+            GitModification::Modified(_) => ModificationKind::Modified,
+            GitModification::Renamed(..) => ModificationKind::Renamed,
+            GitModification::Copied(..) => ModificationKind::Copied,
+            GitModification::Untracked(_) => ModificationKind::Untracked,
+        }
+    }
+}
+
+/// A compressed table of modifications to a working tree.
+///
+/// When a testament is generated with the `compress` option, and the working
+/// tree is dirty, the modifications are stored here rather than in the
+/// `modifications` member of the testament.  This can significantly reduce
+/// the size of binaries built from very dirty working trees.
+///
+/// The table can be decompressed at runtime if the `compression` feature
+/// is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompressedModifications<'a> {
+    /// The number of modifications recorded in the table
+    pub count: usize,
+    /// The zlib compressed modification table
+    pub data: &'a [u8],
+}
+
+/// The most recent release tag reachable from the commit.
+///
+/// This is only recorded if the testament was generated with the `release_tag`
+/// option, and it may differ from the tag chosen by `git describe` if there are
+/// non-release tags between the release and the commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ReleaseTag<'a> {
+    /// The name of the release tag
+    pub tag: &'a str,
+    /// The number of commits since the release tag
+    pub distance: usize,
+}
+
+/// The state of a submodule when the testament was created.
+///
+/// This is only recorded if the testament was generated with the `submodules`
+/// option.  Submodules which were not initialised are not recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubmoduleInfo<'a> {
+    /// The path of the submodule relative to the top of the repository
+    pub path: &'a str,
+    /// The full hash of the commit checked out in the submodule
+    pub commit: &'a str,
+    /// Whether the submodule had modifications, or had a different commit
+    /// checked out to the one recorded in the repository
+    pub dirty: bool,
+}
+
+/// The target and toolchain the crate was built with.
+///
+/// This is only recorded if the testament was generated with the `build_info`
+/// option.  Each part is `None` if it was not known when the crate was built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BuildInfo<'a> {
+    /// The target triple, e.g. `x86_64-unknown-linux-gnu`
+    pub target: Option<&'a str>,
+    /// The cargo profile, which is either `debug` or `release`
+    pub profile: Option<&'a str>,
+    /// The optimisation level, e.g. `0` or `3`
+    pub opt_level: Option<&'a str>,
+    /// The output of `rustc --version`
+    pub rustc: Option<&'a str>,
+}
+
+/// The continuous integration run which built the crate.
+///
+/// This is only recorded if the testament was generated with the `ci` option
+/// and the crate was built by GitHub Actions, GitLab CI, Buildkite or Jenkins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CiInfo<'a> {
+    /// The CI system, one of `github-actions`, `gitlab`, `buildkite` or
+    /// `jenkins`
+    pub provider: &'a str,
+    /// The identifier of the workflow run, pipeline or build
+    pub run_id: Option<&'a str>,
+    /// A link to the job, or to the run if the job cannot be linked to
+    pub job_url: Option<&'a str>,
+}
+
 /// The kind of commit available at the point that the testament was created.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CommitKind<'a> {
     /// No repository was present.  Instead the crate's version and the
     /// build date are recorded.
@@ -177,10 +971,107 @@ pub enum CommitKind<'a> {
     /// There are no tags in the repository in the history of the commit.
     /// The commit hash and commit date are recorded.
     NoTags(&'a str, &'a str),
+    /// There were no tags found in the history of the commit, but the
+    /// repository is a shallow clone so they may simply not have been fetched.
+    /// The crate's version, the commit hash, and the commit date are recorded.
+    Shallow(&'a str, &'a str, &'a str),
     /// There were tags in the history of the commit.
     /// The tag name, commit hash, commit date, and distance from the tag to
     /// the commit are recorded.
     FromTag(&'a str, &'a str, &'a str, usize),
+    /// The commit was tagged, but the tag was overridden by the crate's version
+    /// because the build was made from a clean tree on a trusted branch, or at
+    /// a tag with a trusted signature.  The crate version, the name of the
+    /// trusted branch (rather than the pattern it was trusted by) or of the
+    /// signed tag, the commit hash, and the commit date are recorded.
+    ///
+    /// This is never produced by the testament macros directly, only by
+    /// [`GitTestament::trusting_branch`] and
+    /// [`GitTestament::trusting_signed_tag`] (and so by [`render_testament!`]).
+    FromTrustedBranch(&'a str, &'a str, &'a str, &'a str),
+    /// No commit was present, but the repository had a remote-tracking branch
+    /// which it will most likely be based on.  The crate's version, the name
+    /// of the remote-tracking branch (e.g. `origin/main`), and the commit hash
+    /// and commit date of that branch are recorded.
+    ///
+    /// This is only produced if the testament was generated with the
+    /// `remote_fallback` option.
+    FromRemote(&'a str, &'a str, &'a str, &'a str),
+    /// No repository was present, but the crate was a package made by
+    /// `cargo package`, for example one downloaded from crates.io, which
+    /// recorded the commit it was made from.  The commit hash, the crate's
+    /// version, and the build date are recorded.
+    FromTarball(&'a str, &'a str, &'a str),
+    /// The commit was not tagged, but a later tag contains it.  The tag name,
+    /// commit hash, commit date, and the number of commits in the tag which
+    /// are not in the commit are recorded.
+    ///
+    /// This is only produced if the testament was generated with the
+    /// `describe_mode = "contains"` option, and a tag had been made since the
+    /// commit.
+    BeforeTag(&'a str, &'a str, &'a str, usize),
+}
+
+/// A high level classification of a build, see [`GitTestament::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum BuildKind {
+    /// A clean build exactly at a tag, from a trusted branch, or from a
+    /// published package
+    Release,
+    /// A clean build some commits after a tag
+    PostRelease { distance: usize },
+    /// A clean build some commits before the tag which contains it, see
+    /// [`CommitKind::BeforeTag`]
+    PreRelease { distance: usize },
+    /// A clean build with no tag to describe it, for example from a shallow
+    /// clone
+    Untagged,
+    /// A build from a commit with modifications to the working tree
+    Dirty,
+    /// A build from outside of any repository
+    NoRepo,
+    /// A build from a repository with no commits
+    NoCommit,
+}
+
+/// The decision of a policy given to [`GitTestament::render_with_policy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Trust<'a> {
+    /// Trust the commit as a release of the given version, and render it as
+    /// though that version had been tagged at the commit, just as
+    /// [`render_testament!`] does for a trusted branch
+    Version(&'a str),
+    /// Render the testament as it is
+    Untrusted,
+}
+
+/// Where the information in a testament came from.
+///
+/// The sources which are tried, and the order in which they are tried, can be
+/// chosen with the `sources` option to [`git_testament!`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TestamentSource {
+    /// No source provided any information, so only the crate's version and
+    /// the build date are known.
+    None,
+    /// The information came from running `git` in the crate's repository.
+    Git,
+    /// The information came from running `hg` in the crate's repository.
+    Mercurial,
+    /// The information came from running `jj` in the crate's repository.
+    Jujutsu,
+    /// There was no repository, so the information came from the
+    /// `.cargo_vcs_info.json` which `cargo package` wrote into the crate.
+    CargoVcsInfo,
+    /// No repository could be read, so the information came from the
+    /// `GIT_TESTAMENT_*` environment variables given to the build.
+    Environment,
+    /// There was no repository, so the commit came from a file naming it,
+    /// such as the `.git-revision` of a Nix build, see the `revision_file`
+    /// option of [`git_testament!`].
+    RevisionFile,
 }
 
 /// A testament to the state of a git repository when a crate is built.
@@ -212,11 +1103,96 @@ pub enum CommitKind<'a> {
 /// when you first have run `cargo init`) though that will include the string
 /// `uncommitted` to indicate that once commits are made the information will be
 /// of more use.
-#[derive(Debug)]
+///
+/// Testaments are only equal if every member is, so for example the same
+/// modifications recorded with and without the `compress` option are not
+/// equal.  Use [`GitTestament::same_commit`] to compare just the commits.
+#[derive(Debug, PartialEq, Eq, Hash)]
 pub struct GitTestament<'a> {
     pub commit: CommitKind<'a>,
     pub modifications: &'a [GitModification<'a>],
     pub branch_name: Option<&'a str>,
+    pub compressed_modifications: Option<CompressedModifications<'a>>,
+    pub short_hash_length: usize,
+    pub describe_hash_length: usize,
+    pub release: Option<ReleaseTag<'a>>,
+    pub extras: &'a [(&'a str, &'a str)],
+    pub parents: &'a [&'a str],
+    /// Every tag which points directly at the commit, if the `tags_at_head`
+    /// option was given
+    pub tags_at_head: &'a [&'a str],
+    /// Whether the commit is a merge, with more than one parent.  Unlike the
+    /// `parents` this is recorded whether or not the `parents` option is given.
+    pub is_merge: bool,
+    pub shallow: bool,
+    pub worktree: bool,
+    /// Whether the repository was bare, as when `GIT_DIR` names a bare
+    /// mirror which the source was exported from.  There is no working tree
+    /// to compare with the commit, so there are never any modifications.
+    pub bare: bool,
+    /// Whether `HEAD` was detached, as it is when CI systems check out a
+    /// commit rather than a branch.  The `branch_name` is then the nearest
+    /// branch or tag, or the CI system's branch if the `ci_branch` option was
+    /// given.
+    pub detached: bool,
+    pub remote_url: Option<&'a str>,
+    /// The version of git which was run, as `git --version` describes it, if
+    /// the `git_version` option was given
+    pub git_version: Option<&'a str>,
+    pub signed: bool,
+    pub signer: Option<&'a str>,
+    /// The annotated tag at the commit with a good signature, if the
+    /// `trust_signed_tags` option was given and there was one
+    pub signed_tag: Option<&'a str>,
+    /// Who signed the `signed_tag`, if that is known
+    pub tag_signer: Option<&'a str>,
+    pub source: TestamentSource,
+    pub submodules: &'a [SubmoduleInfo<'a>],
+    pub commit_timestamp: Option<&'a str>,
+    /// The output of `git describe --tags --long --dirty`, verbatim, if the
+    /// commit could be described by a tag
+    pub describe: Option<&'a str>,
+    /// The first line of the message of the annotated tag which describes the
+    /// commit, typically the title of the release, if the `tag_message`
+    /// option was given
+    pub tag_message: Option<&'a str>,
+    /// The date the annotated tag was made, if the `tag_message` option was
+    /// given
+    pub tag_date: Option<&'a str>,
+    /// The number of commits reachable from the commit, including itself, as
+    /// `git rev-list --count HEAD` would count them.  This only ever increases
+    /// along a branch, so it is suitable for use as a build number.
+    pub commit_count: Option<usize>,
+    pub rendered: &'a str,
+    pub trusted_branch: Option<&'a str>,
+    /// The release channel chosen by the `channels` option, if any
+    pub channel: Option<&'a str>,
+    /// A digest of the changes to tracked files, if the `worktree_digest`
+    /// option was given and there were any, so that testaments from
+    /// different dirty working trees can be told apart
+    pub worktree_digest: Option<&'a str>,
+    /// The start of `git diff HEAD`, if the `embed_diff` option was given
+    /// and tracked files had been changed
+    pub diff: Option<&'a [u8]>,
+    /// Whether any files stored with Git LFS had not been fetched, if the
+    /// `lfs` option was given
+    pub lfs_incomplete: bool,
+    /// The target and toolchain, if the `build_info` option was given
+    pub build_info: Option<BuildInfo<'a>>,
+    /// The CI run which built the crate, if the `ci` option was given and
+    /// one was detected
+    pub ci: Option<CiInfo<'a>>,
+    /// Whether the modifications to the working tree were collected.  This
+    /// is false if the `status = false` option was given, or the status could
+    /// not be determined, in which case there are no `modifications` but the
+    /// working tree may have been modified nonetheless.
+    pub modifications_collected: bool,
+    /// Whether any of the information was given by `GIT_TESTAMENT_*`
+    /// environment variables when building, rather than read from the
+    /// repository
+    pub overridden: bool,
+    #[cfg(feature = "provenance")]
+    pub provenance: Provenance,
 }
 
 /// An empty testament.
@@ -230,39 +1206,734 @@ pub const EMPTY_TESTAMENT: GitTestament = GitTestament {
     commit: CommitKind::NoRepository("unknown", "unknown"),
     modifications: &[],
     branch_name: None,
+    compressed_modifications: None,
+    short_hash_length: DEFAULT_SHORT_HASH_LENGTH,
+    describe_hash_length: DEFAULT_DESCRIBE_HASH_LENGTH,
+    release: None,
+    extras: &[],
+    parents: &[],
+    tags_at_head: &[],
+    is_merge: false,
+    shallow: false,
+    worktree: false,
+    bare: false,
+    detached: false,
+    remote_url: None,
+    git_version: None,
+    signed: false,
+    signer: None,
+    signed_tag: None,
+    tag_signer: None,
+    source: TestamentSource::None,
+    submodules: &[],
+    commit_timestamp: None,
+    describe: None,
+    tag_message: None,
+    tag_date: None,
+    commit_count: None,
+    rendered: "",
+    trusted_branch: None,
+    channel: None,
+    worktree_digest: None,
+    diff: None,
+    lfs_incomplete: false,
+    build_info: None,
+    ci: None,
+    modifications_collected: true,
+    overridden: false,
+    #[cfg(feature = "provenance")]
+    provenance: Provenance::DEFAULT,
 };
 
+impl<'a> GitTestament<'a> {
+    /// The full commit hash, if there was a commit when the testament was created.
+    pub const fn commit_hash(&self) -> Option<&'a str> {
+        match self.commit {
+            CommitKind::NoRepository(..)
+            | CommitKind::NoCommit(..)
+            | CommitKind::FromRemote(..) => None,
+            CommitKind::NoTags(commit, _)
+            | CommitKind::Shallow(_, commit, _)
+            | CommitKind::FromTag(_, commit, _, _)
+            | CommitKind::BeforeTag(_, commit, _, _)
+            | CommitKind::FromTrustedBranch(_, _, commit, _)
+            | CommitKind::FromTarball(commit, _, _) => Some(commit),
+        }
+    }
+
+    /// The commit hash, abbreviated to the length used when rendering the
+    /// testament.
+    ///
+    /// This is `None` if there was no commit when the testament was created.
+    /// The length is nine characters unless the testament was generated with
+    /// the `short_hash` option, for example `git_testament!(TESTAMENT, short_hash = 12)`.
+    pub fn commit_hash_short(&self) -> Option<&'a str> {
+        self.commit_hash()
+            .map(|commit| short_hash(commit, self.short_hash_length))
+    }
+
+    /// The date of the commit, if there was a commit when the testament was created.
+    pub const fn commit_date(&self) -> Option<&'a str> {
+        match self.commit {
+            CommitKind::NoRepository(..)
+            | CommitKind::NoCommit(..)
+            | CommitKind::FromRemote(..)
+            | CommitKind::FromTarball(..) => None,
+            CommitKind::NoTags(_, date)
+            | CommitKind::Shallow(_, _, date)
+            | CommitKind::FromTag(_, _, date, _)
+            | CommitKind::BeforeTag(_, _, date, _)
+            | CommitKind::FromTrustedBranch(_, _, _, date) => Some(date),
+        }
+    }
+
+    /// The tag found in the history of the commit, if any.
+    pub const fn tag(&self) -> Option<&'a str> {
+        match self.commit {
+            CommitKind::FromTag(tag, _, _, _) | CommitKind::FromTrustedBranch(tag, _, _, _) => {
+                Some(tag)
+            }
+            _ => None,
+        }
+    }
+
+    /// The number of commits since [`GitTestament::tag`], if there was a tag.
+    pub const fn tag_distance(&self) -> Option<usize> {
+        match self.commit {
+            CommitKind::FromTag(_, _, _, distance) => Some(distance),
+            CommitKind::FromTrustedBranch(..) => Some(0),
+            _ => None,
+        }
+    }
+
+    /// The later tag which contains the commit, and the number of commits in
+    /// it which are not in the commit, if the testament was generated with the
+    /// `describe_mode = "contains"` option and the commit was not itself
+    /// tagged.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::BeforeTag("v1.2.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 3),
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// assert_eq!(testament.containing_tag(), Some(("v1.2.0", 3)));
+    /// assert_eq!(testament.tag(), None);
+    /// assert_eq!(testament.to_string(), "v1.2.0~3 (763aa159d 2019-04-02)");
+    /// ```
+    pub const fn containing_tag(&self) -> Option<(&'a str, usize)> {
+        match self.commit {
+            CommitKind::BeforeTag(tag, _, _, distance) => Some((tag, distance)),
+            _ => None,
+        }
+    }
+
+    /// The date of the build.
+    ///
+    /// This is only recorded when there was no commit to take a date from,
+    /// otherwise this is `None` and you likely want [`GitTestament::commit_date`].
+    pub const fn build_date(&self) -> Option<&'a str> {
+        match self.commit {
+            CommitKind::NoRepository(_, date)
+            | CommitKind::NoCommit(_, date)
+            | CommitKind::FromTarball(_, _, date) => Some(date),
+            _ => None,
+        }
+    }
+
+    /// The value of an extra field given with the `extra` option when the
+    /// testament was created, if there was one with the given key.
+    pub fn extra(&self, key: &str) -> Option<&'a str> {
+        self.extras
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| *value)
+    }
+
+    /// The testament as rendered by [`render_testament!`] when it was
+    /// generated, for use where a version string is needed in a `const`
+    /// context.
+    ///
+    /// This is empty if the testament was not generated by
+    /// [`git_testament!`] or captured at runtime, and it does not reflect any
+    /// changes made to the testament afterwards.
+    pub const fn rendered(&self) -> &'a str {
+        self.rendered
+    }
+
+    /// Whether there were any modifications to the working tree.
+    ///
+    /// This is false if the modifications were not collected, see
+    /// [`modifications_collected`](Self::modifications_collected), so a
+    /// testament which is not dirty is not necessarily clean.
+    pub const fn is_dirty(&self) -> bool {
+        self.modification_count() > 0
+    }
+
+    /// Whether the working tree is known to have had no modifications, which
+    /// is what anything claiming to be a release relies on.
+    const fn is_known_clean(&self) -> bool {
+        self.modifications_collected && self.modification_count() == 0
+    }
+
+    /// Whether both testaments were created from the same commit, for example
+    /// to check that a client and server match.
+    ///
+    /// Unlike `==`, this ignores everything but the commit hash, so the
+    /// testaments may differ in their tags, branches or trust.  Neither are
+    /// the modifications considered, so check [`is_dirty`](Self::is_dirty)
+    /// too if both must also have been built from a clean working tree.
+    /// Testaments without a commit never have the same commit as anything.
+    pub fn same_commit(&self, other: &GitTestament) -> bool {
+        match (self.commit_hash(), other.commit_hash()) {
+            (Some(ours), Some(theirs)) => ours == theirs,
+            _ => false,
+        }
+    }
+
+    /// Classify the build, for example to disable update checks in anything
+    /// other than a release build.
+    ///
+    /// A dirty working tree takes precedence over the tag, so a modified
+    /// build at a tag is [`BuildKind::Dirty`] rather than a release.  If the
+    /// modifications were not collected then the build is classified as
+    /// though the working tree were clean, except that
+    /// [`is_release`](Self::is_release) is still false.
+    pub const fn kind(&self) -> BuildKind {
+        match self.commit {
+            CommitKind::NoRepository(..) => BuildKind::NoRepo,
+            CommitKind::NoCommit(..) | CommitKind::FromRemote(..) => BuildKind::NoCommit,
+            _ if self.is_dirty() => BuildKind::Dirty,
+            CommitKind::FromTag(_, _, _, 0)
+            | CommitKind::FromTrustedBranch(..)
+            | CommitKind::FromTarball(..) => BuildKind::Release,
+            CommitKind::FromTag(_, _, _, distance) => BuildKind::PostRelease { distance },
+            CommitKind::BeforeTag(_, _, _, distance) => BuildKind::PreRelease { distance },
+            CommitKind::NoTags(..) | CommitKind::Shallow(..) => BuildKind::Untagged,
+        }
+    }
+
+    /// Whether this is a clean build exactly at a tag, from a trusted branch,
+    /// or from a published package.  This is shorthand for checking for [`BuildKind::Release`].
+    pub const fn is_release(&self) -> bool {
+        self.modifications_collected && matches!(self.kind(), BuildKind::Release)
+    }
+
+    /// The version of the release this testament was built from, suitable for
+    /// passing to self-update tooling.
+    ///
+    /// This is the tag name, without any leading `v`, but only if the build was
+    /// made exactly at that tag from a working tree known to be clean.  A build from a
+    /// published package gives the crate's version instead.  Development
+    /// builds, dirty builds, and builds without tags return `None`.
+    pub fn release_version(&self) -> Option<&'a str> {
+        match self.commit {
+            CommitKind::FromTag(tag, _, _, 0)
+            | CommitKind::FromTrustedBranch(tag, _, _, _)
+            | CommitKind::FromTarball(_, tag, _)
+                if self.is_known_clean() =>
+            {
+                Some(tag.strip_prefix(['v', 'V']).unwrap_or(tag))
+            }
+            _ => None,
+        }
+    }
+
+    /// Determine if a newer release than this build is available.
+    ///
+    /// `latest_release` is the tag (or version) of the most recent release, for
+    /// example as reported by a GitHub release.  Tags may have a leading `v`.
+    ///
+    /// If this build is from commits after a tag, then it is considered to be
+    /// newer than that tag.  If this build was not made from a tag, or either
+    /// version cannot be understood, then `None` is returned.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::FromTag("v1.2.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 0),
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// assert_eq!(testament.update_available("v1.3.0"), Some(true));
+    /// assert_eq!(testament.update_available("v1.2.0"), Some(false));
+    /// ```
+    pub fn update_available(&self, latest_release: &str) -> Option<bool> {
+        let current = version::Version::parse(self.tag()?)?;
+        let latest = version::Version::parse(latest_release)?;
+        Some(latest > current)
+    }
+
+    /// Determine if this build is at least as new as the given tag (or version).
+    ///
+    /// This is useful to refuse to run a build which predates something it
+    /// must not be mixed with, such as a required data migration.  Builds made
+    /// from commits after a tag are considered to be at least that tag.  If
+    /// this build was not made from a tag, or either version cannot be
+    /// understood, then `None` is returned and the caller must decide.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::FromTag("v1.2.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 3),
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// assert_eq!(testament.at_least("v1.1.0"), Some(true));
+    /// assert_eq!(testament.at_least("v1.2.0"), Some(true));
+    /// assert_eq!(testament.at_least("v1.3.0"), Some(false));
+    /// ```
+    pub fn at_least(&self, tag: &str) -> Option<bool> {
+        let current = version::Version::parse(self.tag()?)?;
+        let required = version::Version::parse(tag)?;
+        Some(current >= required)
+    }
+
+    /// The crate's version, with this testament recorded in its build
+    /// metadata, for update checks or naming artifacts.
+    ///
+    /// `pkg_version` is the crate's version, normally
+    /// `env!("CARGO_PKG_VERSION")`.  The build metadata has the abbreviated
+    /// commit hash prefixed with `g`, then the number of commits since the tag
+    /// prefixed with `d` if there have been any, then `dirty` if the working
+    /// tree had modifications, so a build four commits after its tag is
+    /// something like `1.2.3+g763aa159d.d4`.  Any build metadata already in
+    /// `pkg_version` is kept before these.  Without a commit the version is
+    /// returned as it is.  The error is that of parsing `pkg_version`.
+    ///
+    /// Build metadata is ignored when comparing versions, so this is as
+    /// suitable for update checks as the crate's version itself.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::FromTag("v1.2.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 4),
+    ///     short_hash_length: 7,
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// let version = testament.version_semver("1.2.3").unwrap();
+    /// assert_eq!(version.to_string(), "1.2.3+g763aa15.d4");
+    /// ```
+    #[cfg(feature = "semver")]
+    pub fn version_semver(&self, pkg_version: &str) -> Result<::semver::Version, ::semver::Error> {
+        use alloc::string::String;
+
+        let mut version = ::semver::Version::parse(pkg_version)?;
+        let Some(hash) = self.commit_hash_short() else {
+            return Ok(version);
+        };
+        let mut metadata = String::from(version.build.as_str());
+        if !metadata.is_empty() {
+            metadata.push('.');
+        }
+        metadata.push('g');
+        metadata.push_str(hash);
+        if let Some(distance) = self.tag_distance().filter(|&distance| distance > 0) {
+            metadata.push_str(&alloc::format!(".d{distance}"));
+        }
+        if self.is_dirty() {
+            metadata.push_str(".dirty");
+        }
+        version.build = ::semver::BuildMetadata::new(&metadata)?;
+        Ok(version)
+    }
+
+    /// Apply the trusted branch override to this testament.
+    ///
+    /// If the testament was made from a tag, on a branch matching the given
+    /// trusted branch, and the working tree was clean, then this returns an
+    /// equivalent testament which uses [`CommitKind::FromTrustedBranch`] to
+    /// render as though the tag `pkg_version` had been pushed at the built
+    /// commit.  Otherwise this returns `None`.
+    ///
+    /// The trusted branch may be a glob pattern, in which `*` matches any
+    /// sequence of characters, including `/`, and `?` matches any single
+    /// character, so `release/*` trusts every release branch.
+    ///
+    /// This is what [`render_testament!`] does when given a trusted branch,
+    /// and is useful if you need to know whether or not the override applied.
+    pub fn trusting_branch(
+        &self,
+        pkg_version: &'a str,
+        trusted_branch: &'a str,
+    ) -> Option<GitTestament<'a>> {
+        match (self.commit, self.branch_name) {
+            (
+                CommitKind::FromTag(_, hash, date, _) | CommitKind::BeforeTag(_, hash, date, _),
+                Some(branch),
+            ) if glob_matches(trusted_branch, branch) && self.is_known_clean() => {
+                Some(GitTestament {
+                    commit: CommitKind::FromTrustedBranch(pkg_version, branch, hash, date),
+                    ..*self
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Apply the trusted branch override because of a signed tag.
+    ///
+    /// If the testament was made from a tag, the commit had an annotated tag
+    /// with a good signature (see the `trust_signed_tags` option of
+    /// [`git_testament!`]), and the working tree was clean, then this returns
+    /// an equivalent testament which uses [`CommitKind::FromTrustedBranch`],
+    /// naming the signed tag, just as [`GitTestament::trusting_branch`] would.
+    /// Otherwise this returns `None`.
+    pub fn trusting_signed_tag(&self, pkg_version: &'a str) -> Option<GitTestament<'a>> {
+        match (self.commit, self.signed_tag) {
+            (CommitKind::FromTag(_, hash, date, _), Some(tag)) if self.is_known_clean() => {
+                Some(GitTestament {
+                    commit: CommitKind::FromTrustedBranch(pkg_version, tag, hash, date),
+                    ..*self
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Apply the trusted branch override for a signed tag, or otherwise for
+    /// the first of the `trusted_branches` which applies, or for the
+    /// testament's own `trusted_branch` if none are given.
+    #[cfg(feature = "alloc")]
+    fn trusting_any_branch(
+        &self,
+        pkg_version: &'a str,
+        trusted_branches: &[&'a str],
+    ) -> Option<GitTestament<'a>> {
+        if let Some(trusted) = self.trusting_signed_tag(pkg_version) {
+            return Some(trusted);
+        }
+        let recorded = match &self.trusted_branch {
+            Some(branch) => core::slice::from_ref(branch),
+            None => &[],
+        };
+        let trusted_branches = if trusted_branches.is_empty() {
+            recorded
+        } else {
+            trusted_branches
+        };
+        trusted_branches
+            .iter()
+            .find_map(|branch| self.trusting_branch(pkg_version, branch))
+    }
+
+    /// Render the testament, trusting its commit as a release if `policy`
+    /// says to, rather than only if it was built on a trusted branch.
+    ///
+    /// The policy sees the whole testament, so it can decide on whatever
+    /// grounds suit the application, for example an environment variable or
+    /// a list of released commits.  Unlike a trusted branch, a policy may
+    /// trust a commit with modifications to the working tree, and they are
+    /// still rendered, so a dirty build cannot pass for a clean release.
+    /// Only testaments with a commit, and which are not already trusted, can
+    /// be trusted, others are rendered as they are.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitTestament, Trust, EMPTY_TESTAMENT};
+    ///
+    /// const RELEASED: &[&str] = &["763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3"];
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::FromTag("1.0.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 3),
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// let rendered = testament.render_with_policy(|testament| {
+    ///     match testament.commit_hash() {
+    ///         Some(hash) if RELEASED.contains(&hash) => Trust::Version("1.1.0"),
+    ///         _ => Trust::Untrusted,
+    ///     }
+    /// });
+    /// assert_eq!(rendered.to_string(), "1.1.0 (763aa159d 2019-04-02)");
+    /// ```
+    pub fn render_with_policy<P>(&self, policy: P) -> impl Display + 'a
+    where
+        P: FnOnce(&GitTestament<'a>) -> Trust<'a>,
+    {
+        let (hash, date) = match self.commit {
+            CommitKind::NoTags(hash, date)
+            | CommitKind::Shallow(_, hash, date)
+            | CommitKind::FromTag(_, hash, date, _)
+            | CommitKind::BeforeTag(_, hash, date, _) => (hash, date),
+            _ => return GitTestament { ..*self },
+        };
+        match policy(self) {
+            Trust::Version(version) => GitTestament {
+                commit: CommitKind::FromTrustedBranch(
+                    version,
+                    self.branch_name.unwrap_or(""),
+                    hash,
+                    date,
+                ),
+                ..*self
+            },
+            Trust::Untrusted => GitTestament { ..*self },
+        }
+    }
+
+    /// The number of modifications to the working tree which were recorded,
+    /// regardless of whether or not they were compressed.
+    pub const fn modification_count(&self) -> usize {
+        match self.compressed_modifications {
+            Some(compressed) => compressed.count,
+            None => self.modifications.len(),
+        }
+    }
+
+    /// The number of modifications to the working tree of each kind, for
+    /// example to describe a build as `dirty (2 modified, 1 untracked)`.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitModification, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+    ///     modifications: &[
+    ///         GitModification::Modified(b"src/main.rs"),
+    ///         GitModification::Untracked(b"notes.txt"),
+    ///         GitModification::Modified(b"Cargo.toml"),
+    ///     ],
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// let summary = testament.modification_summary();
+    /// assert_eq!(summary.modified, 2);
+    /// assert_eq!(summary.total(), 3);
+    /// assert_eq!(format!("dirty ({summary})"), "dirty (2 modified, 1 untracked)");
+    /// ```
+    ///
+    /// If the modifications were compressed and the `compression` feature is
+    /// not enabled, then they are all counted as `unknown`.
+    pub fn modification_summary(&self) -> ModificationSummary {
+        let mut summary = ModificationSummary::default();
+        match self.compressed_modifications {
+            #[cfg(feature = "compression")]
+            Some(compressed) => match compressed.decompress() {
+                Some(table) => table.iter().for_each(|m| summary.add(m.kind())),
+                None => summary.unknown = compressed.count,
+            },
+            #[cfg(not(feature = "compression"))]
+            Some(compressed) => summary.unknown = compressed.count,
+            None => self
+                .modifications
+                .iter()
+                .for_each(|m| summary.add(m.kind())),
+        }
+        summary
+    }
+}
+
 #[cfg(feature = "alloc")]
 impl<'a> GitTestament<'a> {
+    /// Render this testament as `git describe --tags --long --dirty --always`
+    /// would have described the working tree when the testament was created.
+    ///
+    /// For example `1.0.0-14-g651af89-dirty`, or just the abbreviated commit
+    /// hash if there were no tags.  If there was no commit then `None` is
+    /// returned since `git describe` would have failed.  A commit before the
+    /// tag which contains it is described as `git describe --contains` would,
+    /// for example `1.2.0~3`, which never mentions modifications.
+    ///
+    /// As with `git describe`, untracked files do not count as making the
+    /// tree dirty.  If the modifications were compressed and the `compression`
+    /// feature is not enabled, then any modification is assumed to be tracked.
+    pub fn to_describe_string(&self) -> Option<alloc::string::String> {
+        let dirty = if self.has_tracked_modifications() {
+            "-dirty"
+        } else {
+            ""
+        };
+        let hash = |commit| short_hash(commit, self.describe_hash_length);
+        match self.commit {
+            CommitKind::NoRepository(..)
+            | CommitKind::NoCommit(..)
+            | CommitKind::FromRemote(..) => None,
+            CommitKind::NoTags(commit, _)
+            | CommitKind::Shallow(_, commit, _)
+            | CommitKind::FromTarball(commit, _, _) => {
+                Some(alloc::format!("{}{dirty}", hash(commit)))
+            }
+            CommitKind::FromTag(tag, commit, _, distance) => {
+                Some(alloc::format!("{tag}-{distance}-g{}{dirty}", hash(commit)))
+            }
+            CommitKind::BeforeTag(tag, _, _, distance) => Some(alloc::format!("{tag}~{distance}")),
+            CommitKind::FromTrustedBranch(version, _, commit, _) => {
+                Some(alloc::format!("{version}-0-g{}{dirty}", hash(commit)))
+            }
+        }
+    }
+
+    fn has_tracked_modifications(&self) -> bool {
+        let tracked = |kind| kind != ModificationKind::Untracked;
+        match self.compressed_modifications {
+            #[cfg(feature = "compression")]
+            Some(compressed) => compressed
+                .decompress()
+                .map_or(compressed.count > 0, |table| {
+                    table.iter().any(|m| tracked(m.kind()))
+                }),
+            #[cfg(not(feature = "compression"))]
+            Some(compressed) => compressed.count > 0,
+            None => self.modifications.iter().any(|m| tracked(m.kind())),
+        }
+    }
+
+    /// Render this testament according to a format string.
+    ///
+    /// The format string may contain the following placeholders:
+    ///
+    /// * `{tag}` -> The tag name, or the crate's version if it was recorded
+    ///   instead of a tag, or `unknown` if there was neither
+    /// * `{hash}` -> The full commit hash, or `{hash:N}` for the first `N`
+    ///   characters of it (empty if there was no commit)
+    /// * `{date}` -> The commit date, or the build date if there was no commit
+    /// * `{distance}` -> The number of commits since the tag, or before it for
+    ///   a [`CommitKind::BeforeTag`] (zero otherwise)
+    /// * `{dirty}` -> `dirty` if there were modifications, otherwise empty
+    /// * `{branch}` -> The branch name if known, otherwise empty
+    ///
+    /// Use `{{` and `}}` for literal braces.  Anything else in braces is
+    /// left as it is.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::FromTag("v1.2.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 3),
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// assert_eq!(
+    ///     testament.render_format("{tag}+{distance}.g{hash:7} {date}"),
+    ///     "v1.2.0+3.g763aa15 2019-04-02"
+    /// );
+    /// ```
+    pub fn render_format(&self, format: &str) -> alloc::string::String {
+        let mut out = alloc::string::String::with_capacity(format.len());
+        let mut rest = format;
+        while let Some(pos) = rest.find(['{', '}']) {
+            out.push_str(&rest[..pos]);
+            rest = &rest[pos..];
+            if let Some(after) = rest.strip_prefix("{{") {
+                out.push('{');
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("}}") {
+                out.push('}');
+                rest = after;
+            } else {
+                match rest[1..].split_once('}') {
+                    Some((placeholder, after))
+                        if rest.starts_with('{')
+                            && self.render_placeholder(placeholder, &mut out) =>
+                    {
+                        rest = after;
+                    }
+                    _ => {
+                        // Not something we understand, so leave it be
+                        out.push_str(&rest[..1]);
+                        rest = &rest[1..];
+                    }
+                }
+            }
+        }
+        out.push_str(rest);
+        out
+    }
+
+    /// Render a single `render_format` placeholder, returning `false` if it
+    /// was not understood.
+    fn render_placeholder(&self, placeholder: &str, out: &mut alloc::string::String) -> bool {
+        let (name, arg) = match placeholder.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (placeholder, None),
+        };
+        match (name, arg) {
+            ("tag", None) => out.push_str(match self.commit {
+                CommitKind::FromTag(tag, ..)
+                | CommitKind::BeforeTag(tag, ..)
+                | CommitKind::FromTrustedBranch(tag, ..)
+                | CommitKind::NoRepository(tag, _)
+                | CommitKind::NoCommit(tag, _)
+                | CommitKind::Shallow(tag, ..)
+                | CommitKind::FromRemote(tag, ..)
+                | CommitKind::FromTarball(_, tag, _) => tag,
+                CommitKind::NoTags(..) => "unknown",
+            }),
+            ("hash", None) => out.push_str(self.commit_hash().unwrap_or("")),
+            ("hash", Some(len)) => match len.parse() {
+                Ok(len) => out.push_str(short_hash(self.commit_hash().unwrap_or(""), len)),
+                Err(_) => return false,
+            },
+            ("date", None) => out.push_str(
+                self.commit_date()
+                    .or_else(|| self.build_date())
+                    .unwrap_or(""),
+            ),
+            ("distance", None) => {
+                let distance = match self.containing_tag() {
+                    Some((_, distance)) => distance,
+                    None => self.tag_distance().unwrap_or(0),
+                };
+                out.push_str(&alloc::format!("{distance}"));
+            }
+            ("dirty", None) => {
+                if self.is_dirty() {
+                    out.push_str("dirty");
+                }
+            }
+            ("branch", None) => out.push_str(self.branch_name.unwrap_or("")),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Render the testament as [`render_testament!`] does, but taking the
+    /// version to display and the branches to trust as arguments rather than
+    /// from the crate being built.
+    ///
+    /// This is useful when the version shown should be that of a whole
+    /// product, such as a workspace version given to the build in an
+    /// environment variable, rather than that of the crate with the binary.
+    /// The version is shown alongside the testament if the tag does not
+    /// contain it, and is used as the tag if a trusted branch was built.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::FromTag("v1.0.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 3),
+    ///     branch_name: Some("main"),
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// assert_eq!(
+    ///     testament.render_with_version("2024.1", &[]),
+    ///     "2024.1 :: v1.0.0+3 (763aa159d 2019-04-02)"
+    /// );
+    /// assert_eq!(
+    ///     testament.render_with_version("2024.1", &["main"]),
+    ///     "2024.1 (763aa159d 2019-04-02)"
+    /// );
+    /// ```
+    pub fn render_with_version(
+        &self,
+        version: &str,
+        trusted_branches: &[&str],
+    ) -> alloc::string::String {
+        self._render_with_version(version, trusted_branches)
+    }
+
     #[doc(hidden)]
     pub fn _render_with_version(
         &self,
         pkg_version: &str,
-        trusted_branch: Option<&'static str>,
+        trusted_branches: &[&str],
     ) -> alloc::string::String {
+        if let Some(trusted) = self.trusting_any_branch(pkg_version, trusted_branches) {
+            // We trust our branch, so render the equivalent testament
+            return alloc::format!("{trusted}");
+        }
         match self.commit {
-            CommitKind::FromTag(tag, hash, date, _) => {
-                let trusted = match trusted_branch {
-                    Some(_) => {
-                        if self.branch_name == trusted_branch {
-                            self.modifications.is_empty()
-                        } else {
-                            false
-                        }
-                    }
-                    None => false,
-                };
-                if trusted {
-                    // We trust our branch, so construct an equivalent
-                    // testament to render
-                    alloc::format!(
-                        "{}",
-                        GitTestament {
-                            commit: CommitKind::FromTag(pkg_version, hash, date, 0),
-                            ..*self
-                        }
-                    )
-                } else if tag.contains(pkg_version) {
+            CommitKind::FromTag(tag, _, _, _) | CommitKind::BeforeTag(tag, _, _, _) => {
+                if tag.contains(pkg_version) {
                     alloc::format!("{self}")
                 } else {
                     alloc::format!("{pkg_version} :: {self}")
@@ -271,6 +1942,48 @@ impl<'a> GitTestament<'a> {
             _ => alloc::format!("{self}"),
         }
     }
+
+    #[doc(hidden)]
+    pub fn _render_verbose_with_version(
+        &self,
+        pkg_version: &str,
+        trusted_branches: &[&'static str],
+    ) -> alloc::string::String {
+        let rendered = self._render_with_version(pkg_version, trusted_branches);
+        alloc::format!("{rendered}{}", ModificationList(self))
+    }
+
+    /// Render the testament as its `Display` implementation would, followed
+    /// by each modification to the working tree on its own indented line.
+    /// The alternate form of `Display`, `{:#}`, also shows the branch and
+    /// the commit.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitModification, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+    ///     modifications: &[
+    ///         GitModification::Modified(b"src/main.rs"),
+    ///         GitModification::Renamed(b"README", b"README.md"),
+    ///     ],
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// assert_eq!(
+    ///     testament.render_verbose().to_string(),
+    ///     concat!(
+    ///         "1.0.0 (uncommitted 2019-04-02) dirty 2 modifications\n",
+    ///         "    modified: src/main.rs\n",
+    ///         "    renamed: README -> README.md",
+    ///     )
+    /// );
+    /// ```
+    ///
+    /// If the modifications were compressed and the `compression` feature is
+    /// not enabled, then they are not listed.
+    pub fn render_verbose(&self) -> impl Display + '_ {
+        RenderVerbose(self)
+    }
 }
 
 /// Render a testament
@@ -289,6 +2002,12 @@ impl<'a> GitTestament<'a> {
 /// a fundamental part of the behaviour of `git_testament` it is recommended that
 /// this *ONLY* be used if you have a trusted CI release branch process.
 ///
+/// The trusted branch may be a glob pattern, in which `*` matches any sequence
+/// of characters, including `/`, and `?` matches any single character.  To
+/// trust several branches, give a list of them as `trusted = [...]`.  A
+/// testament generated with the `trust_signed_tags` option is also trusted,
+/// whatever the branch, when the commit has a tag with a good signature.
+///
 /// ```
 /// use git_testament::{git_testament, render_testament};
 ///
@@ -297,6 +2016,42 @@ impl<'a> GitTestament<'a> {
 /// # fn main() {
 /// println!("The testament is: {}", render_testament!(TESTAMENT));
 /// println!("The fiddled testament is: {}", render_testament!(TESTAMENT, "trusted-branch"));
+/// println!(
+///     "The release testament is: {}",
+///     render_testament!(TESTAMENT, trusted = ["main", "release/*"])
+/// );
+/// # }
+/// ```
+///
+/// Alternatively, the testament can be rendered according to a format string,
+/// see [`GitTestament::render_format`] for the placeholders available.
+///
+/// ```
+/// use git_testament::{git_testament, render_testament};
+///
+/// git_testament!(TESTAMENT);
+///
+/// # fn main() {
+/// println!("Built from {}", render_testament!(TESTAMENT, format = "{tag} {hash:7} {dirty}"));
+/// # }
+/// ```
+///
+/// The version shown defaults to the crate's own, but another may be given
+/// as `version = ...` after any trusted branches, for example to show the
+/// version of a whole workspace, see [`GitTestament::render_with_version`].
+///
+/// ```
+/// use git_testament::{git_testament, render_testament};
+///
+/// git_testament!(TESTAMENT);
+/// const PRODUCT_VERSION: &str = "2024.1";
+///
+/// # fn main() {
+/// println!("Version: {}", render_testament!(TESTAMENT, version = PRODUCT_VERSION));
+/// println!(
+///     "Release: {}",
+///     render_testament!(TESTAMENT, "main", version = PRODUCT_VERSION)
+/// );
 /// # }
 #[cfg(feature = "alloc")]
 #[macro_export]
@@ -305,56 +2060,609 @@ macro_rules! render_testament {
         $crate::GitTestament::_render_with_version(
             &$testament,
             $crate::__core::env!("CARGO_PKG_VERSION"),
-            $crate::__core::option::Option::None,
+            &[],
+        )
+    };
+    ( $testament:expr, format = $format:expr ) => {
+        $crate::GitTestament::render_format(&$testament, $format)
+    };
+    ( $testament:expr, version = $version:expr ) => {
+        $crate::GitTestament::render_with_version(&$testament, $version, &[])
+    };
+    ( $testament:expr, trusted = [$($trusted_branch:expr),* $(,)?], version = $version:expr ) => {
+        $crate::GitTestament::render_with_version(&$testament, $version, &[$($trusted_branch),*])
+    };
+    ( $testament:expr, $trusted_branch:expr, version = $version:expr ) => {
+        $crate::GitTestament::render_with_version(&$testament, $version, &[$trusted_branch])
+    };
+    ( $testament:expr, trusted = [$($trusted_branch:expr),* $(,)?] ) => {
+        $crate::GitTestament::_render_with_version(
+            &$testament,
+            $crate::__core::env!("CARGO_PKG_VERSION"),
+            &[$($trusted_branch),*],
         )
     };
     ( $testament:expr, $trusted_branch:expr ) => {
         $crate::GitTestament::_render_with_version(
             &$testament,
             $crate::__core::env!("CARGO_PKG_VERSION"),
-            $crate::__core::option::Option::Some($trusted_branch),
+            &[$trusted_branch],
         )
     };
 }
 
-impl<'a> Display for CommitKind<'a> {
-    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+/// Render a testament as JSON
+///
+/// This renders a testament in the same way as [`GitTestament::to_json`],
+/// but also records the crate's version.  As with [`render_testament!`], a
+/// trusted branch may be given as a second argument, in which case the
+/// crate's version is recorded as the tag if the branch is trusted.
+///
+/// ```
+/// use git_testament::{git_testament, render_testament_json};
+///
+/// git_testament!(TESTAMENT);
+///
+/// # fn main() {
+/// println!("{}", render_testament_json!(TESTAMENT));
+/// # }
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! render_testament_json {
+    ( $testament:expr ) => {
+        $crate::GitTestament::_to_json_with_version(
+            &$testament,
+            $crate::__core::env!("CARGO_PKG_VERSION"),
+            &[],
+        )
+    };
+    ( $testament:expr, trusted = [$($trusted_branch:expr),* $(,)?] ) => {
+        $crate::GitTestament::_to_json_with_version(
+            &$testament,
+            $crate::__core::env!("CARGO_PKG_VERSION"),
+            &[$($trusted_branch),*],
+        )
+    };
+    ( $testament:expr, $trusted_branch:expr ) => {
+        $crate::GitTestament::_to_json_with_version(
+            &$testament,
+            $crate::__core::env!("CARGO_PKG_VERSION"),
+            &[$trusted_branch],
+        )
+    };
+}
+
+/// Render a testament followed by a list of the modifications to the working
+/// tree
+///
+/// This renders a testament in the same way as [`render_testament!`], and
+/// then lists each modification on its own indented line, as
+/// [`GitTestament::render_verbose`] does.  This is intended for verbose
+/// version output, such as that pasted into bug reports.  As with
+/// [`render_testament!`], a trusted branch may be given as a second argument.
+///
+/// ```
+/// use git_testament::{git_testament, render_testament_verbose};
+///
+/// git_testament!(TESTAMENT);
+///
+/// # fn main() {
+/// println!("{}", render_testament_verbose!(TESTAMENT));
+/// # }
+/// ```
+#[cfg(feature = "alloc")]
+#[macro_export]
+macro_rules! render_testament_verbose {
+    ( $testament:expr ) => {
+        $crate::GitTestament::_render_verbose_with_version(
+            &$testament,
+            $crate::__core::env!("CARGO_PKG_VERSION"),
+            &[],
+        )
+    };
+    ( $testament:expr, trusted = [$($trusted_branch:expr),* $(,)?] ) => {
+        $crate::GitTestament::_render_verbose_with_version(
+            &$testament,
+            $crate::__core::env!("CARGO_PKG_VERSION"),
+            &[$($trusted_branch),*],
+        )
+    };
+    ( $testament:expr, $trusted_branch:expr ) => {
+        $crate::GitTestament::_render_verbose_with_version(
+            &$testament,
+            $crate::__core::env!("CARGO_PKG_VERSION"),
+            &[$trusted_branch],
+        )
+    };
+}
+
+/// Match `text` against a glob `pattern` in which `*` matches any sequence of
+/// characters, including `/`, and `?` matches any single character.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let (mut pattern_rest, mut text_rest) = (pattern, text);
+    // Where to resume if the most recent `*` needs to match more characters
+    let mut star = None;
+    loop {
+        let mut text_chars = text_rest.chars();
+        let c = match text_chars.next() {
+            Some(c) => c,
+            None => break,
+        };
+        let mut pattern_chars = pattern_rest.chars();
+        match pattern_chars.next() {
+            Some('*') => {
+                star = Some((pattern_chars.as_str(), text_rest));
+                pattern_rest = pattern_chars.as_str();
+            }
+            Some(p) if p == '?' || p == c => {
+                pattern_rest = pattern_chars.as_str();
+                text_rest = text_chars.as_str();
+            }
+            _ => match star {
+                Some((after_star, star_text)) => {
+                    let mut star_chars = star_text.chars();
+                    star_chars.next();
+                    pattern_rest = after_star;
+                    text_rest = star_chars.as_str();
+                    star = Some((after_star, text_rest));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern_rest.chars().all(|c| c == '*')
+}
+
+/// The length to which commit hashes are abbreviated unless the testament
+/// was generated with the `short_hash` option.
+const DEFAULT_SHORT_HASH_LENGTH: usize = 9;
+
+/// The length to which `git describe` abbreviates commit hashes in small
+/// repositories, used if the testament did not record the actual length.
+const DEFAULT_DESCRIBE_HASH_LENGTH: usize = 7;
+
+fn short_hash(commit: &str, len: usize) -> &str {
+    commit.get(..len).unwrap_or(commit)
+}
+
+impl<'a> CommitKind<'a> {
+    /// Whether the commit is exactly at a tag, on a trusted branch, or was
+    /// packaged for publishing.
+    ///
+    /// This does not consider modifications to the working tree, see
+    /// [`GitTestament::is_release`] for that.
+    pub const fn is_release(&self) -> bool {
+        matches!(
+            self,
+            CommitKind::FromTag(_, _, _, 0)
+                | CommitKind::FromTrustedBranch(..)
+                | CommitKind::FromTarball(..)
+        )
+    }
+
+    /// Whether a commit was recorded, rather than just the crate's version.
+    pub const fn has_commit(&self) -> bool {
+        !matches!(
+            self,
+            CommitKind::NoRepository(..) | CommitKind::NoCommit(..) | CommitKind::FromRemote(..)
+        )
+    }
+
+    fn fmt_with_strings(
+        &self,
+        fmt: &mut Formatter,
+        hash_len: usize,
+        strings: &RenderStrings,
+    ) -> fmt::Result {
         match self {
             CommitKind::NoRepository(crate_ver, build_date) => {
                 write!(fmt, "{crate_ver} ({build_date})")
             }
             CommitKind::NoCommit(crate_ver, build_date) => {
-                write!(fmt, "{crate_ver} (uncommitted {build_date})")
+                write!(fmt, "{crate_ver} ({} {build_date})", strings.uncommitted)
+            }
+            CommitKind::Shallow(crate_ver, commit, when) => {
+                write!(
+                    fmt,
+                    "{} ({} {} {})",
+                    crate_ver,
+                    strings.shallow,
+                    short_hash(commit, hash_len),
+                    when
+                )
             }
             CommitKind::NoTags(commit, when) => {
-                write!(fmt, "unknown ({} {})", &commit[..9], when)
+                write!(
+                    fmt,
+                    "{} ({} {})",
+                    strings.unknown,
+                    short_hash(commit, hash_len),
+                    when
+                )
             }
             CommitKind::FromTag(tag, commit, when, depth) => {
+                let commit = short_hash(commit, hash_len);
                 if *depth > 0 {
-                    write!(fmt, "{}+{} ({} {})", tag, depth, &commit[..9], when)
+                    write!(fmt, "{tag}+{depth} ({commit} {when})")
                 } else {
-                    write!(fmt, "{} ({} {})", tag, &commit[..9], when)
+                    write!(fmt, "{tag} ({commit} {when})")
                 }
             }
+            CommitKind::BeforeTag(tag, commit, when, depth) => {
+                let commit = short_hash(commit, hash_len);
+                write!(fmt, "{tag}~{depth} ({commit} {when})")
+            }
+            CommitKind::FromTrustedBranch(version, _, commit, when) => {
+                write!(
+                    fmt,
+                    "{} ({} {})",
+                    version,
+                    short_hash(commit, hash_len),
+                    when
+                )
+            }
+            CommitKind::FromRemote(crate_ver, remote, commit, when) => {
+                write!(
+                    fmt,
+                    "{} ({}, {} {} {})",
+                    crate_ver,
+                    strings.uncommitted,
+                    remote,
+                    short_hash(commit, hash_len),
+                    when
+                )
+            }
+            CommitKind::FromTarball(commit, crate_ver, build_date) => {
+                write!(
+                    fmt,
+                    "{} ({} {} {})",
+                    crate_ver,
+                    strings.tarball,
+                    short_hash(commit, hash_len),
+                    build_date
+                )
+            }
         }
     }
 }
 
-impl<'a> Display for GitTestament<'a> {
+impl<'a> Display for CommitKind<'a> {
     fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
-        self.commit.fmt(fmt)?;
-        if !self.modifications.is_empty() {
+        self.fmt_with_strings(fmt, DEFAULT_SHORT_HASH_LENGTH, &RenderStrings::ENGLISH)
+    }
+}
+
+/// The words used when rendering a testament, so that they can be
+/// translated, see [`GitTestament::render_with`].
+///
+/// ```
+/// use git_testament::{CommitKind, GitModification, GitTestament, RenderStrings, EMPTY_TESTAMENT};
+///
+/// const GERMAN: RenderStrings = RenderStrings {
+///     dirty: "geändert",
+///     modifications: |count| if count == 1 { "Änderung" } else { "Änderungen" },
+///     uncommitted: "nicht eingecheckt",
+///     ..RenderStrings::ENGLISH
+/// };
+///
+/// let testament = GitTestament {
+///     commit: CommitKind::FromTag("1.0.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 0),
+///     modifications: &[GitModification::Modified(b"src/main.rs")],
+///     ..EMPTY_TESTAMENT
+/// };
+/// assert_eq!(
+///     testament.render_with(&GERMAN).to_string(),
+///     "1.0.0 (763aa159d 2019-04-02) geändert 1 Änderung"
+/// );
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct RenderStrings<'a> {
+    /// Follows the commit when the working tree had modifications
+    pub dirty: &'a str,
+    /// Follows the commit when the modifications were not collected, so the
+    /// working tree may have had some
+    pub maybe_dirty: &'a str,
+    /// The word for the given number of modifications, which is never zero
+    pub modifications: fn(usize) -> &'a str,
+    /// Marks a build from a repository with no commits
+    pub uncommitted: &'a str,
+    /// Marks a build from a shallow clone with no tags
+    pub shallow: &'a str,
+    /// Marks a build from a package made by `cargo package`
+    pub tarball: &'a str,
+    /// Stands in for the version of a commit with no tags
+    pub unknown: &'a str,
+}
+
+impl RenderStrings<'static> {
+    /// The words used by the `Display` implementation of [`GitTestament`]
+    pub const ENGLISH: RenderStrings<'static> = RenderStrings {
+        dirty: "dirty",
+        maybe_dirty: "maybe dirty",
+        modifications: |count| {
+            if count == 1 {
+                "modification"
+            } else {
+                "modifications"
+            }
+        },
+        uncommitted: "uncommitted",
+        shallow: "shallow",
+        tarball: "tarball",
+        unknown: "unknown",
+    };
+}
+
+impl Default for RenderStrings<'static> {
+    fn default() -> Self {
+        RenderStrings::ENGLISH
+    }
+}
+
+impl<'a> GitTestament<'a> {
+    /// Render the testament as its `Display` implementation would, but with
+    /// the given words, see [`RenderStrings`].
+    pub fn render_with<'r>(&'r self, strings: &'r RenderStrings<'r>) -> impl Display + 'r {
+        RenderWith {
+            testament: self,
+            strings,
+        }
+    }
+
+    /// Render the testament as its `Display` implementation would, followed
+    /// by any extra key/value pairs given with the `extra` option.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::NoRepository("1.0.0", "2019-04-02"),
+    ///     extras: &[("ci_job", "1234"), ("profile", "release")],
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// assert_eq!(
+    ///     testament.render_extended().to_string(),
+    ///     "1.0.0 (2019-04-02) [ci_job=1234, profile=release]"
+    /// );
+    /// ```
+    pub fn render_extended(&self) -> impl Display + '_ {
+        RenderExtended(self)
+    }
+
+    /// Render the testament as its `Display` implementation would into `buf`,
+    /// returning the number of bytes written.  This needs no allocator, so it
+    /// is useful on targets without the `alloc` feature.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::NoRepository("1.0.0", "2019-04-02"),
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// let mut buf = [0; 32];
+    /// let len = testament.render_into(&mut buf).unwrap();
+    /// assert_eq!(&buf[..len], b"1.0.0 (2019-04-02)");
+    /// assert_eq!(testament.render_into(&mut buf[..4]).unwrap_err().required(), 18);
+    /// ```
+    ///
+    /// If `buf` is too small then as much as fits is written, and the error
+    /// says how large it would need to be.
+    pub fn render_into(&self, buf: &mut [u8]) -> Result<usize, RenderError> {
+        use fmt::Write;
+        let mut writer = BufWriter { buf, len: 0 };
+        if write!(writer, "{self}").is_ok() {
+            return Ok(writer.len);
+        }
+        let mut counter = LengthCounter(0);
+        let _ = write!(counter, "{self}");
+        Err(RenderError {
+            required: counter.0,
+        })
+    }
+
+    fn fmt_with_strings(&self, fmt: &mut Formatter, strings: &RenderStrings) -> fmt::Result {
+        self.commit
+            .fmt_with_strings(fmt, self.short_hash_length, strings)?;
+        let count = self.modification_count();
+        if !self.modifications_collected {
+            write!(fmt, " {}", strings.maybe_dirty)?;
+        } else if count > 0 {
             write!(
                 fmt,
-                " dirty {} modification{}",
-                self.modifications.len(),
-                if self.modifications.len() > 1 {
-                    "s"
-                } else {
-                    ""
-                }
+                " {} {} {}",
+                strings.dirty,
+                count,
+                (strings.modifications)(count)
             )?;
         }
         Ok(())
     }
 }
+
+/// The error from [`GitTestament::render_into`] when the buffer is too small
+/// for the rendered testament.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderError {
+    required: usize,
+}
+
+impl RenderError {
+    /// The length of buffer, in bytes, which the testament needs
+    pub fn required(&self) -> usize {
+        self.required
+    }
+}
+
+impl Display for RenderError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(
+            fmt,
+            "buffer too small to render testament, {} bytes required",
+            self.required
+        )
+    }
+}
+
+impl core::error::Error for RenderError {}
+
+/// Writes into a fixed buffer, failing if it fills up.  Everything which fits
+/// is written, even if it splits a character.
+struct BufWriter<'b> {
+    buf: &'b mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for BufWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let space = &mut self.buf[self.len..];
+        let n = s.len().min(space.len());
+        space[..n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        if n < s.len() {
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Counts the bytes written, discarding them
+struct LengthCounter(usize);
+
+impl fmt::Write for LengthCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+struct RenderWith<'r, 'a> {
+    testament: &'r GitTestament<'a>,
+    strings: &'r RenderStrings<'r>,
+}
+
+impl Display for RenderWith<'_, '_> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        self.testament.fmt_with_strings(fmt, self.strings)
+    }
+}
+
+struct RenderExtended<'r, 'a>(&'r GitTestament<'a>);
+
+impl Display for RenderExtended<'_, '_> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        self.0.fmt(fmt)?;
+        for (i, (key, value)) in self.0.extras.iter().enumerate() {
+            let sep = if i == 0 { " [" } else { ", " };
+            write!(fmt, "{sep}{key}={value}")?;
+        }
+        if !self.0.extras.is_empty() {
+            fmt.write_str("]")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "alloc")]
+struct RenderVerbose<'r, 'a>(&'r GitTestament<'a>);
+
+#[cfg(feature = "alloc")]
+impl Display for RenderVerbose<'_, '_> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{}{}", self.0, ModificationList(self.0))
+    }
+}
+
+/// The modifications in a testament, each on its own indented line
+#[cfg(feature = "alloc")]
+struct ModificationList<'r, 'a>(&'r GitTestament<'a>);
+
+#[cfg(feature = "alloc")]
+impl Display for ModificationList<'_, '_> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let list = |fmt: &mut Formatter, modification: &GitModification| {
+            let kind = match modification.kind() {
+                ModificationKind::Added => "added",
+                ModificationKind::Removed => "removed",
+                ModificationKind::Modified => "modified",
+                ModificationKind::Renamed => "renamed",
+                ModificationKind::Copied => "copied",
+                ModificationKind::Untracked => "untracked",
+            };
+            write!(fmt, "\n    {kind}: ")?;
+            if let Some(original) = modification.original_path_bytes() {
+                write!(
+                    fmt,
+                    "{} -> ",
+                    alloc::string::String::from_utf8_lossy(original)
+                )?;
+            }
+            write!(fmt, "{}", modification.path_lossy())
+        };
+        match self.0.compressed_modifications {
+            #[cfg(feature = "compression")]
+            Some(compressed) => match compressed.decompress() {
+                Some(table) => table.iter().try_for_each(|m| list(fmt, &m)),
+                None => Ok(()),
+            },
+            #[cfg(not(feature = "compression"))]
+            Some(_) => Ok(()),
+            None => self.0.modifications.iter().try_for_each(|m| list(fmt, m)),
+        }
+    }
+}
+
+/// The testament is displayed compactly, as in `1.0.0+3 (763aa159d
+/// 2019-04-02) dirty 2 modifications`.  The alternate form, `{:#}`, follows
+/// this with the branch, the full commit hash, the tag and the distance from
+/// it, and then each modification to the working tree, each on its own
+/// indented line.
+///
+/// ```
+/// use git_testament::{CommitKind, GitModification, GitTestament, EMPTY_TESTAMENT};
+///
+/// let testament = GitTestament {
+///     commit: CommitKind::FromTag("1.0.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 3),
+///     modifications: &[GitModification::Modified(b"src/main.rs")],
+///     branch_name: Some("main"),
+///     ..EMPTY_TESTAMENT
+/// };
+/// assert_eq!(
+///     format!("{testament:#}"),
+///     concat!(
+///         "1.0.0+3 (763aa159d 2019-04-02) dirty 1 modification\n",
+///         "    branch: main\n",
+///         "    commit: 763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3\n",
+///         "    tag: 1.0.0\n",
+///         "    distance: 3\n",
+///         "    modified: src/main.rs",
+///     )
+/// );
+/// ```
+///
+/// The modifications are only listed if the `alloc` feature is enabled, and
+/// if they were compressed, the `compression` feature.
+impl<'a> Display for GitTestament<'a> {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        self.fmt_with_strings(fmt, &RenderStrings::ENGLISH)?;
+        if fmt.alternate() {
+            if let Some(branch) = self.branch_name {
+                write!(fmt, "\n    branch: {branch}")?;
+            }
+            if let Some(hash) = self.commit_hash() {
+                write!(fmt, "\n    commit: {hash}")?;
+            }
+            if let Some(tag) = self.tag() {
+                write!(fmt, "\n    tag: {tag}")?;
+            }
+            if let Some(distance) = self.tag_distance() {
+                write!(fmt, "\n    distance: {distance}")?;
+            }
+            #[cfg(feature = "alloc")]
+            write!(fmt, "{}", ModificationList(self))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,195 @@
+//! Parsing rendered testaments back into their parts
+//!
+//! Logs and bug reports often only carry the rendered testament, as produced
+//! by the `Display` implementation of [`GitTestament`] or by
+//! [`render_testament!`](crate::render_testament), so this recovers what it
+//! can from that.  Only the English rendering can be parsed, see
+//! [`RenderStrings::ENGLISH`](crate::RenderStrings::ENGLISH).
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use crate::GitTestament;
+
+/// The kind of commit a rendered testament records, which mirrors
+/// [`CommitKind`](crate::CommitKind) as far as the rendering allows.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ParsedKind {
+    /// No repository was present, e.g. `1.0.0 (2019-04-02)`
+    NoRepository,
+    /// The repository had no commits, e.g. `1.0.0 (uncommitted 2019-04-02)`
+    NoCommit,
+    /// The repository had no commits but the remote default branch was
+    /// recorded, e.g. `1.0.0 (uncommitted, origin/main 763aa159d 2019-04-02)`
+    FromRemote(String),
+    /// A shallow clone with no tags, e.g. `1.0.0 (shallow 763aa159d 2019-04-02)`
+    Shallow,
+    /// A package made by `cargo package`, e.g.
+    /// `1.0.0 (tarball 763aa159d 2019-04-02)`
+    FromTarball,
+    /// A commit, rendered with its tag (as with
+    /// [`CommitKind::FromTag`](crate::CommitKind::FromTag)), the crate's
+    /// version for a trusted branch, or `unknown` if there were no tags.
+    /// These cannot be told apart once rendered.
+    Commit,
+    /// A commit before the tag which contains it, e.g.
+    /// `1.2.0~3 (763aa159d 2019-04-02)`, as with
+    /// [`CommitKind::BeforeTag`](crate::CommitKind::BeforeTag)
+    BeforeTag,
+}
+
+/// The parts of a rendered testament, see [`GitTestament::parse_rendered`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParsedTestament {
+    /// The crate's version, if [`render_testament!`](crate::render_testament)
+    /// prefixed it (as in `1.1.0 :: 1.0.0+3 (...)`) because it differed from
+    /// the tag
+    pub crate_version: Option<String>,
+    /// What was rendered before the parentheses, without any distance.  This
+    /// is the tag, the crate's version, or `unknown`, depending on the kind.
+    pub version: String,
+    /// The number of commits since the tag, or before it for
+    /// [`ParsedKind::BeforeTag`], zero if none was rendered
+    pub distance: usize,
+    pub kind: ParsedKind,
+    /// The (possibly abbreviated) commit hash, if one was rendered
+    pub hash: Option<String>,
+    /// The date of the commit, or the build date if there was no commit or
+    /// the testament came from a package
+    pub date: String,
+    /// The number of modifications, zero if the working tree was clean
+    pub modifications: usize,
+    /// Whether the modifications were collected, false if the testament was
+    /// rendered as `maybe dirty`
+    pub modifications_collected: bool,
+}
+
+/// The error from [`GitTestament::parse_rendered`] when the string is not a
+/// rendered testament.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    reason: &'static str,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "not a rendered testament: {}", self.reason)
+    }
+}
+
+impl core::error::Error for ParseError {}
+
+fn error<T>(reason: &'static str) -> Result<T, ParseError> {
+    Err(ParseError { reason })
+}
+
+impl<'a> GitTestament<'a> {
+    /// Parse a testament rendered by its `Display` implementation or by
+    /// [`render_testament!`](crate::render_testament), for example
+    /// `1.1.0 :: 1.0.0+14 (651af89ed 2019-04-02) dirty 4 modifications`.
+    ///
+    /// Leading and trailing whitespace is ignored, but nothing else may
+    /// surround the testament.
+    ///
+    /// ```
+    /// use git_testament::{GitTestament, ParsedKind};
+    ///
+    /// let parsed = GitTestament::parse_rendered("1.0.0+14 (651af89ed 2019-04-02) dirty 4 modifications")
+    ///     .unwrap();
+    /// assert_eq!(parsed.version, "1.0.0");
+    /// assert_eq!(parsed.distance, 14);
+    /// assert_eq!(parsed.kind, ParsedKind::Commit);
+    /// assert_eq!(parsed.hash.as_deref(), Some("651af89ed"));
+    /// assert_eq!(parsed.modifications, 4);
+    /// ```
+    pub fn parse_rendered(rendered: &str) -> Result<ParsedTestament, ParseError> {
+        let rendered = rendered.trim();
+        let (crate_version, rendered) = match rendered.split_once(" :: ") {
+            Some((crate_version, rest)) => (Some(crate_version.to_owned()), rest),
+            None => (None, rendered),
+        };
+        let (version, rest) = match rendered.split_once(" (") {
+            Some(parts) => parts,
+            None => return error("no parenthesised commit"),
+        };
+        let (inner, suffix) = match rest.split_once(')') {
+            Some(parts) => parts,
+            None => return error("unterminated parentheses"),
+        };
+        if version.is_empty() || version.contains(' ') {
+            return error("invalid version");
+        }
+
+        let words: Vec<&str> = inner.split(' ').collect();
+        let (mut kind, hash, date) = match words[..] {
+            [date] => (ParsedKind::NoRepository, None, date),
+            ["uncommitted", date] => (ParsedKind::NoCommit, None, date),
+            ["uncommitted,", remote, hash, date] => {
+                (ParsedKind::FromRemote(remote.to_owned()), Some(hash), date)
+            }
+            ["shallow", hash, date] => (ParsedKind::Shallow, Some(hash), date),
+            ["tarball", hash, date] => (ParsedKind::FromTarball, Some(hash), date),
+            [hash, date] => (ParsedKind::Commit, Some(hash), date),
+            _ => return error("unrecognised commit"),
+        };
+        if date.is_empty() {
+            return error("missing date");
+        }
+        if let Some(hash) = hash {
+            if hash.is_empty() || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return error("invalid commit hash");
+            }
+        }
+
+        // Only a tag is followed by its distance, and tags may themselves
+        // contain `+`, so anything which is not a number is part of the tag.
+        // Tags cannot contain `~`, so that always precedes a distance.
+        let (version, distance) = match (&kind, version.rsplit_once('+')) {
+            (ParsedKind::Commit, _) if version.contains('~') => {
+                match version.split_once('~').map(|(tag, d)| (tag, d.parse())) {
+                    Some((tag, Ok(distance))) if !tag.is_empty() => {
+                        kind = ParsedKind::BeforeTag;
+                        (tag, distance)
+                    }
+                    _ => return error("invalid distance before the tag"),
+                }
+            }
+            (ParsedKind::Commit, Some((tag, distance))) => match distance.parse() {
+                Ok(distance) => (tag, distance),
+                Err(_) => (version, 0),
+            },
+            _ => (version, 0),
+        };
+
+        let modifications_collected = suffix != " maybe dirty";
+        let modifications = match suffix {
+            "" | " maybe dirty" => 0,
+            suffix => {
+                let count = suffix
+                    .strip_prefix(" dirty ")
+                    .and_then(|dirty| dirty.split_once(' '));
+                match count {
+                    Some((count, "modification" | "modifications")) => match count.parse() {
+                        Ok(count) if count > 0 => count,
+                        _ => return error("invalid modification count"),
+                    },
+                    _ => return error("unrecognised text after the commit"),
+                }
+            }
+        };
+
+        Ok(ParsedTestament {
+            crate_version,
+            version: version.to_owned(),
+            distance,
+            kind,
+            hash: hash.map(str::to_owned),
+            date: date.to_owned(),
+            modifications,
+            modifications_collected,
+        })
+    }
+}
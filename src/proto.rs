@@ -0,0 +1,176 @@
+//! Protocol buffer messages for testaments
+//!
+//! Services which report their build from a gRPC health or information RPC
+//! can return a [`Testament`], converted from a [`GitTestament`], rather than
+//! each defining a message of their own.  The schema is shipped with the
+//! crate as `proto/git_testament.proto`, and is also available as [`PROTO`]
+//! for build scripts which compile it for other languages or include it in
+//! their own services' schemas.
+//!
+//! The messages are [`prost`](https://docs.rs/prost) 0.14 messages, so they
+//! can be used directly with `tonic`.
+//!
+//! ```
+//! use git_testament::{git_testament, proto};
+//! use prost::Message;
+//!
+//! git_testament!(TESTAMENT);
+//!
+//! # fn main() {
+//! let message = proto::Testament::from(&TESTAMENT);
+//! let encoded = message.encode_to_vec();
+//! assert_eq!(proto::Testament::decode(&*encoded).unwrap(), message);
+//! # }
+//! ```
+
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{CommitKind as GitCommitKind, GitModification, GitTestament};
+
+/// The schema of the messages in this module, as shipped in
+/// `proto/git_testament.proto`
+pub const PROTO: &str = include_str!("../proto/git_testament.proto");
+
+/// The testament of a build, see the [module documentation](crate::proto).
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Testament {
+    /// What kind of commit the build came from
+    #[prost(enumeration = "CommitKind", tag = "1")]
+    pub kind: i32,
+    /// The full commit hash, if there was a commit
+    #[prost(string, optional, tag = "2")]
+    pub commit: Option<String>,
+    /// The tag, if the commit was described by one
+    #[prost(string, optional, tag = "3")]
+    pub tag: Option<String>,
+    /// The number of commits since the tag
+    #[prost(uint64, optional, tag = "4")]
+    pub distance: Option<u64>,
+    /// The commit date, or the build date if there was no commit
+    #[prost(string, optional, tag = "5")]
+    pub date: Option<String>,
+    /// The branch, if one was checked out
+    #[prost(string, optional, tag = "6")]
+    pub branch: Option<String>,
+    /// Whether there were any modifications to the working tree
+    #[prost(bool, tag = "7")]
+    pub dirty: bool,
+    /// The modifications to the working tree.  If the modifications were
+    /// compressed and the `compression` feature is not enabled, then this is
+    /// empty even if `dirty` is `true`.
+    #[prost(message, repeated, tag = "8")]
+    pub modifications: Vec<Modification>,
+    /// The testament as it would be displayed
+    #[prost(string, tag = "9")]
+    pub rendered: String,
+}
+
+/// The kind of commit a build came from, mirroring
+/// [`CommitKind`](crate::CommitKind)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum CommitKind {
+    Unspecified = 0,
+    NoRepository = 1,
+    NoCommit = 2,
+    NoTags = 3,
+    Shallow = 4,
+    FromTag = 5,
+    FromTrustedBranch = 6,
+    FromRemote = 7,
+    FromTarball = 8,
+    BeforeTag = 9,
+}
+
+/// A modification to the working tree, mirroring [`GitModification`]
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Modification {
+    #[prost(enumeration = "modification::Kind", tag = "1")]
+    pub kind: i32,
+    /// The path, converted lossily if it was not UTF-8
+    #[prost(string, tag = "2")]
+    pub path: String,
+    /// The path a renamed or copied file came from
+    #[prost(string, optional, tag = "3")]
+    pub original: Option<String>,
+}
+
+/// The nested types of [`Modification`]
+pub mod modification {
+    /// The kind of a [`Modification`](super::Modification)
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum Kind {
+        Unspecified = 0,
+        Added = 1,
+        Removed = 2,
+        Modified = 3,
+        Renamed = 4,
+        Copied = 5,
+        Untracked = 6,
+    }
+}
+
+impl From<&GitModification<'_>> for Modification {
+    fn from(modification: &GitModification<'_>) -> Self {
+        let kind = match modification {
+            GitModification::Added(_) => modification::Kind::Added,
+            GitModification::Removed(_) => modification::Kind::Removed,
+            GitModification::Modified(_) => modification::Kind::Modified,
+            GitModification::Renamed(..) => modification::Kind::Renamed,
+            GitModification::Copied(..) => modification::Kind::Copied,
+            GitModification::Untracked(_) => modification::Kind::Untracked,
+        };
+        Modification {
+            kind: kind as i32,
+            path: modification.path_lossy().into_owned(),
+            original: modification
+                .original_path_bytes()
+                .map(|original| String::from_utf8_lossy(original).into_owned()),
+        }
+    }
+}
+
+impl From<&GitTestament<'_>> for Testament {
+    fn from(testament: &GitTestament<'_>) -> Self {
+        let kind = match testament.commit {
+            GitCommitKind::NoRepository(..) => CommitKind::NoRepository,
+            GitCommitKind::NoCommit(..) => CommitKind::NoCommit,
+            GitCommitKind::NoTags(..) => CommitKind::NoTags,
+            GitCommitKind::Shallow(..) => CommitKind::Shallow,
+            GitCommitKind::FromTag(..) => CommitKind::FromTag,
+            GitCommitKind::FromTrustedBranch(..) => CommitKind::FromTrustedBranch,
+            GitCommitKind::FromRemote(..) => CommitKind::FromRemote,
+            GitCommitKind::FromTarball(..) => CommitKind::FromTarball,
+            GitCommitKind::BeforeTag(..) => CommitKind::BeforeTag,
+        };
+        let mut modifications = Vec::new();
+        match testament.compressed_modifications {
+            #[cfg(feature = "compression")]
+            Some(compressed) => {
+                if let Some(table) = compressed.decompress() {
+                    modifications.extend(table.iter().map(|m| Modification::from(&m)));
+                }
+            }
+            #[cfg(not(feature = "compression"))]
+            Some(_) => {}
+            None => modifications.extend(testament.modifications.iter().map(Modification::from)),
+        }
+        Testament {
+            kind: kind as i32,
+            commit: testament.commit_hash().map(str::to_owned),
+            tag: testament.tag().map(str::to_owned),
+            distance: testament.tag_distance().map(|distance| distance as u64),
+            date: testament
+                .commit_date()
+                .or_else(|| testament.build_date())
+                .map(str::to_owned),
+            branch: testament.branch_name.map(str::to_owned),
+            dirty: testament.is_dirty(),
+            modifications,
+            rendered: testament.to_string(),
+        }
+    }
+}
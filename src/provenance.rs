@@ -0,0 +1,81 @@
+//! Where each field of a testament came from
+//!
+//! With the `provenance` feature, every [`GitTestament`] carries a
+//! [`Provenance`] alongside its data, so that consumers can decide how far to
+//! trust each part of it.  For example, a commit hash which came from the
+//! repository is more trustworthy than an extra value taken from the build
+//! environment.
+//!
+//! [`GitTestament`]: crate::GitTestament
+
+/// Where a single field of a testament came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum FieldSource {
+    /// Nothing was recorded, so the field holds its default value
+    Default,
+    /// The repository the crate was built from, whichever tool it was read
+    /// with
+    Git,
+    /// An environment variable at build time, such as `SOURCE_DATE_EPOCH`
+    Environment,
+    /// A value given literally when invoking the macro
+    Invocation,
+    /// The build itself, such as the crate's version or today's date
+    Build,
+}
+
+/// Where each field of a testament came from.
+///
+/// Fields which are always determined by the others, such as the
+/// `compressed_modifications` and `modifications` of a testament, share a
+/// single entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Provenance {
+    /// The `commit`, and so the version, hash and dates reported
+    pub commit: FieldSource,
+    /// The `modifications` (or `compressed_modifications`) and
+    /// `lfs_incomplete` fields
+    pub modifications: FieldSource,
+    /// The `branch_name` and `detached` fields
+    pub branch_name: FieldSource,
+    pub release: FieldSource,
+    /// The `tag_message` and `tag_date` fields
+    pub tag_message: FieldSource,
+    /// The `extras`, or [`FieldSource::Environment`] if any of them came from
+    /// the environment
+    pub extras: FieldSource,
+    pub parents: FieldSource,
+    pub tags_at_head: FieldSource,
+    pub shallow: FieldSource,
+    /// The `worktree` and `bare` fields
+    pub worktree: FieldSource,
+    pub remote_url: FieldSource,
+    /// The `signed`, `signer`, `signed_tag` and `tag_signer` fields
+    pub signature: FieldSource,
+    pub submodules: FieldSource,
+    pub build_info: FieldSource,
+    pub ci: FieldSource,
+}
+
+impl Provenance {
+    /// The provenance of [`EMPTY_TESTAMENT`](crate::EMPTY_TESTAMENT), where
+    /// every field is a default.
+    pub const DEFAULT: Provenance = Provenance {
+        commit: FieldSource::Default,
+        modifications: FieldSource::Default,
+        branch_name: FieldSource::Default,
+        release: FieldSource::Default,
+        tag_message: FieldSource::Default,
+        extras: FieldSource::Default,
+        parents: FieldSource::Default,
+        tags_at_head: FieldSource::Default,
+        shallow: FieldSource::Default,
+        worktree: FieldSource::Default,
+        remote_url: FieldSource::Default,
+        signature: FieldSource::Default,
+        submodules: FieldSource::Default,
+        build_info: FieldSource::Default,
+        ci: FieldSource::Default,
+    };
+}
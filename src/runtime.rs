@@ -0,0 +1,282 @@
+//! Capturing testaments at runtime
+//!
+//! This uses the same repository probing logic as the derive macro, but
+//! operates on an arbitrary path when the program is run rather than on the
+//! crate being compiled.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+use std::path::Path;
+
+use git_testament_core::{AcquireOptions, GitInformation, StatusFlag, Vcs};
+
+use crate::{CommitKind, GitModification, GitTestament, TestamentSource, EMPTY_TESTAMENT};
+
+/// The error returned when a testament cannot be captured at runtime
+pub type CaptureError = git_testament_core::Error;
+
+/// A testament captured at runtime by [`GitTestament::capture`].
+///
+/// Since a [`GitTestament`] borrows all of its data, this owns the captured
+/// information and can lend it out as a [`GitTestament`]:
+///
+/// ```no_run
+/// use git_testament::GitTestament;
+///
+/// # fn main() -> Result<(), git_testament::CaptureError> {
+/// let owned = GitTestament::capture("plugins/example".as_ref())?;
+/// let modifications = owned.modifications();
+/// let testament = owned.as_testament(&modifications);
+/// println!("Plugin version: {testament}");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OwnedGitTestament {
+    info: GitInformation,
+    captured: String,
+    rendered: String,
+}
+
+impl<'a> GitTestament<'a> {
+    /// Capture a testament for the git repository containing `path`.
+    ///
+    /// This fails if `path` is not inside a git repository, or if `git` could
+    /// not be run.  If the repository has no commits, then the testament will
+    /// record an unknown version and the date of the capture.
+    pub fn capture(path: &Path) -> Result<OwnedGitTestament, CaptureError> {
+        let info = GitInformation::acquire(path, &AcquireOptions::default())?;
+        Ok(OwnedGitTestament::new(info, git_testament_core::today()))
+    }
+
+    /// Capture testaments for several crates in one workspace at once.
+    ///
+    /// The repository containing `root` is only probed once, and then each
+    /// of the `members` (relative to `root`) gets a testament which shares the
+    /// commit information but only records the modifications within that
+    /// member.  The testaments are returned in the same order as `members`.
+    pub fn capture_workspace<P>(
+        root: &Path,
+        members: &[P],
+    ) -> Result<Vec<OwnedGitTestament>, CaptureError>
+    where
+        P: AsRef<Path>,
+    {
+        let captured = git_testament_core::today();
+        Ok(
+            GitInformation::acquire_workspace(root, members, &AcquireOptions::default())?
+                .into_iter()
+                .map(|info| OwnedGitTestament::new(info, captured.clone()))
+                .collect(),
+        )
+    }
+
+    /// Capture a testament for the git repository containing `path`, as
+    /// [`GitTestament::capture`] does, without blocking the async runtime.
+    ///
+    /// Capturing runs several git commands one after another, so this runs
+    /// them on tokio's pool of blocking threads, leaving the runtime's worker
+    /// threads free.  Many testaments can then be captured concurrently, as
+    /// with `futures::future::join_all`.
+    ///
+    /// This must be called from within a tokio runtime.
+    #[cfg(feature = "tokio")]
+    pub async fn capture_async(path: &Path) -> Result<OwnedGitTestament, CaptureError> {
+        let path = path.to_path_buf();
+        join_blocking(tokio::task::spawn_blocking(move || Self::capture(&path))).await
+    }
+
+    /// Capture testaments for several crates in one workspace at once, as
+    /// [`GitTestament::capture_workspace`] does, without blocking the async
+    /// runtime.  See [`GitTestament::capture_async`].
+    #[cfg(feature = "tokio")]
+    pub async fn capture_workspace_async<P>(
+        root: &Path,
+        members: &[P],
+    ) -> Result<Vec<OwnedGitTestament>, CaptureError>
+    where
+        P: AsRef<Path>,
+    {
+        let root = root.to_path_buf();
+        let members: Vec<_> = members.iter().map(|m| m.as_ref().to_path_buf()).collect();
+        join_blocking(tokio::task::spawn_blocking(move || {
+            Self::capture_workspace(&root, &members)
+        }))
+        .await
+    }
+}
+
+/// Wait for a capture on the blocking pool, passing on any panic from it
+#[cfg(feature = "tokio")]
+async fn join_blocking<T>(
+    handle: tokio::task::JoinHandle<Result<T, CaptureError>>,
+) -> Result<T, CaptureError> {
+    match handle.await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(_) => Err(CaptureError::Io(std::io::Error::new(
+            std::io::ErrorKind::Interrupted,
+            "the runtime shut down during the capture",
+        ))),
+    }
+}
+
+impl OwnedGitTestament {
+    fn new(info: GitInformation, captured: String) -> Self {
+        let mut owned = OwnedGitTestament {
+            info,
+            captured,
+            rendered: String::new(),
+        };
+        owned.rendered = owned.to_string();
+        owned
+    }
+
+    /// The modifications to the working tree, suitable for passing to
+    /// [`OwnedGitTestament::as_testament`].
+    pub fn modifications(&self) -> Vec<GitModification<'_>> {
+        self.info
+            .status
+            .iter()
+            .map(|entry| {
+                let path = &entry.path[..];
+                match entry.status {
+                    StatusFlag::Added => GitModification::Added(path),
+                    StatusFlag::Deleted => GitModification::Removed(path),
+                    StatusFlag::Modified => GitModification::Modified(path),
+                    StatusFlag::Renamed => GitModification::Renamed(
+                        entry.original_path.as_deref().unwrap_or_default(),
+                        path,
+                    ),
+                    StatusFlag::Copied => GitModification::Copied(
+                        entry.original_path.as_deref().unwrap_or_default(),
+                        path,
+                    ),
+                    StatusFlag::Untracked => GitModification::Untracked(path),
+                }
+            })
+            .collect()
+    }
+
+    /// Borrow the captured information as a [`GitTestament`].
+    ///
+    /// The modifications should be those returned by
+    /// [`OwnedGitTestament::modifications`].
+    pub fn as_testament<'a>(
+        &'a self,
+        modifications: &'a [GitModification<'a>],
+    ) -> GitTestament<'a> {
+        let commit = match &self.info.commitinfo {
+            None => match (&self.info.remote, &self.info.cargo_vcs_info) {
+                (Some(remote), _) => {
+                    CommitKind::FromRemote("unknown", &remote.name, &remote.id, &remote.date)
+                }
+                (None, Some(vcs_info)) => {
+                    CommitKind::FromTarball(&vcs_info.sha1, "unknown", &self.captured)
+                }
+                (None, None) => CommitKind::NoCommit("unknown", &self.captured),
+            },
+            Some(ci) if ci.tag.is_empty() && self.info.shallow => {
+                CommitKind::Shallow("unknown", &ci.id, &ci.date)
+            }
+            Some(ci) if ci.tag.is_empty() => CommitKind::NoTags(&ci.id, &ci.date),
+            Some(ci) => CommitKind::FromTag(&ci.tag, &ci.id, &ci.date, ci.distance),
+        };
+        GitTestament {
+            commit,
+            modifications,
+            branch_name: self.info.branch.as_deref(),
+            shallow: self.info.shallow,
+            worktree: self.info.worktree,
+            bare: self.info.bare,
+            detached: self.info.detached,
+            source: match (self.info.vcs, &self.info.cargo_vcs_info) {
+                (Vcs::RevisionFile, _) => TestamentSource::RevisionFile,
+                (_, Some(_)) => TestamentSource::CargoVcsInfo,
+                (_, None) => TestamentSource::Git,
+            },
+            remote_url: self.info.remote_url.as_deref(),
+            git_version: self.info.git_version.as_deref(),
+            describe_hash_length: self
+                .info
+                .commitinfo
+                .as_ref()
+                .map_or(EMPTY_TESTAMENT.describe_hash_length, |ci| ci.abbrev),
+            signed: self.info.commitinfo.as_ref().is_some_and(|ci| ci.signed),
+            signer: self
+                .info
+                .commitinfo
+                .as_ref()
+                .and_then(|ci| ci.signer.as_deref()),
+            signed_tag: self
+                .info
+                .commitinfo
+                .as_ref()
+                .and_then(|ci| Some(ci.signed_tag.as_ref()?.0.as_str())),
+            tag_signer: self
+                .info
+                .commitinfo
+                .as_ref()
+                .and_then(|ci| ci.signed_tag.as_ref()?.1.as_deref()),
+            rendered: &self.rendered,
+            commit_timestamp: self
+                .info
+                .commitinfo
+                .as_ref()
+                .map(|ci| ci.timestamp.as_str()),
+            describe: self
+                .info
+                .commitinfo
+                .as_ref()
+                .and_then(|ci| ci.describe.as_deref()),
+            tag_message: self
+                .info
+                .commitinfo
+                .as_ref()
+                .and_then(|ci| Some(ci.tag_message.as_ref()?.0.as_str()))
+                .filter(|message| !message.is_empty()),
+            tag_date: self
+                .info
+                .commitinfo
+                .as_ref()
+                .and_then(|ci| Some(ci.tag_message.as_ref()?.1.as_str())),
+            commit_count: self.info.commitinfo.as_ref().map(|ci| ci.commit_count),
+            is_merge: self.info.commitinfo.as_ref().is_some_and(|ci| ci.merge),
+            worktree_digest: self.info.worktree_digest.as_deref(),
+            diff: self.info.diff.as_deref(),
+            lfs_incomplete: self.info.lfs_incomplete,
+            modifications_collected: self.info.status_collected,
+            overridden: !self.info.overridden.is_empty(),
+            #[cfg(feature = "provenance")]
+            provenance: self.provenance(),
+            ..EMPTY_TESTAMENT
+        }
+    }
+}
+
+#[cfg(feature = "provenance")]
+impl OwnedGitTestament {
+    fn provenance(&self) -> crate::Provenance {
+        use crate::{FieldSource, Provenance};
+        let commit = match (&self.info.commitinfo, &self.info.remote) {
+            (None, None) if self.info.cargo_vcs_info.is_none() => FieldSource::Build,
+            _ => FieldSource::Git,
+        };
+        Provenance {
+            commit,
+            modifications: FieldSource::Git,
+            branch_name: FieldSource::Git,
+            shallow: FieldSource::Git,
+            worktree: FieldSource::Git,
+            ..Provenance::DEFAULT
+        }
+    }
+}
+
+impl Display for OwnedGitTestament {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        let modifications = self.modifications();
+        self.as_testament(&modifications).fmt(fmt)
+    }
+}
@@ -0,0 +1,109 @@
+//! Rendering testaments as SLSA provenance
+//!
+//! This produces an [in-toto statement] whose predicate is [SLSA provenance
+//! v1], naming the commit the testament records as the source of the build.
+//! The statement is written by the build itself, rather than by a trusted
+//! build platform, so it is only as trustworthy as the build which made it.
+//! It is intended to be signed and attached to release artifacts by tooling
+//! such as `cosign attest`.
+//!
+//! [in-toto statement]: https://github.com/in-toto/attestation/blob/main/spec/v1/statement.md
+//! [SLSA provenance v1]: https://slsa.dev/spec/v1.0/provenance
+
+use alloc::string::String;
+
+use crate::json::{push_optional, push_string};
+use crate::GitTestament;
+
+/// The type of the statement, see the in-toto specification
+const STATEMENT_TYPE: &str = "https://in-toto.io/Statement/v1";
+/// The type of the predicate, see the SLSA specification
+const PREDICATE_TYPE: &str = "https://slsa.dev/provenance/v1";
+/// The build type, which describes the parameters recorded below
+const BUILD_TYPE: &str = "https://github.com/kinnison/git-testament/slsa/cargo/v1";
+/// The builder, which is the build itself as far as the testament knows
+const BUILDER_ID: &str = "https://github.com/kinnison/git-testament";
+
+impl<'a> GitTestament<'a> {
+    /// Render this testament as an in-toto statement of SLSA v1 provenance
+    /// for the artifact with the given digest, as a single line of JSON.
+    ///
+    /// The digest is written `algorithm:hex`, as in `sha256:abc123...`, and is
+    /// taken to be a SHA-256 digest if it has no algorithm.  The commit, if
+    /// any, is the only resolved dependency, with the remote's URL if the
+    /// `remote` option was given.  The branch, tag and whether the working
+    /// tree was dirty are recorded as external parameters, and the target
+    /// and toolchain as internal parameters if the `build_info` option was
+    /// given.  The CI job which built the artifact, if the `ci` option was
+    /// given, is recorded as the invocation.
+    ///
+    /// ```
+    /// use git_testament::{CommitKind, GitTestament, EMPTY_TESTAMENT};
+    ///
+    /// let testament = GitTestament {
+    ///     commit: CommitKind::FromTag("v1.2.0", "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3", "2019-04-02", 0),
+    ///     remote_url: Some("https://example.com/app.git"),
+    ///     ..EMPTY_TESTAMENT
+    /// };
+    /// let statement = testament.to_slsa_provenance("sha256:5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03");
+    /// assert!(statement.contains(r#""digest":{"gitCommit":"763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3"}"#));
+    /// ```
+    pub fn to_slsa_provenance(&self, subject_digest: &str) -> String {
+        let (algorithm, digest) = subject_digest
+            .split_once(':')
+            .unwrap_or(("sha256", subject_digest));
+        let mut out = String::from("{\"_type\":");
+        push_string(&mut out, STATEMENT_TYPE);
+        out.push_str(",\"subject\":[{\"digest\":{");
+        push_string(&mut out, algorithm);
+        out.push(':');
+        push_string(&mut out, digest);
+        out.push_str("}}],\"predicateType\":");
+        push_string(&mut out, PREDICATE_TYPE);
+
+        out.push_str(",\"predicate\":{\"buildDefinition\":{\"buildType\":");
+        push_string(&mut out, BUILD_TYPE);
+        out.push_str(",\"externalParameters\":{\"branch\":");
+        push_optional(&mut out, self.branch_name);
+        out.push_str(",\"tag\":");
+        push_optional(&mut out, self.tag());
+        out.push_str(",\"dirty\":");
+        out.push_str(if self.is_dirty() { "true" } else { "false" });
+        out.push('}');
+        if let Some(info) = self.build_info {
+            out.push_str(",\"internalParameters\":{\"target\":");
+            push_optional(&mut out, info.target);
+            out.push_str(",\"profile\":");
+            push_optional(&mut out, info.profile);
+            out.push_str(",\"optLevel\":");
+            push_optional(&mut out, info.opt_level);
+            out.push_str(",\"rustc\":");
+            push_optional(&mut out, info.rustc);
+            out.push('}');
+        }
+        out.push_str(",\"resolvedDependencies\":[");
+        if let Some(commit) = self.commit_hash() {
+            out.push('{');
+            if let Some(url) = self.remote_url {
+                out.push_str("\"uri\":");
+                push_string(&mut out, &alloc::format!("git+{url}"));
+                out.push(',');
+            }
+            out.push_str("\"digest\":{\"gitCommit\":");
+            push_string(&mut out, commit);
+            out.push_str("}}");
+        }
+        out.push_str("]}");
+
+        out.push_str(",\"runDetails\":{\"builder\":{\"id\":");
+        push_string(&mut out, BUILDER_ID);
+        out.push('}');
+        if let Some(job_url) = self.ci.and_then(|ci| ci.job_url) {
+            out.push_str(",\"metadata\":{\"invocationId\":");
+            push_string(&mut out, job_url);
+            out.push('}');
+        }
+        out.push_str("}}}");
+        out
+    }
+}
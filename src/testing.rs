@@ -0,0 +1,165 @@
+//! Helpers for checking testaments in integration tests
+//!
+//! These let a crate verify, end to end, that a binary it has built reports
+//! a testament which matches the state of the repository it was built from,
+//! for example that its `--version` output is wired up correctly:
+//!
+//! ```no_run
+//! // In an integration test, use env!("CARGO_BIN_EXE_my-program") instead
+//! git_testament::testing::assert_binary_testament(
+//!     "target/debug/my-program".as_ref(),
+//!     &["--version"],
+//!     ".".as_ref(),
+//! );
+//! ```
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use std::path::Path;
+use std::process::Command;
+
+use crate::{CommitKind, GitTestament};
+
+/// The parts of a testament as rendered by [`render_testament!`], for
+/// example `1.0.0+14 (651af89ed 2019-04-02) dirty 4 modifications`.
+///
+/// [`render_testament!`]: crate::render_testament
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedTestament {
+    /// The tag, or the crate's version if the branch was trusted
+    pub version: String,
+    /// The number of commits since the tag
+    pub distance: usize,
+    /// The (possibly abbreviated) commit hash
+    pub hash: String,
+    /// The date of the commit
+    pub date: String,
+    /// The number of modifications, zero if the tree was clean
+    pub modifications: usize,
+}
+
+impl RenderedTestament {
+    /// Find a rendered testament within `line`.
+    ///
+    /// Any text before the version, such as the name of a program, is
+    /// skipped.  If the crate's version was prepended to the tag (as in
+    /// `1.1.0 :: 1.0.0+3 (...)`) then the tag is used.  Only testaments which
+    /// record a commit can be parsed.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        let line = line.rsplit_once(" :: ").map_or(line, |(_, basis)| basis);
+        let (before, rest) = line.split_once(" (")?;
+        let version = before.rsplit(' ').next()?;
+        let (inner, after) = rest.split_once(')')?;
+
+        let (version, distance) = match version.rsplit_once('+') {
+            Some((tag, distance)) => match distance.parse() {
+                Ok(distance) => (tag, distance),
+                Err(_) => (version, 0),
+            },
+            None => (version, 0),
+        };
+
+        let inner = inner.strip_prefix("shallow ").unwrap_or(inner);
+        let (hash, date) = inner.split_once(' ')?;
+        if hash.is_empty() || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        let valid_date = date.len() == 10
+            && date.bytes().enumerate().all(|(i, b)| {
+                if i == 4 || i == 7 {
+                    b == b'-'
+                } else {
+                    b.is_ascii_digit()
+                }
+            });
+        if !valid_date {
+            return None;
+        }
+
+        let modifications = match after.trim() {
+            "" | "maybe dirty" => 0,
+            dirty => dirty
+                .strip_prefix("dirty ")?
+                .split(' ')
+                .next()?
+                .parse()
+                .ok()?,
+        };
+
+        Some(Self {
+            version: version.to_owned(),
+            distance,
+            hash: hash.to_owned(),
+            date: date.to_owned(),
+            modifications,
+        })
+    }
+
+    /// Check that this rendering agrees with `testament`, returning a
+    /// description of the first difference found.
+    pub fn check(&self, testament: &GitTestament) -> Result<(), String> {
+        let commit = testament
+            .commit_hash()
+            .ok_or_else(|| format!("The testament has no commit, but {self:?} was rendered"))?;
+        if !commit.starts_with(&self.hash) {
+            return Err(format!(
+                "Rendered commit {} is not the expected {commit}",
+                self.hash
+            ));
+        }
+        if testament.commit_date() != Some(&self.date) {
+            return Err(format!(
+                "Rendered date {} is not the expected {}",
+                self.date,
+                testament.commit_date().unwrap_or_default()
+            ));
+        }
+        if let CommitKind::FromTag(tag, _, _, distance) = testament.commit {
+            if tag == self.version && distance != self.distance {
+                return Err(format!(
+                    "Rendered distance {} from {tag} is not the expected {distance}",
+                    self.distance
+                ));
+            }
+        }
+        if testament.modification_count() != self.modifications {
+            return Err(format!(
+                "Rendered {} modifications but expected {}",
+                self.modifications,
+                testament.modification_count()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Run `binary` with `args` and assert that the first testament in its
+/// output matches the current state of the repository containing `repo`.
+///
+/// The binary must have been built from the repository in its current state,
+/// so this is best used with `CARGO_BIN_EXE_<name>` in an integration test.
+///
+/// # Panics
+///
+/// If the binary cannot be run, no testament is found in its output, the
+/// repository cannot be captured, or the testament does not match.
+pub fn assert_binary_testament(binary: &Path, args: &[&str], repo: &Path) {
+    let output = Command::new(binary)
+        .args(args)
+        .output()
+        .unwrap_or_else(|e| panic!("Unable to run {}: {e}", binary.display()));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let rendered = stdout
+        .lines()
+        .find_map(RenderedTestament::parse)
+        .unwrap_or_else(|| panic!("No testament found in the output {stdout:?}"));
+    let owned = GitTestament::capture(repo)
+        .unwrap_or_else(|e| panic!("Unable to capture {}: {e}", repo.display()));
+    let modifications: Vec<_> = owned.modifications();
+    if let Err(e) = rendered.check(&owned.as_testament(&modifications)) {
+        panic!("{e}\nOutput was {stdout:?}");
+    }
+}
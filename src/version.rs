@@ -0,0 +1,54 @@
+//! Minimal version parsing and comparison for tags
+//!
+//! Tags are commonly of the form `v1.2.3` or `1.2.3-rc.1`.  We accept an
+//! optional leading `v`, between one and three numeric components, an optional
+//! pre-release suffix, and ignore any build metadata.  This is deliberately
+//! simpler than full semver since it must work without `alloc`.
+
+use core::cmp::Ordering;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Version<'a> {
+    numbers: [u64; 3],
+    pre: &'a str,
+}
+
+impl<'a> Version<'a> {
+    pub(crate) fn parse(tag: &'a str) -> Option<Self> {
+        let tag = tag.strip_prefix(['v', 'V']).unwrap_or(tag);
+        let tag = tag.split_once('+').map_or(tag, |(version, _)| version);
+        let (core, pre) = tag.split_once('-').unwrap_or((tag, ""));
+        let mut numbers = [0; 3];
+        let mut parts = core.split('.');
+        for number in numbers.iter_mut() {
+            match parts.next() {
+                Some(part) => *number = part.parse().ok()?,
+                None => break,
+            }
+        }
+        if core.is_empty() || parts.next().is_some() {
+            return None;
+        }
+        Some(Version { numbers, pre })
+    }
+}
+
+impl<'a> PartialOrd for Version<'a> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<'a> Ord for Version<'a> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.numbers.cmp(&other.numbers).then_with(|| {
+            match (self.pre.is_empty(), other.pre.is_empty()) {
+                // A pre-release sorts before the release itself
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(other.pre),
+            }
+        })
+    }
+}
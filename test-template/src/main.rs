@@ -4,17 +4,277 @@ use git_testament::{git_testament, render_testament};
 #[cfg(feature = "alloc")]
 git_testament!(TESTAMENT);
 
+#[cfg(feature = "alloc")]
+git_testament!(COMPRESSED, compress);
+
+#[cfg(feature = "alloc")]
+git_testament!(EXPORT_IGNORE, respect_export_ignore);
+
+#[cfg(feature = "alloc")]
+git_testament!(RELEASE, release_tag = "v*");
+
+#[cfg(feature = "alloc")]
+git_testament!(SCOPED, scope = "crate");
+
+#[cfg(feature = "alloc")]
+git_testament!(IGNORED, ignore = ["*.orig", "docs/coverage/*"]);
+
+#[cfg(feature = "alloc")]
+git_testament!(UNTRACKED_ALL, untracked = "all");
+git_testament!(UNTRACKED_NO, untracked = "no");
+
+#[cfg(feature = "alloc")]
+git_testament!(SUBMODULES, submodules);
+
+#[cfg(feature = "alloc")]
+git_testament!(SECTIONED, link_section);
+
+#[cfg(feature = "alloc")]
+git_testament!(EXPORTED, export_c = "test_template_version");
+
+#[cfg(feature = "alloc")]
+git_testament!(FFI, ffi = "test_template");
+
+// The functions exported by the `ffi` option, as C code would see them
+#[cfg(feature = "alloc")]
+unsafe extern "C" {
+    fn test_template_rendered() -> *const std::ffi::c_char;
+    fn test_template_commit_hash() -> *const std::ffi::c_char;
+    fn test_template_tag() -> *const std::ffi::c_char;
+    fn test_template_tag_distance() -> i64;
+    fn test_template_branch() -> *const std::ffi::c_char;
+    fn test_template_modification_count() -> usize;
+}
+
+#[cfg(feature = "alloc")]
+fn ffi_str(value: *const std::ffi::c_char) -> Option<String> {
+    (!value.is_null())
+        .then(|| unsafe { std::ffi::CStr::from_ptr(value) }.to_string_lossy().into_owned())
+}
+
+#[cfg(feature = "alloc")]
+git_testament!(REMOTE, remote_fallback);
+
+#[cfg(feature = "alloc")]
+git_testament!(SEMVER, enforce_semver = "warn");
+
+#[cfg(feature = "alloc")]
+git_testament!(PARENTS, parents);
+
+#[cfg(feature = "alloc")]
+git_testament!(TAGS_AT_HEAD, tags_at_head);
+
+#[cfg(feature = "alloc")]
+git_testament!(REMOTE_URL, remote);
+
+#[cfg(feature = "alloc")]
+git_testament!(SIGNATURE, verify_signature);
+
+#[cfg(feature = "alloc")]
+git_testament!(SIGNED_TAG, trust_signed_tags, allowed_signers = "allowed-signers");
+
+#[cfg(feature = "alloc")]
+git_testament!(FIRST_PARENT, first_parent, exclude = "*-rejected", candidates = 5);
+
+#[cfg(feature = "alloc")]
+git_testament!(CONTAINS, describe_mode = "contains");
+
+#[cfg(feature = "alloc")]
+git_testament!(CONTAINS_HASH_ONLY, describe_mode = "contains", privacy = "hash_only");
+
+#[cfg(feature = "alloc")]
+git_testament!(NO_STATUS, status = false);
+
+#[cfg(feature = "alloc")]
+git_testament!(REVISION_FILE, revision_file = "revision.txt");
+
+#[cfg(feature = "alloc")]
+git_testament!(CHANNEL, channels = { "stable" => "1.*", "nightly" => "*" });
+
+#[cfg(feature = "alloc")]
+git_testament!(ELSEWHERE, repo_env = "GTT_REPO_ROOT");
+
+#[cfg(feature = "alloc")]
+git_testament!(DIGEST, worktree_digest);
+
+#[cfg(feature = "alloc")]
+git_testament!(DIFF, embed_diff = 30);
+
+#[cfg(feature = "alloc")]
+git_testament!(GIT_VERSION, git_version);
+
+#[cfg(feature = "alloc")]
+git_testament!(LFS, lfs);
+
+#[cfg(feature = "alloc")]
+git_testament!(TAG_MESSAGE, tag_message);
+
+#[cfg(feature = "alloc")]
+git_testament!(CI, ci);
+
+#[cfg(feature = "alloc")]
+git_testament!(CI_BRANCH, ci_branch);
+
 use git_testament::git_testament_macros;
 
 git_testament_macros!(version, "trusted");
 
+git_testament_macros!(
+    layout,
+    format = "{tag}|{hash}|{hash:7}|{date}|{distance}|{dirty}|{branch}|{{{unknown}}}"
+);
+
+git_testament_macros!(
+    contains_layout,
+    describe_mode = "contains",
+    format = "{tag}~{distance}"
+);
+
 #[cfg(feature = "alloc")]
 fn main() {
     assert_eq!(
         format!("{}", render_testament!(TESTAMENT, "trusted")),
         version_testament!()
     );
+    assert_eq!(format!("{TESTAMENT}"), format!("{COMPRESSED}"));
+    #[cfg(feature = "compression")]
+    {
+        let decompressed = COMPRESSED
+            .compressed_modifications
+            .map(|table| format!("{:?}", table.decompress().unwrap().iter().collect::<Vec<_>>()))
+            .unwrap_or_else(|| format!("{:?}", COMPRESSED.modifications));
+        assert_eq!(format!("{:?}", TESTAMENT.modifications), decompressed);
+    }
+    assert_eq!(
+        render_testament!(
+            TESTAMENT,
+            format = "{tag}|{hash}|{hash:7}|{date}|{distance}|{dirty}|{branch}|{{{unknown}}}"
+        ),
+        layout_formatted!()
+    );
     println!("{}", render_testament!(TESTAMENT, "trusted"));
+    println!("export-ignore: {}", EXPORT_IGNORE.modification_count());
+    println!("scoped: {}", SCOPED.modification_count());
+    println!("ignored: {}", IGNORED.modification_count());
+    println!("untracked-all: {}", UNTRACKED_ALL.modification_count());
+    println!("untracked-no: {}", UNTRACKED_NO.modification_count());
+    for modification in TESTAMENT.modifications {
+        println!(
+            "modification: {:?} {}",
+            modification.kind(),
+            modification.path_lossy()
+        );
+    }
+    println!("remote: {REMOTE}");
+    println!("semver: {SEMVER}");
+    println!("parents: {}", PARENTS.parents.join(" "));
+    println!("tags-at-head: {}", TAGS_AT_HEAD.tags_at_head.join(" "));
+    assert_eq!(TESTAMENT.is_merge, version_is_merge!());
+    println!("merge: {}", TESTAMENT.is_merge);
+    println!("elsewhere: {ELSEWHERE}");
+    println!("first-parent: {FIRST_PARENT}");
+    println!("contains: {CONTAINS}");
+    println!(
+        "contains-rendered: {}",
+        CONTAINS.rendered() == render_testament!(CONTAINS)
+    );
+    println!(
+        "contains-formatted: {} {}",
+        contains_layout_formatted!(),
+        render_testament!(CONTAINS, format = "{tag}~{distance}") == contains_layout_formatted!()
+    );
+    println!("contains-hash-only: {CONTAINS_HASH_ONLY}");
+    println!("no-status: {NO_STATUS}");
+    println!("revision-file: {REVISION_FILE}");
+    println!("channel: {}", CHANNEL.channel.unwrap_or("none"));
+    println!("digest: {}", DIGEST.worktree_digest.unwrap_or("none"));
+    match DIFF.diff {
+        Some(diff) => println!("diff: {}", String::from_utf8_lossy(diff)),
+        None => println!("diff: none"),
+    }
+    println!("lfs-incomplete: {}", LFS.lfs_incomplete);
+    assert_eq!(TESTAMENT.git_version, None);
+    println!("git-version: {}", GIT_VERSION.git_version.unwrap_or("none"));
+    println!(
+        "tag-message: {:?} {:?}",
+        TAG_MESSAGE.tag_message, TAG_MESSAGE.tag_date
+    );
+    match CI.ci {
+        Some(ci) => println!("ci: {} {:?} {:?}", ci.provider, ci.run_id, ci.job_url),
+        None => println!("ci: none"),
+    }
+    println!(
+        "branch: {:?} {:?} detached: {}",
+        TESTAMENT.branch_name, CI_BRANCH.branch_name, TESTAMENT.detached
+    );
+    assert_eq!(
+        TESTAMENT.commit_timestamp.unwrap_or_default(),
+        version_commit_timestamp!()
+    );
+    println!("timestamp: {}", version_commit_timestamp!());
+    assert_eq!(TESTAMENT.describe, version_describe!());
+    println!("describe: {}", TESTAMENT.describe.unwrap_or("none"));
+    assert_eq!(TESTAMENT.commit_count.unwrap_or_default(), version_commit_count!());
+    println!("commit-count: {}", version_commit_count!());
+    println!(
+        "worktree: {} {}",
+        TESTAMENT.worktree,
+        TESTAMENT.branch_name.unwrap_or_default()
+    );
+    assert_eq!(TESTAMENT.bare, version_bare!());
+    assert_eq!(
+        TESTAMENT.modifications_collected,
+        version_modifications_collected!()
+    );
+    println!("bare: {}", TESTAMENT.bare);
+    println!("remote-url: {:?}", REMOTE_URL.remote_url);
+    println!("signature: {} {:?}", SIGNATURE.signed, SIGNATURE.signer);
+    println!(
+        "signed-tag: {:?} {:?} {}",
+        SIGNED_TAG.signed_tag,
+        SIGNED_TAG.tag_signer,
+        render_testament!(SIGNED_TAG).starts_with(concat!(env!("CARGO_PKG_VERSION"), " ("))
+    );
+    for submodule in SUBMODULES.submodules {
+        println!(
+            "submodule: {} {} {}",
+            submodule.path, submodule.commit, submodule.dirty
+        );
+    }
+    // The link section static is named after the testament
+    assert_eq!(
+        format!("{}\0", render_testament!(SECTIONED)).as_bytes(),
+        &__GIT_TESTAMENT_SECTION_SECTIONED[..]
+    );
+    // The exported static is named after the symbol
+    assert_eq!(
+        format!("{}\0", render_testament!(EXPORTED)).as_bytes(),
+        &test_template_version[..]
+    );
+    // The ffi functions agree with the testament
+    unsafe {
+        assert_eq!(
+            ffi_str(test_template_rendered()),
+            Some(render_testament!(FFI))
+        );
+        assert_eq!(
+            ffi_str(test_template_commit_hash()).as_deref(),
+            FFI.commit_hash()
+        );
+        assert_eq!(ffi_str(test_template_tag()).as_deref(), FFI.tag());
+        assert_eq!(
+            test_template_tag_distance(),
+            FFI.tag_distance().map_or(-1, |distance| distance as i64)
+        );
+        assert_eq!(ffi_str(test_template_branch()).as_deref(), FFI.branch_name);
+        assert_eq!(test_template_modification_count(), FFI.modification_count());
+    }
+    if let Some(describe) = TESTAMENT.to_describe_string() {
+        println!("describe: {describe}");
+    }
+    if let Some(release) = RELEASE.release {
+        println!("release: {}+{}", release.tag, release.distance);
+    }
 }
 
 #[cfg(not(feature = "alloc"))]
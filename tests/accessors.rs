@@ -0,0 +1,671 @@
+use git_testament::{
+    render_testament, render_testament_json, render_testament_verbose, BuildKind, CommitKind,
+    GitModification, GitTestament, ParsedKind, RenderStrings, Trust, EMPTY_TESTAMENT,
+};
+
+const HASH: &str = "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3";
+
+#[test]
+fn from_tag() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        modifications: &[GitModification::Modified(b"src/main.rs")],
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(testament.commit_hash(), Some(HASH));
+    assert_eq!(testament.commit_hash_short(), Some("763aa159d"));
+    assert_eq!(testament.commit_date(), Some("2019-04-02"));
+    assert_eq!(testament.tag(), Some("1.0.0"));
+    assert_eq!(testament.tag_distance(), Some(14));
+    assert_eq!(testament.build_date(), None);
+    assert!(testament.is_dirty());
+}
+
+#[test]
+fn no_tags() {
+    let testament = GitTestament {
+        commit: CommitKind::NoTags(HASH, "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(testament.commit_hash(), Some(HASH));
+    assert_eq!(testament.tag(), None);
+    assert_eq!(testament.tag_distance(), None);
+    assert!(!testament.is_dirty());
+}
+
+#[test]
+fn no_commit() {
+    let testament = GitTestament {
+        commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(testament.commit_hash(), None);
+    assert_eq!(testament.commit_date(), None);
+    assert_eq!(testament.build_date(), Some("2019-04-02"));
+}
+
+#[test]
+fn trusted_branch() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        branch_name: Some("stable"),
+        ..EMPTY_TESTAMENT
+    };
+    assert!(testament.trusting_branch("1.1.0", "main").is_none());
+    let trusted = testament
+        .trusting_branch("1.1.0", "stable")
+        .expect("branch should be trusted");
+    assert!(matches!(
+        trusted.commit,
+        CommitKind::FromTrustedBranch("1.1.0", "stable", HASH, "2019-04-02")
+    ));
+    assert_eq!(trusted.tag(), Some("1.1.0"));
+    assert_eq!(trusted.tag_distance(), Some(0));
+    assert_eq!(trusted.to_string(), "1.1.0 (763aa159d 2019-04-02)");
+
+    let dirty = GitTestament {
+        modifications: &[GitModification::Modified(b"src/main.rs")],
+        ..testament
+    };
+    assert!(dirty.trusting_branch("1.1.0", "stable").is_none());
+}
+
+#[test]
+fn trust_policy() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        modifications: &[GitModification::Modified(b"src/main.rs")],
+        ..EMPTY_TESTAMENT
+    };
+    // A policy may trust a dirty build, but the modifications are still shown
+    assert_eq!(
+        testament
+            .render_with_policy(|t| {
+                assert_eq!(t.modification_count(), 1);
+                Trust::Version("1.1.0")
+            })
+            .to_string(),
+        "1.1.0 (763aa159d 2019-04-02) dirty 1 modification"
+    );
+    assert_eq!(
+        testament
+            .render_with_policy(|_| Trust::Untrusted)
+            .to_string(),
+        testament.to_string()
+    );
+
+    // Without a commit there is nothing to trust, so the policy is not asked
+    let uncommitted = GitTestament {
+        commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        uncommitted
+            .render_with_policy(|_| unreachable!())
+            .to_string(),
+        "1.0.0 (uncommitted 2019-04-02)"
+    );
+}
+
+#[test]
+fn trusted_branch_patterns() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        branch_name: Some("release/1.x"),
+        ..EMPTY_TESTAMENT
+    };
+    assert!(testament.trusting_branch("1.1.0", "release/2.*").is_none());
+    let trusted = testament
+        .trusting_branch("1.1.0", "release/*")
+        .expect("branch should be trusted");
+    // The branch is recorded, rather than the pattern which trusted it
+    assert!(matches!(
+        trusted.commit,
+        CommitKind::FromTrustedBranch("1.1.0", "release/1.x", HASH, "2019-04-02")
+    ));
+    let version = env!("CARGO_PKG_VERSION");
+    assert_eq!(
+        render_testament!(testament, trusted = ["main", "release/?.x"]),
+        format!("{version} (763aa159d 2019-04-02)")
+    );
+    assert_eq!(
+        render_testament!(testament, trusted = ["main", "stable"]),
+        format!("{version} :: 1.0.0+14 (763aa159d 2019-04-02)")
+    );
+    assert_eq!(
+        render_testament!(testament, "release/*"),
+        render_testament!(testament, trusted = ["release/*"])
+    );
+}
+
+#[test]
+fn render_other_version() {
+    const PRODUCT_VERSION: &str = "2024.1";
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        branch_name: Some("release/1.x"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        render_testament!(testament, version = PRODUCT_VERSION),
+        "2024.1 :: 1.0.0+14 (763aa159d 2019-04-02)"
+    );
+    assert_eq!(
+        render_testament!(testament, "release/*", version = PRODUCT_VERSION),
+        "2024.1 (763aa159d 2019-04-02)"
+    );
+    assert_eq!(
+        render_testament!(
+            testament,
+            trusted = ["main", "release/?.x"],
+            version = "2024.2"
+        ),
+        "2024.2 (763aa159d 2019-04-02)"
+    );
+    // The tag containing the version is enough for it not to be repeated
+    assert_eq!(
+        render_testament!(testament, version = "1.0"),
+        "1.0.0+14 (763aa159d 2019-04-02)"
+    );
+    let trusted: Vec<&str> = vec!["main"];
+    assert_eq!(
+        testament.render_with_version(PRODUCT_VERSION, &trusted),
+        render_testament!(testament, version = PRODUCT_VERSION)
+    );
+}
+
+#[test]
+fn signed_tag_trust() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("v1.0.0", HASH, "2019-04-02", 0),
+        branch_name: Some("detached"),
+        signed_tag: Some("v1.0.0"),
+        ..EMPTY_TESTAMENT
+    };
+    let trusted = testament
+        .trusting_signed_tag("1.0.0")
+        .expect("signed tag should be trusted");
+    assert!(matches!(
+        trusted.commit,
+        CommitKind::FromTrustedBranch("1.0.0", "v1.0.0", HASH, "2019-04-02")
+    ));
+    // No branch need be trusted
+    let version = env!("CARGO_PKG_VERSION");
+    assert_eq!(
+        render_testament!(testament),
+        format!("{version} (763aa159d 2019-04-02)")
+    );
+    let dirty = GitTestament {
+        modifications: &[GitModification::Modified(b"src/lib.rs")],
+        ..testament
+    };
+    assert!(dirty.trusting_signed_tag("1.0.0").is_none());
+    let unsigned = GitTestament {
+        signed_tag: None,
+        ..testament
+    };
+    assert!(unsigned.trusting_signed_tag("1.0.0").is_none());
+}
+
+#[test]
+fn describe_string() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        modifications: &[GitModification::Untracked(b"notes.txt")],
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        testament.to_describe_string().as_deref(),
+        Some("1.0.0-14-g763aa15")
+    );
+    let dirty = GitTestament {
+        commit: CommitKind::NoTags(HASH, "2019-04-02"),
+        modifications: &[GitModification::Modified(b"src/main.rs")],
+        describe_hash_length: 10,
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        dirty.to_describe_string().as_deref(),
+        Some("763aa159d6-dirty")
+    );
+    let uncommitted = GitTestament {
+        commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(uncommitted.to_describe_string(), None);
+}
+
+#[test]
+fn render_format() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        modifications: &[GitModification::Modified(b"src/main.rs")],
+        branch_name: Some("main"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        testament.render_format("{tag}+{distance} {hash:7} {date} {branch} {dirty}"),
+        "1.0.0+14 763aa15 2019-04-02 main dirty"
+    );
+    assert_eq!(
+        testament.render_format("{{tag}} {nope} {hash:x} }"),
+        "{tag} {nope} {hash:x} }"
+    );
+    let uncommitted = GitTestament {
+        commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        uncommitted.render_format("{tag} [{hash}] {date} [{dirty}]"),
+        "1.0.0 [] 2019-04-02 []"
+    );
+}
+
+#[test]
+fn json() {
+    let uncommitted = GitTestament {
+        commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+        modifications: &[
+            GitModification::Untracked(b"say \"hi\"\n.txt"),
+            GitModification::Renamed(b"old.txt", b"new.txt"),
+        ],
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        uncommitted.to_json(),
+        concat!(
+            r#"{"version":null,"commit":null,"tag":null,"distance":null,"date":"2019-04-02","#,
+            r#""branch":null,"dirty":true,"#,
+            r#""modifications":[{"kind":"untracked","path":"say \"hi\"\n.txt"},"#,
+            r#"{"kind":"renamed","path":"new.txt","from":"old.txt"}]}"#,
+        )
+    );
+    let trusted = GitTestament {
+        commit: CommitKind::FromTag("0.9.0", HASH, "2019-04-02", 0),
+        branch_name: Some("main"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        render_testament_json!(trusted, "main"),
+        format!(
+            concat!(
+                r#"{{"version":"{0}","commit":"{1}","tag":"{0}","distance":0,"#,
+                r#""date":"2019-04-02","branch":"main","dirty":false,"modifications":[]}}"#,
+            ),
+            env!("CARGO_PKG_VERSION"),
+            HASH
+        )
+    );
+}
+
+#[test]
+fn build_kind() {
+    let release = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 0),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(release.kind(), BuildKind::Release);
+    assert!(release.is_release());
+    assert!(release.commit.is_release());
+    let dirty = GitTestament {
+        modifications: &[GitModification::Modified(b"src/main.rs")],
+        ..release
+    };
+    assert_eq!(dirty.kind(), BuildKind::Dirty);
+    assert!(!dirty.is_release());
+    assert!(dirty.commit.is_release());
+    let post = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 3),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(post.kind(), BuildKind::PostRelease { distance: 3 });
+    let pre = GitTestament {
+        commit: CommitKind::BeforeTag("1.1.0", HASH, "2019-04-02", 2),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(pre.kind(), BuildKind::PreRelease { distance: 2 });
+    assert_eq!(pre.release_version(), None);
+    assert_eq!(pre.to_describe_string().as_deref(), Some("1.1.0~2"));
+    let untagged = GitTestament {
+        commit: CommitKind::Shallow("1.0.0", HASH, "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(untagged.kind(), BuildKind::Untagged);
+    assert!(untagged.commit.has_commit());
+    let uncommitted = GitTestament {
+        commit: CommitKind::FromRemote("1.0.0", "origin/main", HASH, "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(uncommitted.kind(), BuildKind::NoCommit);
+    assert!(!uncommitted.commit.has_commit());
+    assert_eq!(EMPTY_TESTAMENT.kind(), BuildKind::NoRepo);
+    let packaged = GitTestament {
+        commit: CommitKind::FromTarball(HASH, "1.0.0", "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(packaged.kind(), BuildKind::Release);
+    assert_eq!(packaged.release_version(), Some("1.0.0"));
+    assert_eq!(packaged.commit_hash(), Some(HASH));
+    assert_eq!(packaged.commit_date(), None);
+    assert_eq!(packaged.build_date(), Some("2019-04-02"));
+}
+
+#[test]
+fn modifications_not_collected() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 0),
+        branch_name: Some("main"),
+        modifications_collected: false,
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        testament.to_string(),
+        "1.0.0 (763aa159d 2019-04-02) maybe dirty"
+    );
+    assert!(!testament.is_dirty());
+    assert_eq!(testament.kind(), BuildKind::Release);
+    // Nothing is claimed to be a release without knowing the tree was clean
+    assert!(!testament.is_release());
+    assert_eq!(testament.release_version(), None);
+    assert_eq!(testament.trusting_branch("1.0.0", "main"), None);
+    assert!(testament
+        .to_json()
+        .contains(r#""dirty":null,"modifications":[]"#));
+
+    let parsed = GitTestament::parse_rendered(&testament.to_string()).expect("Unable to parse");
+    assert_eq!(parsed.modifications, 0);
+    assert!(!parsed.modifications_collected);
+    assert!(
+        GitTestament::parse_rendered("1.0.0 (763aa159d 2019-04-02)")
+            .expect("Unable to parse")
+            .modifications_collected
+    );
+}
+
+#[test]
+fn alternate_display() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 0),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        format!("{testament:#}"),
+        format!(
+            "1.0.0 (763aa159d 2019-04-02)\n    commit: {HASH}\n    tag: 1.0.0\n    distance: 0"
+        )
+    );
+    // Without a commit there is nothing more to say
+    let uncommitted = GitTestament {
+        commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+        modifications: &[
+            GitModification::Added(b"src/lib.rs"),
+            GitModification::Untracked(b"notes.txt"),
+        ],
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        format!("{uncommitted:#}"),
+        concat!(
+            "1.0.0 (uncommitted 2019-04-02) dirty 2 modifications\n",
+            "    added: src/lib.rs\n",
+            "    untracked: notes.txt",
+        )
+    );
+    assert!(!uncommitted.to_string().contains('\n'));
+}
+
+#[test]
+fn render_with() {
+    let strings = RenderStrings {
+        dirty: "sale",
+        modifications: |count| {
+            if count > 1 {
+                "modifications"
+            } else {
+                "modification"
+            }
+        },
+        uncommitted: "non validé",
+        ..RenderStrings::ENGLISH
+    };
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        modifications: &[
+            GitModification::Modified(b"src/main.rs"),
+            GitModification::Untracked(b"notes.txt"),
+        ],
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        testament.render_with(&strings).to_string(),
+        "1.0.0+14 (763aa159d 2019-04-02) sale 2 modifications"
+    );
+    assert_eq!(
+        testament.render_with(&RenderStrings::ENGLISH).to_string(),
+        testament.to_string()
+    );
+    let testament = GitTestament {
+        commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        testament.render_with(&strings).to_string(),
+        "1.0.0 (non validé 2019-04-02)"
+    );
+}
+
+#[test]
+fn render_verbose() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        modifications: &[
+            GitModification::Modified(b"src/main.rs"),
+            GitModification::Untracked(b"notes\xff.txt"),
+        ],
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        render_testament_verbose!(testament),
+        format!(
+            "{}\n    modified: src/main.rs\n    untracked: notes\u{FFFD}.txt",
+            render_testament!(testament)
+        )
+    );
+    let clean = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(clean.render_verbose().to_string(), clean.to_string());
+}
+
+#[test]
+fn render_into_buffer() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 14),
+        modifications: &[GitModification::Modified(b"src/main.rs")],
+        ..EMPTY_TESTAMENT
+    };
+    let expected = testament.to_string();
+    let mut buf = [0u8; 64];
+    let len = testament
+        .render_into(&mut buf)
+        .expect("buffer is large enough");
+    assert_eq!(&buf[..len], expected.as_bytes());
+    // An exact fit is enough
+    let len = testament
+        .render_into(&mut buf[..expected.len()])
+        .expect("buffer is exactly large enough");
+    assert_eq!(len, expected.len());
+    let mut small = [0u8; 10];
+    let err = testament
+        .render_into(&mut small)
+        .expect_err("buffer is too small");
+    assert_eq!(err.required(), expected.len());
+    assert_eq!(&small[..], &expected.as_bytes()[..10]);
+}
+
+#[test]
+fn equality() {
+    let tagged = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 0),
+        ..EMPTY_TESTAMENT
+    };
+    let untagged = GitTestament {
+        commit: CommitKind::NoTags(HASH, "2019-04-02"),
+        modifications: &[GitModification::Modified(b"src/main.rs")],
+        ..EMPTY_TESTAMENT
+    };
+    let other = GitTestament {
+        commit: CommitKind::NoTags("651af89ed", "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        tagged,
+        GitTestament {
+            commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 0),
+            ..EMPTY_TESTAMENT
+        }
+    );
+    assert_ne!(tagged, untagged);
+    assert!(tagged.same_commit(&untagged));
+    assert!(!tagged.same_commit(&other));
+    assert!(!EMPTY_TESTAMENT.same_commit(&EMPTY_TESTAMENT));
+
+    let hashes: std::collections::HashSet<_> = [&tagged, &untagged, &tagged].into();
+    assert_eq!(hashes.len(), 2);
+}
+
+#[test]
+fn parse_rendered() {
+    let modifications = &[
+        GitModification::Modified(b"src/main.rs"),
+        GitModification::Untracked(b"notes.txt"),
+    ];
+    let cases = [
+        (
+            CommitKind::NoRepository("1.0.0", "2019-04-02"),
+            ParsedKind::NoRepository,
+        ),
+        (
+            CommitKind::NoCommit("1.0.0", "2019-04-02"),
+            ParsedKind::NoCommit,
+        ),
+        (
+            CommitKind::FromRemote("1.0.0", "origin/main", HASH, "2019-04-02"),
+            ParsedKind::FromRemote("origin/main".to_owned()),
+        ),
+        (
+            CommitKind::Shallow("1.0.0", HASH, "2019-04-02"),
+            ParsedKind::Shallow,
+        ),
+        (
+            CommitKind::FromTarball(HASH, "1.0.0", "2019-04-02"),
+            ParsedKind::FromTarball,
+        ),
+        (CommitKind::NoTags(HASH, "2019-04-02"), ParsedKind::Commit),
+        (
+            CommitKind::FromTag("v1.0.0", HASH, "2019-04-02", 14),
+            ParsedKind::Commit,
+        ),
+        (
+            CommitKind::BeforeTag("v1.1.0", HASH, "2019-04-02", 3),
+            ParsedKind::BeforeTag,
+        ),
+    ];
+    for (commit, kind) in cases {
+        for modifications in [&modifications[..], &modifications[..1], &[]] {
+            let testament = GitTestament {
+                commit,
+                modifications,
+                ..EMPTY_TESTAMENT
+            };
+            let rendered = testament.to_string();
+            let parsed = GitTestament::parse_rendered(&rendered).expect(&rendered);
+            assert_eq!(parsed.kind, kind, "{rendered}");
+            assert_eq!(parsed.crate_version, None);
+            let hash = match kind {
+                ParsedKind::NoRepository | ParsedKind::NoCommit => None,
+                _ => Some("763aa159d"),
+            };
+            assert_eq!(parsed.hash.as_deref(), hash, "{rendered}");
+            assert_eq!(parsed.modifications, modifications.len(), "{rendered}");
+        }
+    }
+
+    let parsed = GitTestament::parse_rendered(" 1.1.0 :: v1.0.0+14 (763aa159d 2019-04-02)\n")
+        .expect("Unable to parse");
+    assert_eq!(parsed.crate_version.as_deref(), Some("1.1.0"));
+    assert_eq!(parsed.version, "v1.0.0");
+    assert_eq!(parsed.distance, 14);
+    assert_eq!(parsed.date, "2019-04-02");
+
+    let parsed = GitTestament::parse_rendered("1.0.0+build (763aa159d 2019-04-02)")
+        .expect("Unable to parse");
+    assert_eq!(
+        (parsed.version.as_str(), parsed.distance),
+        ("1.0.0+build", 0)
+    );
+
+    let parsed =
+        GitTestament::parse_rendered("v1.1.0~3 (763aa159d 2019-04-02)").expect("Unable to parse");
+    assert_eq!((parsed.version.as_str(), parsed.distance), ("v1.1.0", 3));
+
+    for bad in [
+        "",
+        "1.0.0",
+        "1.0.0 (763aa159d 2019-04-02",
+        "1.0.0 (not-a-hash 2019-04-02)",
+        "1.0.0 (763aa159d 2019-04-02) dirty",
+        "1.0.0 (763aa159d 2019-04-02) dirty 0 modifications",
+        "1.0.0 (763aa159d 2019-04-02) and more",
+        "1.0.0~next (763aa159d 2019-04-02)",
+        "my program 1.0.0 (763aa159d 2019-04-02)",
+    ] {
+        assert!(GitTestament::parse_rendered(bad).is_err(), "{bad:?}");
+    }
+}
+
+#[test]
+fn slsa_provenance() {
+    let testament = GitTestament {
+        commit: CommitKind::FromTag("1.0.0", HASH, "2019-04-02", 0),
+        branch_name: Some("main"),
+        remote_url: Some("https://example.com/app.git"),
+        ci: Some(git_testament::CiInfo {
+            provider: "gitlab",
+            run_id: Some("42"),
+            job_url: Some("https://gitlab.example.com/app/-/jobs/7"),
+        }),
+        ..EMPTY_TESTAMENT
+    };
+    assert_eq!(
+        testament.to_slsa_provenance("sha256:abc123"),
+        format!(
+            concat!(
+                r#"{{"_type":"https://in-toto.io/Statement/v1","#,
+                r#""subject":[{{"digest":{{"sha256":"abc123"}}}}],"#,
+                r#""predicateType":"https://slsa.dev/provenance/v1","#,
+                r#""predicate":{{"buildDefinition":{{"#,
+                r#""buildType":"https://github.com/kinnison/git-testament/slsa/cargo/v1","#,
+                r#""externalParameters":{{"branch":"main","tag":"1.0.0","dirty":false}},"#,
+                r#""resolvedDependencies":[{{"uri":"git+https://example.com/app.git","#,
+                r#""digest":{{"gitCommit":"{0}"}}}}]}},"#,
+                r#""runDetails":{{"builder":{{"id":"https://github.com/kinnison/git-testament"}},"#,
+                r#""metadata":{{"invocationId":"https://gitlab.example.com/app/-/jobs/7"}}}}}}}}"#,
+            ),
+            HASH
+        )
+    );
+
+    // Without a commit there is nothing to say the build came from
+    let uncommitted = GitTestament {
+        commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+        ..EMPTY_TESTAMENT
+    };
+    assert!(uncommitted
+        .to_slsa_provenance("abc123")
+        .contains(r#""subject":[{"digest":{"sha256":"abc123"}}]"#));
+    assert!(uncommitted
+        .to_slsa_provenance("abc123")
+        .contains(r#""resolvedDependencies":[]"#));
+}
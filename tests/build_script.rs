@@ -0,0 +1,125 @@
+mod testutils;
+
+use std::fs;
+
+const BUILD_RS: &str = r#"
+fn main() {
+    let out_dir = std::env::var_os("OUT_DIR").unwrap();
+    git_testament_build::emit(out_dir).expect("Unable to generate testament");
+}
+"#;
+
+const MAIN_RS: &str = r#"
+use git_testament::{git_testament, render_testament, GitTestament};
+
+git_testament!(TESTAMENT);
+
+const BUILT: GitTestament<'static> = include!(concat!(env!("OUT_DIR"), "/testament.rs"));
+
+fn main() {
+    println!("{}", render_testament!(BUILT));
+    println!("macro: {}", render_testament!(TESTAMENT));
+    println!("modifications: {}", BUILT.modification_count());
+}
+"#;
+
+fn prep_build_script_test(name: &str) -> testutils::TestSentinel {
+    let test = testutils::prep_test(name);
+    let toml = fs::read_to_string(test.path().join("Cargo.toml")).expect("Unable to read toml");
+    test.write_file(
+        "Cargo.toml",
+        &format!(
+            "{toml}\n[build-dependencies]\ngit-testament-build = {{ path=\"{}\" }}\n",
+            concat!(env!("CARGO_MANIFEST_DIR"), "/git-testament-build").replace('\\', "\\\\")
+        ),
+    );
+    test.write_file("build.rs", BUILD_RS);
+    test.write_file("src/main.rs", MAIN_RS);
+    test
+}
+
+#[test]
+fn build_script_matches_macro() {
+    let test = prep_build_script_test("build-script");
+    assert!(test.basic_git_init());
+    assert!(test.run_cmd("cargo", &["check"]));
+    assert!(test.run_cmd("git", &["add", "."]));
+    assert!(test.run_cmd("git", &["commit", "-m", "first"]));
+    assert!(test.run_cmd("git", &["tag", "-m", "1.0.0", "1.0.0"]));
+    assert!(test.run_cmd("cargo", &["build"]));
+    test.assert_manifest_parts("1.0.0", 0, "TODO", None);
+    let manifest = test.get_manifest().expect("Unable to run test binary");
+    let built = manifest.lines().next().expect("No manifest line");
+    test.assert_manifest_contains(&format!("macro: {built}\n"));
+
+    // A new commit must be noticed without touching the code
+    assert!(test.run_cmd("git", &["commit", "--allow-empty", "-m", "second"]));
+    assert!(test.run_cmd("cargo", &["build"]));
+    test.assert_manifest_parts("1.0.0", 1, "TODO", None);
+
+    // As must staged changes
+    test.write_file("notes.txt", "notes\n");
+    assert!(test.run_cmd("git", &["add", "notes.txt"]));
+    assert!(test.run_cmd("cargo", &["build"]));
+    test.assert_manifest_contains("modifications: 1");
+}
+
+#[test]
+fn build_script_without_repository() {
+    let test = prep_build_script_test("build-script-no-repo");
+    assert!(test.run_cmd("cargo", &["build"]));
+    test.assert_manifest_contains("1.0.0");
+    test.assert_manifest_contains("modifications: 0");
+}
+
+#[test]
+fn build_info_forwarded() {
+    let test = prep_build_script_test("build-script-build-info");
+    test.write_file(
+        "build.rs",
+        r#"
+fn main() {
+    git_testament_build::forward_build_info();
+    let out_dir = std::env::var_os("OUT_DIR").unwrap();
+    git_testament_build::emit(out_dir).expect("Unable to generate testament");
+}
+"#,
+    );
+    test.write_file(
+        "src/main.rs",
+        r#"
+use git_testament::{git_testament, git_testament_macros, GitTestament};
+
+git_testament!(TESTAMENT, build_info);
+git_testament_macros!(info, build_info);
+
+const BUILT: GitTestament<'static> = include!(concat!(env!("OUT_DIR"), "/testament.rs"));
+
+fn main() {
+    let info = TESTAMENT.build_info.unwrap();
+    let built = BUILT.build_info.unwrap();
+    assert_eq!(info.target, built.target);
+    assert_eq!(info.rustc, built.rustc);
+    assert_eq!(info.target, info_target!());
+    assert_eq!(info.rustc, info_rustc!());
+    println!("target: {}", info.target.unwrap());
+    println!("profile: {} {}", info.profile.unwrap(), info_profile!().unwrap());
+    println!("opt-level: {}", info_opt_level!().unwrap());
+    println!("rustc: {}", info.rustc.unwrap());
+}
+"#,
+    );
+    assert!(test.basic_git_init());
+    assert!(test.run_cmd("cargo", &["build"]));
+    test.assert_manifest_contains("profile: debug debug\n");
+    test.assert_manifest_contains("opt-level: 0\n");
+    test.assert_manifest_contains("rustc: rustc ");
+    let rustc = test
+        .get_output("rustc", &["-vV"])
+        .expect("Unable to run rustc");
+    let host = rustc
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .expect("No host in rustc -vV");
+    test.assert_manifest_contains(&format!("target: {host}\n"));
+}
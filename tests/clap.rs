@@ -0,0 +1,42 @@
+#![cfg(feature = "clap")]
+
+use git_testament::{
+    git_testament, render_testament, CommitKind, GitModification, EMPTY_TESTAMENT,
+};
+
+git_testament!(TESTAMENT);
+
+#[test]
+fn version() {
+    let command = clap::Command::new("test").version(git_testament::clap::version!(TESTAMENT));
+    assert_eq!(command.get_version(), Some(&*render_testament!(TESTAMENT)));
+    let trusted = git_testament::clap::version!(TESTAMENT, "main");
+    assert_eq!(trusted.as_str(), render_testament!(TESTAMENT, "main"));
+}
+
+#[test]
+fn long_version() {
+    let testament = git_testament::GitTestament {
+        commit: CommitKind::FromTag(
+            "1.0.0",
+            "651af89ed0123456789abcdef0123456789abcde",
+            "2019-04-02",
+            0,
+        ),
+        modifications: &[
+            GitModification::Modified(b"src/main.rs"),
+            GitModification::Renamed(b"src/lib.rs", b"src/core.rs"),
+            GitModification::Untracked(b"notes.txt"),
+        ],
+        branch_name: Some("main"),
+        ..EMPTY_TESTAMENT
+    };
+    let long = git_testament::clap::long_version!(testament);
+    assert_eq!(
+        long.as_str(),
+        format!(
+            "{}\ncommit: 651af89ed0123456789abcdef0123456789abcde\nbranch: main\nmodified: src/main.rs\nrenamed: src/lib.rs -> src/core.rs\nuntracked: notes.txt",
+            render_testament!(testament)
+        )
+    );
+}
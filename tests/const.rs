@@ -25,3 +25,10 @@ fn it_works() {
         MACROS_BRANCH_NAME_OR_DEFAULT
     );
 }
+
+const RENDERED: &str = TESTAMENT.rendered();
+
+#[test]
+fn rendered() {
+    assert_eq!(RENDERED, TESTAMENT.to_string());
+}
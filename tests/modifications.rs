@@ -0,0 +1,90 @@
+use git_testament::{
+    CommitKind, GitModification, GitTestament, ModificationKind, ModificationSummary,
+    EMPTY_TESTAMENT,
+};
+
+#[test]
+fn path_and_kind() {
+    let modifications = [
+        GitModification::Added(b"src/new.rs"),
+        GitModification::Removed(b"src/old.rs"),
+        GitModification::Modified(b"src/main.rs"),
+        GitModification::Renamed(b"src/lib.rs", b"src/core.rs"),
+        GitModification::Copied(b"src/core.rs", b"src/more.rs"),
+        GitModification::Untracked(b"notes\xff.txt"),
+    ];
+    let kinds: Vec<_> = modifications.iter().map(GitModification::kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            ModificationKind::Added,
+            ModificationKind::Removed,
+            ModificationKind::Modified,
+            ModificationKind::Renamed,
+            ModificationKind::Copied,
+            ModificationKind::Untracked
+        ]
+    );
+    assert_eq!(modifications[2].path_bytes(), b"src/main.rs");
+    assert_eq!(modifications[2].original_path_bytes(), None);
+    assert_eq!(modifications[3].path_bytes(), b"src/core.rs");
+    assert_eq!(
+        modifications[3].original_path_bytes(),
+        Some(&b"src/lib.rs"[..])
+    );
+    assert_eq!(
+        modifications[4].original_path_bytes(),
+        Some(&b"src/core.rs"[..])
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn lossy_paths() {
+    assert_eq!(
+        GitModification::Modified(b"src/main.rs").path_lossy(),
+        "src/main.rs"
+    );
+    assert_eq!(
+        GitModification::Untracked(b"notes\xff.txt").path_lossy(),
+        "notes\u{FFFD}.txt"
+    );
+}
+
+#[test]
+fn summary_by_kind() {
+    let testament = GitTestament {
+        commit: CommitKind::NoCommit("1.0.0", "2019-04-02"),
+        modifications: &[
+            GitModification::Added(b"src/new.rs"),
+            GitModification::Modified(b"src/main.rs"),
+            GitModification::Renamed(b"src/lib.rs", b"src/core.rs"),
+            GitModification::Modified(b"Cargo.toml"),
+            GitModification::Untracked(b"notes.txt"),
+        ],
+        ..EMPTY_TESTAMENT
+    };
+    let summary = testament.modification_summary();
+    assert_eq!(
+        (summary.added, summary.removed, summary.modified),
+        (1, 0, 2)
+    );
+    assert_eq!(
+        (
+            summary.renamed,
+            summary.copied,
+            summary.untracked,
+            summary.unknown
+        ),
+        (1, 0, 1, 0)
+    );
+    assert_eq!(summary.total(), testament.modification_count());
+    assert_eq!(
+        summary.to_string(),
+        "2 modified, 1 added, 1 renamed, 1 untracked"
+    );
+
+    let clean = EMPTY_TESTAMENT.modification_summary();
+    assert_eq!(clean, ModificationSummary::default());
+    assert_eq!(clean.to_string(), "");
+}
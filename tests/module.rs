@@ -0,0 +1,30 @@
+use git_testament::{git_testament, git_testament_full, git_testament_mod, render_testament};
+
+git_testament!(TESTAMENT);
+
+git_testament_mod!(build_info);
+
+git_testament_full!(FULL, format = "{hash}");
+
+#[test]
+fn module_matches_testament() {
+    assert_eq!(build_info::COMMIT, TESTAMENT.commit_hash());
+    assert_eq!(build_info::COMMIT_DATE, TESTAMENT.commit_date());
+    assert_eq!(build_info::TAG, TESTAMENT.tag());
+    assert_eq!(build_info::TAG_DISTANCE, TESTAMENT.tag_distance());
+    assert_eq!(build_info::BRANCH, TESTAMENT.branch_name);
+    assert_eq!(build_info::DIRTY, TESTAMENT.is_dirty());
+    assert_eq!(build_info::render(), render_testament!(TESTAMENT));
+}
+
+#[test]
+fn full_matches_testament() {
+    assert_eq!(FULL.commit_hash(), TESTAMENT.commit_hash());
+    assert_eq!(FULL_branch!(), FULL.branch_name);
+    assert_eq!(FULL_dirty!(), FULL.is_dirty());
+    if let Some(commit) = FULL.commit_hash() {
+        assert_eq!(FULL_commit_hash!(), commit);
+        assert_eq!(FULL_formatted!(), commit);
+    }
+    assert_eq!(FULL_testament!(), FULL.to_string());
+}
@@ -0,0 +1,215 @@
+use git_testament::{
+    git_testament, git_testament_macros, render_testament, render_testament_json, CommitKind,
+    TestamentSource,
+};
+
+git_testament!(TESTAMENT, short_hash = 12);
+
+git_testament_macros!(version, short_hash = 12);
+
+git_testament!(
+    EXTRAS,
+    extra = [
+        ("flavor", "qa"),
+        ("package", env("CARGO_PKG_NAME")),
+        ("missing", env("GIT_TESTAMENT_SURELY_UNSET")),
+    ]
+);
+
+git_testament!(NO_SOURCES, sources = []);
+
+git_testament!(GIT_SOURCE, sources = ["git"]);
+
+// This is a git repository, so the other tools fail and git is used
+git_testament!(FALLBACK_SOURCE, sources = ["jj", "hg", "git"]);
+
+// The tests directory is within the same repository as the crate
+git_testament!(REPO_PATH, repo_path = "tests");
+
+git_testament_macros!(any_channel, channels = { "any" => "*" });
+
+git_testament_macros!(custom, extra = [("flavor", "qa")]);
+
+git_testament!(
+    EXTRA_MAP,
+    extra = { "flavor" => "qa", "package" => env "CARGO_PKG_NAME", "missing" => env("GIT_TESTAMENT_SURELY_UNSET") }
+);
+
+git_testament!(pub TRUSTED, trusted = "main", match = "v*", short_hash = 12);
+
+git_testament!(pub(crate) MATCHED, match = "v*", short_hash = 12);
+
+git_testament!(
+    TIMESTAMPED,
+    date_format =
+        "[year]-[month]-[day]T[hour]:[minute]:[second][offset_hour sign:mandatory]:[offset_minute]"
+);
+
+git_testament_macros!(timestamped, date_format = "[year]/[month]/[day]");
+
+git_testament_macros!(trusted, trusted = "main", match = "v*", short_hash = 12);
+
+git_testament!(RELEASES, trusted = ["release/*", "main"], match = "v*", short_hash = 12);
+
+git_testament_macros!(releases, ["release/*", "main"], match = "v*", short_hash = 12);
+
+git_testament!(NO_PATHS, privacy = "no_paths");
+
+git_testament!(HASH_ONLY, privacy = "hash_only", short_hash = 12);
+
+git_testament_macros!(hash_only, privacy = "hash_only");
+
+git_testament!(
+    ARTIFACT,
+    artifact = "target/git-testament-tests/artifact.json"
+);
+
+git_testament_macros!(
+    artifact,
+    artifact = "target/git-testament-tests/artifact-macros.json"
+);
+
+#[test]
+fn date_format() {
+    let (Some(date), Some(timestamp)) = (TIMESTAMPED.commit_date(), TIMESTAMPED.commit_timestamp)
+    else {
+        return;
+    };
+    // The timestamp is RFC 3339, which writes UTC as `Z`
+    assert_eq!(date.len(), 25);
+    assert_eq!(date[..19], timestamp[..19]);
+    assert!(TIMESTAMPED.to_string().contains(date));
+    assert_eq!(
+        timestamped_commit_date!(),
+        timestamp[..10].replace('-', "/")
+    );
+}
+
+#[test]
+fn short_hash_length() {
+    if let Some(hash) = TESTAMENT.commit_hash_short() {
+        assert_eq!(hash.len(), 12);
+        assert_eq!(hash, version_commit_hash_short!());
+        assert!(version_commit_hash!().starts_with(hash));
+    }
+}
+
+#[test]
+fn dirty_macros() {
+    let count: usize = version_dirty_count!();
+    assert_eq!(count, TESTAMENT.modification_count());
+    assert_eq!(version_dirty!(), TESTAMENT.is_dirty());
+}
+
+#[test]
+fn extras() {
+    assert_eq!(EXTRAS.extra("flavor"), Some("qa"));
+    assert_eq!(EXTRAS.extra("package"), Some("git-testament"));
+    assert_eq!(EXTRAS.extra("missing"), None);
+    assert_eq!(EXTRAS.extras.len(), 2);
+    assert_eq!(TESTAMENT.extra("flavor"), None);
+    assert_eq!(concat!("flavor ", custom_extra!("flavor")), "flavor qa");
+    assert_eq!(EXTRA_MAP.extras, EXTRAS.extras);
+    assert!(EXTRA_MAP
+        .render_extended()
+        .to_string()
+        .ends_with(" [flavor=qa, package=git-testament]"));
+    assert_eq!(
+        TESTAMENT.render_extended().to_string(),
+        TESTAMENT.to_string()
+    );
+}
+
+#[test]
+fn sources() {
+    assert!(matches!(NO_SOURCES.commit, CommitKind::NoRepository(_, _)));
+    assert_eq!(NO_SOURCES.source, TestamentSource::None);
+    if !matches!(GIT_SOURCE.commit, CommitKind::NoRepository(_, _)) {
+        assert_eq!(GIT_SOURCE.source, TestamentSource::Git);
+    }
+    assert_eq!(TESTAMENT.source, GIT_SOURCE.source);
+    assert_eq!(FALLBACK_SOURCE.source, GIT_SOURCE.source);
+    assert_eq!(REPO_PATH.commit_hash(), TESTAMENT.commit_hash());
+}
+
+#[test]
+fn named_trusted_and_match() {
+    assert_eq!(TRUSTED.trusted_branch, Some("main"));
+    assert_eq!(MATCHED.trusted_branch, None);
+    assert_eq!(
+        render_testament!(TRUSTED),
+        render_testament!(MATCHED, "main")
+    );
+    assert_eq!(TRUSTED.rendered, trusted_testament!());
+    if let Some(tag) = MATCHED.tag() {
+        assert!(tag.starts_with('v'));
+    }
+}
+
+#[test]
+fn trusted_lists() {
+    // The pattern matching the branch is the one recorded
+    if TESTAMENT.branch_name == Some("main") {
+        assert_eq!(RELEASES.trusted_branch, Some("main"));
+    } else {
+        assert_eq!(RELEASES.trusted_branch, Some("release/*"));
+    }
+    assert_eq!(
+        render_testament!(RELEASES),
+        render_testament!(MATCHED, trusted = ["release/*", "main"])
+    );
+    assert_eq!(RELEASES.rendered, releases_testament!());
+}
+
+#[test]
+fn channels() {
+    assert_eq!(TESTAMENT.channel, None);
+    if TESTAMENT.branch_name.is_some() {
+        assert_eq!(any_channel_channel!(), Some("any"));
+    }
+}
+
+#[test]
+fn privacy() {
+    assert_eq!(
+        NO_PATHS.modification_count(),
+        TESTAMENT.modification_count()
+    );
+    assert!(NO_PATHS
+        .modifications
+        .iter()
+        .all(|m| m.path_bytes().is_empty()));
+    assert_eq!(NO_PATHS.branch_name, TESTAMENT.branch_name);
+    assert_eq!(HASH_ONLY.commit_hash(), TESTAMENT.commit_hash());
+    assert_eq!(
+        HASH_ONLY.modification_count(),
+        TESTAMENT.modification_count()
+    );
+    assert_eq!(HASH_ONLY.branch_name, None);
+    assert_eq!(HASH_ONLY.tag(), None);
+    assert_eq!(HASH_ONLY.describe, None);
+    assert_eq!(hash_only_branch!(), None::<&str>);
+    assert_eq!(hash_only_tag_present!(), HASH_ONLY.tag().is_some());
+}
+
+#[test]
+fn artifact() {
+    let read = |name: &str| {
+        let path = format!(
+            "{}/target/git-testament-tests/{name}",
+            env!("CARGO_MANIFEST_DIR")
+        );
+        std::fs::read_to_string(path).expect("artifact should have been written")
+    };
+    let json = render_testament_json!(ARTIFACT);
+    let rendered = render_testament!(ARTIFACT);
+    assert_eq!(
+        read("artifact.json"),
+        format!(
+            "{},\"rendered\":\"{rendered}\"}}",
+            json.strip_suffix('}').unwrap()
+        )
+    );
+    assert!(read("artifact-macros.json")
+        .ends_with(&format!(",\"rendered\":\"{}\"}}", artifact_testament!())));
+}
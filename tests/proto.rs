@@ -0,0 +1,67 @@
+#![cfg(feature = "proto")]
+
+use git_testament::proto::{self, modification};
+use git_testament::{CommitKind, GitModification, EMPTY_TESTAMENT};
+use prost::Message;
+
+#[test]
+fn from_testament() {
+    let testament = git_testament::GitTestament {
+        commit: CommitKind::FromTag(
+            "1.0.0",
+            "651af89ed0123456789abcdef0123456789abcde",
+            "2019-04-02",
+            3,
+        ),
+        modifications: &[
+            GitModification::Modified(b"src/main.rs"),
+            GitModification::Renamed(b"src/lib.rs", b"src/core.rs"),
+        ],
+        branch_name: Some("main"),
+        ..EMPTY_TESTAMENT
+    };
+    let message = proto::Testament::from(&testament);
+    assert_eq!(message.kind(), proto::CommitKind::FromTag);
+    assert_eq!(
+        message.commit.as_deref(),
+        Some("651af89ed0123456789abcdef0123456789abcde")
+    );
+    assert_eq!(message.tag.as_deref(), Some("1.0.0"));
+    assert_eq!(message.distance, Some(3));
+    assert_eq!(message.date.as_deref(), Some("2019-04-02"));
+    assert_eq!(message.branch.as_deref(), Some("main"));
+    assert!(message.dirty);
+    assert_eq!(message.rendered, testament.to_string());
+    assert_eq!(
+        message.modifications,
+        vec![
+            proto::Modification {
+                kind: modification::Kind::Modified as i32,
+                path: "src/main.rs".to_owned(),
+                original: None,
+            },
+            proto::Modification {
+                kind: modification::Kind::Renamed as i32,
+                path: "src/core.rs".to_owned(),
+                original: Some("src/lib.rs".to_owned()),
+            },
+        ]
+    );
+
+    let encoded = message.encode_to_vec();
+    assert_eq!(proto::Testament::decode(&*encoded).unwrap(), message);
+}
+
+#[test]
+fn no_repository() {
+    let message = proto::Testament::from(&EMPTY_TESTAMENT);
+    assert_eq!(message.kind(), proto::CommitKind::NoRepository);
+    assert_eq!(message.commit, None);
+    assert_eq!(message.distance, None);
+    assert!(!message.dirty);
+}
+
+#[test]
+fn schema_shipped() {
+    assert!(proto::PROTO.contains("message Testament {"));
+}
@@ -0,0 +1,30 @@
+#![cfg(feature = "provenance")]
+
+use git_testament::{git_testament, CommitKind, FieldSource, EMPTY_TESTAMENT};
+
+git_testament!(TESTAMENT, parents, extra = [("flavor", "qa")]);
+
+git_testament!(
+    FROM_ENV,
+    sources = [],
+    extra = [("flavor", "qa"), ("package", env("CARGO_PKG_NAME"))]
+);
+
+#[test]
+fn provenance() {
+    assert_eq!(EMPTY_TESTAMENT.provenance.commit, FieldSource::Default);
+    assert_eq!(TESTAMENT.provenance.extras, FieldSource::Invocation);
+    assert_eq!(TESTAMENT.provenance.release, FieldSource::Default);
+    assert_eq!(TESTAMENT.provenance.signature, FieldSource::Default);
+    if !matches!(TESTAMENT.commit, CommitKind::NoRepository(..)) {
+        assert_eq!(TESTAMENT.provenance.commit, FieldSource::Git);
+        assert_eq!(TESTAMENT.provenance.modifications, FieldSource::Git);
+        assert_eq!(TESTAMENT.provenance.parents, FieldSource::Git);
+    }
+    assert!(matches!(
+        FROM_ENV.provenance.commit,
+        FieldSource::Build | FieldSource::Environment
+    ));
+    assert_eq!(FROM_ENV.provenance.modifications, FieldSource::Default);
+    assert_eq!(FROM_ENV.provenance.extras, FieldSource::Environment);
+}
@@ -0,0 +1,107 @@
+#![cfg(feature = "runtime")]
+
+use git_testament::{CommitKind, GitTestament};
+
+mod testutils;
+
+fn prep_test(name: &str) -> testutils::TestSentinel {
+    let test = testutils::prep_test(name);
+    // Captures happen in this process, so we need to stop git from finding
+    // the repository that the tests are being run from.
+    std::env::set_var("GIT_CEILING_DIRECTORIES", test.path().parent().unwrap());
+    test
+}
+
+#[test]
+fn capture_no_repo() {
+    let test = prep_test("runtime-no-repo");
+    assert!(GitTestament::capture(test.path()).is_err());
+}
+
+#[test]
+fn capture_tagged_dirty() {
+    let test = prep_test("runtime-tagged");
+    assert!(test.basic_git_init());
+    assert!(test.run_cmd("git", &["add", "."]));
+    assert!(test.run_cmd("git", &["commit", "-m", "first"]));
+    assert!(test.run_cmd("git", &["tag", "-m", "1.0.0", "1.0.0"]));
+    test.dirty_code();
+
+    let owned = GitTestament::capture(test.path()).expect("Unable to capture testament");
+    let modifications = owned.modifications();
+    let testament = owned.as_testament(&modifications);
+    let head = test
+        .get_output("git", &["rev-parse", "HEAD"])
+        .expect("Unable to get HEAD commit");
+    match testament.commit {
+        CommitKind::FromTag(tag, hash, _, distance) => {
+            assert_eq!(tag, "1.0.0");
+            assert_eq!(hash, head.trim_end());
+            assert_eq!(distance, 0);
+        }
+        other => panic!("Unexpected commit kind {other:?}"),
+    }
+    assert_eq!(modifications.len(), 1);
+    assert!(format!("{owned}").ends_with("dirty 1 modification"));
+    assert_eq!(testament.rendered(), format!("{owned}"));
+}
+
+#[test]
+fn capture_workspace() {
+    let test = prep_test("runtime-workspace");
+    assert!(test.basic_git_init());
+    test.write_file("first.txt", "first\n");
+    std::fs::create_dir(test.path().join("other")).unwrap();
+    test.write_file("other/second.txt", "second\n");
+    assert!(test.run_cmd("git", &["add", "."]));
+    assert!(test.run_cmd("git", &["commit", "-m", "first"]));
+    test.write_file("other/second.txt", "changed\n");
+
+    let owned = GitTestament::capture_workspace(test.path(), &[".", "src", "other"])
+        .expect("Unable to capture workspace");
+    let counts: Vec<_> = owned.iter().map(|o| o.modifications().len()).collect();
+    assert_eq!(counts, [1, 0, 1]);
+    let hashes: Vec<_> = owned
+        .iter()
+        .map(|o| o.as_testament(&[]).commit_hash().map(str::to_owned))
+        .collect();
+    assert!(hashes.iter().all(|h| h.is_some() && *h == hashes[0]));
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn capture_async_concurrently() {
+    let test = prep_test("runtime-async");
+    assert!(test.basic_git_init());
+    assert!(test.run_cmd("git", &["add", "."]));
+    assert!(test.run_cmd("git", &["commit", "-m", "first"]));
+    assert!(test.run_cmd("git", &["tag", "-m", "1.0.0", "1.0.0"]));
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to build runtime");
+    let path = test.path();
+    let (first, second, workspace) = runtime.block_on(async {
+        let owned_path = path.to_path_buf();
+        let first = tokio::spawn(async move { GitTestament::capture_async(&owned_path).await });
+        let second = GitTestament::capture_async(path).await;
+        let workspace = GitTestament::capture_workspace_async(path, &["."]).await;
+        (first.await.expect("Capture panicked"), second, workspace)
+    });
+    let first = first.expect("Unable to capture testament");
+    let second = second.expect("Unable to capture testament");
+    let workspace = workspace.expect("Unable to capture workspace");
+    assert_eq!(
+        first.to_string(),
+        GitTestament::capture(path).unwrap().to_string()
+    );
+    assert_eq!(first.to_string(), second.to_string());
+    assert_eq!(workspace.len(), 1);
+    assert_eq!(workspace[0].to_string(), first.to_string());
+    assert!(first.to_string().starts_with("1.0.0 ("));
+
+    let no_repo = prep_test("runtime-async-no-repo");
+    assert!(runtime
+        .block_on(GitTestament::capture_async(no_repo.path()))
+        .is_err());
+}
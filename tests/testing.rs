@@ -0,0 +1,43 @@
+#![cfg(feature = "testing")]
+
+use git_testament::testing::{assert_binary_testament, RenderedTestament};
+
+mod testutils;
+
+#[test]
+fn parse_rendered() {
+    let parsed = RenderedTestament::parse(
+        "prog 1.1.0 :: 1.0.0+3 (651af89ed 2019-04-02) dirty 4 modifications",
+    )
+    .expect("Unable to parse");
+    assert_eq!(parsed.version, "1.0.0");
+    assert_eq!(parsed.distance, 3);
+    assert_eq!(parsed.hash, "651af89ed");
+    assert_eq!(parsed.date, "2019-04-02");
+    assert_eq!(parsed.modifications, 4);
+    let clean = RenderedTestament::parse("1.0.0 (763aa159d 2019-04-02)").expect("Unable to parse");
+    assert_eq!((clean.distance, clean.modifications), (0, 0));
+    assert!(RenderedTestament::parse("1.0.0 (uncommitted 2019-04-02)").is_none());
+    assert!(RenderedTestament::parse("usage: prog [options]").is_none());
+}
+
+#[test]
+fn binary_matches_repository() {
+    let test = testutils::prep_test("testing-helper");
+    std::env::set_var("GIT_CEILING_DIRECTORIES", test.path().parent().unwrap());
+    assert!(test.basic_git_init());
+    assert!(test.run_cmd("cargo", &["check"]));
+    assert!(test.run_cmd("git", &["add", "."]));
+    assert!(test.run_cmd("git", &["commit", "-m", "first"]));
+    assert!(test.run_cmd("git", &["tag", "-m", "1.0.0", "1.0.0"]));
+    test.dirty_code();
+    assert!(test.run_cmd("cargo", &["build"]));
+    assert_binary_testament(test.binary_path().as_ref(), &[], test.path());
+
+    // Once the repository moves on, the binary no longer matches
+    assert!(test.run_cmd("git", &["commit", "-am", "second"]));
+    let stale = std::panic::catch_unwind(|| {
+        assert_binary_testament(test.binary_path().as_ref(), &[], test.path())
+    });
+    assert!(stale.is_err());
+}
@@ -1,10 +1,12 @@
+#![allow(dead_code)]
+
 use lazy_static::lazy_static;
 use rand::{thread_rng, Rng};
 use regex::Regex;
 use std::collections::HashMap;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use tempfile::Builder;
 use tempfile::TempDir;
@@ -19,7 +21,7 @@ impl Drop for TestSentinel {
     fn drop(&mut self) {
         self.run_cmd("cargo", &["clean", "-p", &self.prog_name]);
         if env::var("DO_NOT_ERASE_TESTS").is_ok() {
-            let _ = self.dir.take().unwrap().into_path();
+            let _ = self.dir.take().unwrap().keep();
         }
     }
 }
@@ -110,6 +112,10 @@ pub fn prep_test(name: &str) -> TestSentinel {
 }
 
 impl TestSentinel {
+    pub fn path(&self) -> &Path {
+        self.dir.as_ref().unwrap().path()
+    }
+
     pub fn setenv(&mut self, key: &str, value: &str) {
         self.env.insert(key.to_owned(), value.to_owned());
     }
@@ -181,17 +187,18 @@ impl TestSentinel {
         }
     }
 
-    pub fn get_manifest(&self) -> Option<String> {
-        self.get_output(
-            &format!(
-                "{}/target/debug/{}",
-                env::var("CARGO_MANIFEST_DIR").expect("Unable to run without CARGO_MANIFEST_DIR"),
-                self.prog_name
-            ),
-            &[],
+    pub fn binary_path(&self) -> String {
+        format!(
+            "{}/target/debug/{}",
+            env::var("CARGO_MANIFEST_DIR").expect("Unable to run without CARGO_MANIFEST_DIR"),
+            self.prog_name
         )
     }
 
+    pub fn get_manifest(&self) -> Option<String> {
+        self.get_output(&self.binary_path(), &[])
+    }
+
     pub fn get_manifest_parts(&self) -> ManifestParts {
         let output = self
             .get_manifest()
@@ -282,6 +289,11 @@ impl TestSentinel {
         assert!(manifest.contains(substr));
     }
 
+    pub fn write_file(&self, name: &str, content: &str) {
+        let path = self.dir.as_ref().unwrap().path().join(name);
+        fs::write(path, content).expect("Unable to write file");
+    }
+
     pub fn dirty_code(&self) {
         let main_rs = self.dir.as_ref().unwrap().path().join("src/main.rs");
         let code = fs::read_to_string(&main_rs).expect("Unable to read code");
@@ -0,0 +1,71 @@
+use git_testament::{CommitKind, GitModification, GitTestament, EMPTY_TESTAMENT};
+
+const HASH: &str = "763aa159d6a2d9a2e1d7c1b8d1e9e0b8f0d1c2a3";
+
+fn at_tag(tag: &'static str, distance: usize) -> GitTestament<'static> {
+    GitTestament {
+        commit: CommitKind::FromTag(tag, HASH, "2019-04-02", distance),
+        ..EMPTY_TESTAMENT
+    }
+}
+
+#[test]
+fn release_version() {
+    assert_eq!(at_tag("v1.2.0", 0).release_version(), Some("1.2.0"));
+    assert_eq!(at_tag("1.2.0", 0).release_version(), Some("1.2.0"));
+    assert_eq!(at_tag("v1.2.0", 3).release_version(), None);
+    let dirty = GitTestament {
+        modifications: &[GitModification::Modified(b"src/main.rs")],
+        ..at_tag("v1.2.0", 0)
+    };
+    assert_eq!(dirty.release_version(), None);
+    assert_eq!(EMPTY_TESTAMENT.release_version(), None);
+}
+
+#[test]
+fn update_available() {
+    assert_eq!(at_tag("v1.2.0", 0).update_available("v1.2.1"), Some(true));
+    assert_eq!(at_tag("v1.2.0", 0).update_available("1.10.0"), Some(true));
+    assert_eq!(at_tag("v1.2.0", 0).update_available("v1.2.0"), Some(false));
+    assert_eq!(at_tag("v1.2.0", 5).update_available("v1.2.0"), Some(false));
+    assert_eq!(at_tag("v1.2.0", 0).update_available("v1.1.9"), Some(false));
+    assert_eq!(
+        at_tag("v1.2.0-rc.1", 0).update_available("v1.2.0"),
+        Some(true)
+    );
+    assert_eq!(at_tag("nightly", 0).update_available("v1.2.0"), None);
+    assert_eq!(EMPTY_TESTAMENT.update_available("v1.2.0"), None);
+}
+
+#[test]
+fn at_least() {
+    assert_eq!(at_tag("v1.2.0", 0).at_least("v1.2.0"), Some(true));
+    assert_eq!(at_tag("v1.2.0", 0).at_least("1.1"), Some(true));
+    assert_eq!(at_tag("v1.2.0", 4).at_least("v1.2.0"), Some(true));
+    assert_eq!(at_tag("v1.2.0", 0).at_least("v1.10.0"), Some(false));
+    assert_eq!(at_tag("v1.2.0-rc.1", 0).at_least("v1.2.0"), Some(false));
+    assert_eq!(at_tag("v1.2.0", 0).at_least("migration-7"), None);
+    assert_eq!(EMPTY_TESTAMENT.at_least("v1.0.0"), None);
+}
+
+#[cfg(feature = "semver")]
+#[test]
+fn version_semver() {
+    let version = |testament: GitTestament| testament.version_semver("1.2.3").unwrap().to_string();
+    assert_eq!(version(at_tag("v1.2.3", 0)), "1.2.3+g763aa159d");
+    assert_eq!(version(at_tag("v1.2.0", 4)), "1.2.3+g763aa159d.d4");
+    let dirty = GitTestament {
+        modifications: &[GitModification::Modified(b"src/main.rs")],
+        ..at_tag("v1.2.0", 4)
+    };
+    assert_eq!(version(dirty), "1.2.3+g763aa159d.d4.dirty");
+    assert_eq!(version(EMPTY_TESTAMENT), "1.2.3");
+    assert_eq!(
+        at_tag("v1.2.0", 0)
+            .version_semver("1.2.3-rc.1+linux")
+            .unwrap()
+            .to_string(),
+        "1.2.3-rc.1+linux.g763aa159d"
+    );
+    assert!(at_tag("v1.2.0", 0).version_semver("one").is_err());
+}